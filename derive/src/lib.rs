@@ -0,0 +1,458 @@
+//! `#[derive(FromJson, ToJson)]` for parser-json-rs, enabled there with its
+//! `derive` feature
+//!
+//! Structs with named fields map to objects, newtypes to what they wrap,
+//! tuple structs to arrays and unit structs to null. Enums are externally
+//! tagged: unit variants are their name as a string, other variants an
+//! object with their name as the only key.
+//!
+//! Fields and variants can be `#[json(rename = "name")]`d, or all of them at
+//! once with `#[json(rename_all = "camelCase")]` on the type (also
+//! `snake_case`, `kebab-case`, `PascalCase`, `SCREAMING_SNAKE_CASE`,
+//! `lowercase` and `UPPERCASE`). Named fields can be `#[json(default)]`, read
+//! as `Default::default()` when missing or null, or `#[json(skip)]`, never
+//! written and always read as the default.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Fields, GenericParam, Ident, LitStr, Result,
+    parse_macro_input, parse_quote,
+};
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_json(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_json(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Spelling of the names given by `rename_all`
+#[derive(Clone, Copy)]
+enum Case {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+}
+
+impl Case {
+    fn parse(name: &LitStr) -> Result<Self> {
+        Ok(match name.value().as_str() {
+            "lowercase" => Case::Lower,
+            "UPPERCASE" => Case::Upper,
+            "PascalCase" => Case::Pascal,
+            "camelCase" => Case::Camel,
+            "snake_case" => Case::Snake,
+            "SCREAMING_SNAKE_CASE" => Case::ScreamingSnake,
+            "kebab-case" => Case::Kebab,
+            _ => return Err(Error::new(name.span(), "unknown case for `rename_all`")),
+        })
+    }
+
+    /// `ident` split into lowercase words and joined in this case
+    fn apply(self, ident: &str) -> String {
+        let mut words: Vec<String> = Vec::new();
+        for part in ident.split('_').filter(|part| !part.is_empty()) {
+            let mut word = String::new();
+            let mut previous_lower = false;
+            for c in part.chars() {
+                if c.is_uppercase() && previous_lower {
+                    words.push(std::mem::take(&mut word));
+                }
+                previous_lower = c.is_lowercase() || c.is_ascii_digit();
+                word.extend(c.to_lowercase());
+            }
+            words.push(word);
+        }
+
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        };
+        match self {
+            Case::Lower => words.concat(),
+            Case::Upper => words.concat().to_uppercase(),
+            Case::Pascal => words
+                .iter()
+                .map(capitalize)
+                .collect::<Vec<String>>()
+                .concat(),
+            Case::Camel => {
+                let rest: String = words.iter().skip(1).map(capitalize).collect();
+                words.first().cloned().unwrap_or_default() + &rest
+            }
+            Case::Snake => words.join("_"),
+            Case::ScreamingSnake => words.join("_").to_uppercase(),
+            Case::Kebab => words.join("-"),
+        }
+    }
+}
+
+/// `#[json(...)]` on the type
+#[derive(Default)]
+struct TypeAttrs {
+    rename_all: Option<Case>,
+}
+
+/// `#[json(...)]` on a field or variant
+#[derive(Default)]
+struct MemberAttrs {
+    rename: Option<String>,
+    default: bool,
+    skip: bool,
+}
+
+fn type_attrs(attrs: &[Attribute]) -> Result<TypeAttrs> {
+    let mut parsed = TypeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                parsed.rename_all = Some(Case::parse(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename_all`"))
+            }
+        })?;
+    }
+    Ok(parsed)
+}
+
+fn member_attrs(attrs: &[Attribute]) -> Result<MemberAttrs> {
+    let mut parsed = MemberAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else {
+                return Err(meta.error("expected `rename`, `default` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+/// The JSON name of a field or variant
+fn name(ident: &Ident, attrs: &MemberAttrs, case: Option<Case>) -> String {
+    let ident = ident.to_string();
+    let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+    match (&attrs.rename, case) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(case)) => case.apply(ident),
+        (None, None) => ident.to_owned(),
+    }
+}
+
+/// Reject attributes that only make sense on named fields
+fn check_unnamed(fields: &Fields) -> Result<()> {
+    for field in fields.iter().filter(|field| field.ident.is_none()) {
+        if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("json")) {
+            return Err(Error::new_spanned(
+                attr,
+                "`json` attributes are only supported on named fields",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `input` with every type parameter bounded by `bound`
+fn bounded(input: &mut DeriveInput, bound: TokenStream2) {
+    let params: Vec<Ident> = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+}
+
+fn from_json(mut input: DeriveInput) -> Result<TokenStream2> {
+    bounded(&mut input, quote!(::parser_json_rs::FromJson));
+    let case = type_attrs(&input.attrs)?.rename_all;
+
+    let body = match &input.data {
+        Data::Struct(data) => fields_from_json(&data.fields, quote!(Self), case)?,
+        Data::Enum(data) => {
+            let type_name = input.ident.to_string();
+            let expected = LitStr::new(
+                &format!("a variant of {type_name}"),
+                proc_macro2::Span::call_site(),
+            );
+            let mut unit = Vec::new();
+            let mut tagged = Vec::new();
+            for variant in &data.variants {
+                check_unnamed(&variant.fields)?;
+                let attrs = member_attrs(&variant.attrs)?;
+                if attrs.default || attrs.skip {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "only `rename` is supported on variants",
+                    ));
+                }
+                let name = name(&variant.ident, &attrs, case);
+                let ident = &variant.ident;
+                if let Fields::Unit = variant.fields {
+                    unit.push(quote!(#name => Ok(Self::#ident),));
+                } else {
+                    let body = fields_from_json(&variant.fields, quote!(Self::#ident), case)?;
+                    tagged.push(quote! {
+                        #name => (|value: &::parser_json_rs::Value| -> ::core::result::Result<Self, ::parser_json_rs::JsonError> {
+                            #body
+                        })(inner)
+                        .map_err(|err| ::parser_json_rs::__private::within(err, #name)),
+                    });
+                }
+            }
+            quote! {
+                let unknown = |found: &str| ::parser_json_rs::JsonError::from(
+                    ::parser_json_rs::ConversionError {
+                        path: ::parser_json_rs::__private::String::new(),
+                        expected: #expected,
+                        found: ::parser_json_rs::__private::format!("{found:?}"),
+                    }
+                );
+                match value {
+                    ::parser_json_rs::Value::String(s) => match s.as_str() {
+                        #(#unit)*
+                        s => Err(unknown(s)),
+                    },
+                    ::parser_json_rs::Value::Object(members) if members.len() == 1 => {
+                        let (key, inner) = members.iter().next().expect("checked the length");
+                        match key.as_str() {
+                            #(#tagged)*
+                            key => Err(unknown(key)),
+                        }
+                    }
+                    value => Err(::parser_json_rs::ConversionError::new(#expected, value).into()),
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions can't be derived",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::parser_json_rs::FromJson for #ident #type_generics #where_clause {
+            fn from_json(
+                value: &::parser_json_rs::Value,
+            ) -> ::core::result::Result<Self, ::parser_json_rs::JsonError> {
+                #body
+            }
+        }
+    })
+}
+
+/// Code reading `value` into the struct or variant `constructor`
+fn fields_from_json(
+    fields: &Fields,
+    constructor: TokenStream2,
+    case: Option<Case>,
+) -> Result<TokenStream2> {
+    check_unnamed(fields)?;
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut inits = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().expect("named fields have names");
+                let attrs = member_attrs(&field.attrs)?;
+                let name = name(ident, &attrs, case);
+                inits.push(if attrs.skip {
+                    quote!(#ident: ::core::default::Default::default())
+                } else if attrs.default {
+                    quote! {
+                        #ident: match value.get(#name) {
+                            Some(member) if !member.is_null() => value.field(#name)?,
+                            _ => ::core::default::Default::default(),
+                        }
+                    }
+                } else {
+                    quote!(#ident: value.field(#name)?)
+                });
+            }
+            quote! {
+                if !value.is_object() {
+                    return Err(::parser_json_rs::ConversionError::new("an object", value).into());
+                }
+                Ok(#constructor { #(#inits),* })
+            }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            quote!(Ok(#constructor(::parser_json_rs::FromJson::from_json(value)?)))
+        }
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len();
+            let expected = LitStr::new(
+                &format!("an array of {len} elements"),
+                proc_macro2::Span::call_site(),
+            );
+            let elements = (0..len).map(|i| {
+                let segment = i.to_string();
+                quote! {
+                    ::parser_json_rs::FromJson::from_json(&elements[#i])
+                        .map_err(|err| ::parser_json_rs::__private::within(err, #segment))?
+                }
+            });
+            quote! {
+                let elements = match value {
+                    ::parser_json_rs::Value::Array(elements) if elements.len() == #len => elements,
+                    value => {
+                        return Err(::parser_json_rs::ConversionError::new(#expected, value).into());
+                    }
+                };
+                Ok(#constructor(#(#elements),*))
+            }
+        }
+        Fields::Unit => quote! {
+            match value {
+                ::parser_json_rs::Value::Null => Ok(#constructor),
+                value => Err(::parser_json_rs::ConversionError::new("null", value).into()),
+            }
+        },
+    })
+}
+
+fn to_json(mut input: DeriveInput) -> Result<TokenStream2> {
+    bounded(&mut input, quote!(::parser_json_rs::ToJson));
+    let case = type_attrs(&input.attrs)?.rename_all;
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, value) = fields_to_json(&data.fields, case)?;
+            quote! {
+                let Self #pattern = self;
+                #value
+            }
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                check_unnamed(&variant.fields)?;
+                let attrs = member_attrs(&variant.attrs)?;
+                let name = name(&variant.ident, &attrs, case);
+                let ident = &variant.ident;
+                let (pattern, value) = fields_to_json(&variant.fields, case)?;
+                arms.push(match variant.fields {
+                    Fields::Unit => quote! {
+                        Self::#ident => ::parser_json_rs::Value::String(::core::convert::From::from(#name)),
+                    },
+                    _ => quote! {
+                        Self::#ident #pattern => {
+                            let mut __json_members = ::parser_json_rs::Map::default();
+                            __json_members.insert(::parser_json_rs::__private::String::from(#name), { #value });
+                            ::parser_json_rs::Value::Object(__json_members)
+                        }
+                    },
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions can't be derived",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::parser_json_rs::ToJson for #ident #type_generics #where_clause {
+            fn to_json(&self) -> ::parser_json_rs::Value {
+                #body
+            }
+        }
+    })
+}
+
+/// A pattern binding the fields of a struct or variant by reference, and the
+/// code writing them out as a value
+///
+/// The fields are bound, and the locals named, with a `__` prefix so neither
+/// clashes with a field called `members` or anything else.
+fn fields_to_json(fields: &Fields, case: Option<Case>) -> Result<(TokenStream2, TokenStream2)> {
+    Ok(match fields {
+        Fields::Named(named) => {
+            let mut bindings = Vec::new();
+            let mut inserts = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().expect("named fields have names");
+                let attrs = member_attrs(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let name = name(ident, &attrs, case);
+                let binding = format_ident!("__field_{}", ident.unraw());
+                bindings.push(quote!(#ident: #binding));
+                inserts.push(quote! {
+                    __json_members.insert(
+                        ::parser_json_rs::__private::String::from(#name),
+                        ::parser_json_rs::ToJson::to_json(#binding),
+                    );
+                });
+            }
+            (
+                quote!({ #(#bindings,)* .. }),
+                quote! {
+                    let mut __json_members = ::parser_json_rs::Map::default();
+                    #(#inserts)*
+                    ::parser_json_rs::Value::Object(__json_members)
+                },
+            )
+        }
+        Fields::Unnamed(unnamed) => {
+            let bindings: Vec<Ident> = (0..unnamed.unnamed.len())
+                .map(|i| format_ident!("__field{i}"))
+                .collect();
+            let value = match bindings.as_slice() {
+                [only] => quote!(::parser_json_rs::ToJson::to_json(#only)),
+                bindings => quote! {
+                    ::parser_json_rs::Value::Array(::parser_json_rs::__private::vec![
+                        #(::parser_json_rs::ToJson::to_json(#bindings)),*
+                    ])
+                },
+            };
+            (quote!((#(#bindings),*)), value)
+        }
+        Fields::Unit => (quote!(), quote!(::parser_json_rs::Value::Null)),
+    })
+}
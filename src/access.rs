@@ -0,0 +1,435 @@
+// Reading and navigating the contents of a `Value` without matching on it
+
+use core::ops;
+
+use super::Map;
+use super::Value;
+use super::map::Entry;
+use super::prelude::*;
+
+/// Something a [`Value`] can be indexed by: a `str` key for objects or a
+/// `usize` position for arrays
+pub trait ValueIndex: private::Sealed {
+    /// The member or element, `None` if `value` doesn't have it
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value>;
+
+    /// The member or element for assignment, turning a null `value` into an
+    /// object and adding missing members to objects, panics when `value`
+    /// can't have it
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value;
+}
+
+mod private {
+    use alloc::string::String;
+
+    pub trait Sealed {}
+
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl Sealed for usize {}
+    impl<T: Sealed + ?Sized> Sealed for &T {}
+}
+
+impl ValueIndex for str {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_object()?.get(self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        value.as_object_mut()?.get_mut(self)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        if value.is_null() {
+            *value = Value::Object(Map::default());
+        }
+        match value {
+            Value::Object(map) => map.entry(self.to_owned()).or_insert(Value::Null),
+            value => panic!("cannot index {} with key {self:?}", value.kind()),
+        }
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_str().index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        self.as_str().index_or_insert(value)
+    }
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_array()?.get(*self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        value.as_array_mut()?.get_mut(*self)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        match value {
+            Value::Array(values) => {
+                let len = values.len();
+                match values.get_mut(*self) {
+                    Some(element) => element,
+                    None => panic!("index {self} out of bounds for array of length {len}"),
+                }
+            }
+            value => panic!("cannot index {} with position {self}", value.kind()),
+        }
+    }
+}
+
+impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut Value) -> &'v mut Value {
+        (**self).index_or_insert(value)
+    }
+}
+
+/// `value["key"]` or `value[0]`, null when there is no such member or element
+impl<I: ValueIndex> ops::Index<I> for Value {
+    type Output = Value;
+
+    fn index(&self, index: I) -> &Value {
+        static NULL: Value = Value::Null;
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
+
+/// `value["key"] = ...` adds the member if missing, and turns a null `value`
+/// into an object first, so nested objects can be built by assignment.
+/// Panics when indexing anything but objects by key, or arrays by a
+/// position they have.
+impl<I: ValueIndex> ops::IndexMut<I> for Value {
+    fn index_mut(&mut self, index: I) -> &mut Value {
+        index.index_or_insert(self)
+    }
+}
+
+/// Where a member sits in its container, see [`Value::retain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Member<'a> {
+    /// key of an object member
+    Key(&'a str),
+    /// position of an array element
+    Index(usize),
+}
+
+impl Value {
+    /// Name of the kind of value, for messages
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Boolean(_) => "a boolean",
+            Value::String(_) => "a string",
+            Value::Number(_) => "a number",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    /// The member of an object by key or the element of an array by
+    /// position, `None` if there is no such member or element
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    pub fn get_mut<I: ValueIndex>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// The member `key` of an object for in-place manipulation, like
+    /// [`Map::entry`]. A null value is turned into an empty object first;
+    /// panics for anything else.
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_, String, Value> {
+        if self.is_null() {
+            *self = Value::Object(Map::default());
+        }
+        match self {
+            Value::Object(map) => map.entry(key.into()),
+            value => panic!("cannot take an entry of {}", value.kind()),
+        }
+    }
+
+    /// Keep only the object members or array elements for which `keep`
+    /// returns true, given their key or original position. Other values are
+    /// left alone.
+    pub fn retain<F: FnMut(Member<'_>, &Value) -> bool>(&mut self, mut keep: F) {
+        match self {
+            Value::Object(map) => map.retain(|key, value| keep(Member::Key(key), value)),
+            Value::Array(values) => {
+                let mut i = 0;
+                values.retain(|value| {
+                    i += 1;
+                    keep(Member::Index(i - 1), value)
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Move the value out, leaving null in its place
+    pub fn take(&mut self) -> Value {
+        core::mem::replace(self, Value::Null)
+    }
+
+    /// Put `value` in place of this one, returning the old value
+    pub fn replace(&mut self, value: Value) -> Value {
+        core::mem::replace(self, value)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, Value::Boolean(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, Value::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number as an `i64` if it is a whole number within its range
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            // the bounds are exact powers of two, so comparing as f64 is exact
+            Value::Number(n)
+                if n % 1.0 == 0.0 && *n >= i64::MIN as f64 && *n < -(i64::MIN as f64) =>
+            {
+                Some(*n as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// The number as a `u64` if it is a whole number within its range
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            // `u64::MAX as f64` rounds up to 2^64
+            Value::Number(n) if n % 1.0 == 0.0 && *n >= 0.0 && *n < u64::MAX as f64 => {
+                Some(*n as u64)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<String, Value>> {
+        match self {
+            Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, Value>> {
+        match self {
+            Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Member;
+    use crate::prelude::*;
+    use crate::{Value, parse};
+
+    #[test]
+    fn accessors_match_variants() {
+        let value = parse(r#"{"s": "x", "n": 2, "b": true, "z": null, "a": [1]}"#).unwrap();
+        let object = value.as_object().unwrap();
+
+        assert_eq!(object["s"].as_str(), Some("x"));
+        assert_eq!(object["n"].as_f64(), Some(2.0));
+        assert_eq!(object["b"].as_bool(), Some(true));
+        assert!(object["z"].is_null());
+        assert_eq!(object["a"].as_array(), Some(&vec![Value::Number(1.0)]));
+        assert_eq!(object["s"].as_f64(), None);
+        assert_eq!(object["n"].as_str(), None);
+        assert!(value.is_object() && !value.is_array());
+    }
+
+    #[test]
+    fn integer_accessors_check_range() {
+        assert_eq!(Value::Number(-3.0).as_i64(), Some(-3));
+        assert_eq!(Value::Number(-3.0).as_u64(), None);
+        assert_eq!(Value::Number(2.5).as_i64(), None);
+        assert_eq!(Value::Number(1e19).as_i64(), None);
+        assert_eq!(
+            Value::Number(1e19).as_u64(),
+            Some(10_000_000_000_000_000_000)
+        );
+        assert_eq!(Value::Number(f64::NAN).as_u64(), None);
+    }
+
+    #[test]
+    fn mutable_accessors() {
+        let mut value = parse("[1]").unwrap();
+        value.as_array_mut().unwrap().push(Value::Null);
+
+        assert_eq!(value, parse("[1, null]").unwrap());
+        assert_eq!(value.as_object_mut(), None);
+    }
+
+    #[test]
+    fn get_by_key_and_position() {
+        let mut value = parse(r#"{"a": [10, {"b": "c"}]}"#).unwrap();
+
+        assert_eq!(
+            value
+                .get("a")
+                .and_then(|a| a.get(1))
+                .and_then(|x| x.get("b")),
+            Some(&Value::String("c".into()))
+        );
+        assert_eq!(value.get(String::from("a")).and_then(|a| a.get(2)), None);
+        assert_eq!(value.get(0), None);
+
+        *value.get_mut("a").unwrap().get_mut(0).unwrap() = Value::Null;
+        assert_eq!(value.pointer("/a/0"), Some(&Value::Null));
+    }
+
+    #[test]
+    fn index_missing_paths_as_null() {
+        let value = parse(r#"{"config": {"port": 80, "hosts": ["a"]}}"#).unwrap();
+
+        assert_eq!(value["config"]["port"], Value::Number(80.0));
+        assert_eq!(value["config"]["hosts"][0], Value::String("a".into()));
+        assert_eq!(value["config"]["missing"]["deeper"][3], Value::Null);
+        assert_eq!(value[0], Value::Null);
+    }
+
+    #[test]
+    fn index_mut_creates_objects() {
+        let mut value = Value::Null;
+        value["server"]["tls"]["port"] = Value::Number(443.0);
+        value["server"]["name"] = Value::String("x".into());
+
+        assert_eq!(
+            value,
+            parse(r#"{"server": {"tls": {"port": 443}, "name": "x"}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "index 1 out of bounds for array of length 1")]
+    fn index_mut_past_array_end_panics() {
+        let mut value = parse("[1]").unwrap();
+        value[1] = Value::Null;
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index a number with key \"a\"")]
+    fn index_mut_into_scalar_panics() {
+        let mut value = Value::Number(1.0);
+        value["a"] = Value::Null;
+    }
+
+    #[test]
+    fn entry_counts_in_place() {
+        let mut counts = Value::Null;
+        for word in ["a", "b", "a"] {
+            let count = counts.entry(word).or_insert(Value::Number(0.0));
+            *count = Value::Number(count.as_f64().unwrap() + 1.0);
+        }
+        counts.entry("c").or_insert(Value::Null);
+
+        assert_eq!(counts, parse(r#"{"a": 2, "b": 1, "c": null}"#).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot take an entry of an array")]
+    fn entry_of_array_panics() {
+        parse("[]").unwrap().entry("a");
+    }
+
+    #[test]
+    fn retain_members_and_elements() {
+        let mut value = parse(r#"{"a": 1, "_internal": 2, "b": null}"#).unwrap();
+        value.retain(|member, value| match member {
+            Member::Key(key) => !key.starts_with('_') && !value.is_null(),
+            Member::Index(_) => unreachable!("an object has no positions"),
+        });
+        assert_eq!(value, parse(r#"{"a": 1}"#).unwrap());
+
+        let mut value = parse("[10, 11, 12, 13]").unwrap();
+        value.retain(|member, _| member != Member::Index(1) && member != Member::Index(2));
+        assert_eq!(value, parse("[10, 13]").unwrap());
+    }
+
+    #[test]
+    fn take_and_replace_move_out() {
+        let mut value = parse(r#"{"big": [1, 2, 3], "n": 1}"#).unwrap();
+
+        let big = value["big"].take();
+        assert_eq!(big, parse("[1, 2, 3]").unwrap());
+        let n = value["n"].replace(Value::Boolean(true));
+        assert_eq!(n, Value::Number(1.0));
+        assert_eq!(value, parse(r#"{"big": null, "n": true}"#).unwrap());
+    }
+}
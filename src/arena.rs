@@ -0,0 +1,203 @@
+// A DOM allocated in a bump arena, freed all at once with the arena
+
+use alloc::borrow::Cow;
+use core::cell::RefCell;
+
+use super::Map;
+use super::Value;
+use super::parser::{self, Builder};
+use super::prelude::*;
+use super::{JsonError, ParserOptions};
+
+/// The bump allocator [`parse_in`] allocates values in
+pub use bumpalo::Bump as Arena;
+
+/// Counterpart of [`Value`] whose strings, arrays and objects live in an
+/// [`Arena`]
+///
+/// Nothing is freed while the arena is alive and dropping the arena frees
+/// the whole document at once, without walking it. Members of an object are
+/// kept in no particular order, and keys are interned: the same key in many
+/// objects points to a single copy.
+#[derive(Debug, Clone, Copy)]
+pub enum ArenaValue<'a> {
+    Null,
+    Boolean(bool),
+    String(&'a str),
+    Number(f64),
+    Array(&'a [ArenaValue<'a>]),
+    Object(&'a [(&'a str, ArenaValue<'a>)]),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// The member `key` of an object, `None` for missing members and any
+    /// other value
+    pub fn get(&self, key: &str) -> Option<&'a ArenaValue<'a>> {
+        match *self {
+            ArenaValue::Object(members) => members
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Copy the value out of the arena
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ArenaValue::Null => Value::Null,
+            ArenaValue::Boolean(b) => Value::Boolean(b),
+            ArenaValue::String(s) => Value::String(s.into()),
+            ArenaValue::Number(n) => Value::Number(n),
+            ArenaValue::Array(values) => {
+                Value::Array(values.iter().map(ArenaValue::to_value).collect())
+            }
+            ArenaValue::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(key, value)| ((*key).to_owned(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Objects are equal when they have the same members, in any order
+impl PartialEq for ArenaValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ArenaValue::Null, ArenaValue::Null) => true,
+            (ArenaValue::Boolean(a), ArenaValue::Boolean(b)) => a == b,
+            (ArenaValue::String(a), ArenaValue::String(b)) => a == b,
+            (ArenaValue::Number(a), ArenaValue::Number(b)) => a == b,
+            (ArenaValue::Array(a), ArenaValue::Array(b)) => a == b,
+            (ArenaValue::Object(a), ArenaValue::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(key, value)| other.get(key) == Some(value))
+            }
+            _ => false,
+        }
+    }
+}
+
+struct ArenaBuilder<'a> {
+    arena: &'a Arena,
+    /// every key allocated so far, so repeated keys share one copy
+    keys: RefCell<Map<&'a str, ()>>,
+}
+
+impl<'i, 'a> Builder<'i> for ArenaBuilder<'a> {
+    type Node = ArenaValue<'a>;
+    type Key = &'a str;
+
+    fn null(&self) -> ArenaValue<'a> {
+        ArenaValue::Null
+    }
+
+    fn boolean(&self, b: bool) -> ArenaValue<'a> {
+        ArenaValue::Boolean(b)
+    }
+
+    fn number(&self, n: f64) -> ArenaValue<'a> {
+        ArenaValue::Number(n)
+    }
+
+    fn string(&self, s: Cow<'i, str>) -> ArenaValue<'a> {
+        ArenaValue::String(self.arena.alloc_str(&s))
+    }
+
+    fn key(&self, s: Cow<'i, str>) -> &'a str {
+        let mut keys = self.keys.borrow_mut();
+        if let Some((key, _)) = keys.get_key_value(&*s) {
+            return key;
+        }
+        let key = self.arena.alloc_str(&s);
+        keys.insert(key, ());
+        key
+    }
+
+    fn array(&self, arr: Vec<ArenaValue<'a>>) -> ArenaValue<'a> {
+        ArenaValue::Array(self.arena.alloc_slice_copy(&arr))
+    }
+
+    fn object(&self, map: Map<&'a str, ArenaValue<'a>>) -> ArenaValue<'a> {
+        ArenaValue::Object(self.arena.alloc_slice_fill_iter(map))
+    }
+}
+
+/// Parse a JSON document from text into `arena`
+///
+/// Every string, array and object of the document is allocated in the
+/// arena, which makes parsing many documents cheaper on the allocator and
+/// lets them all be freed with a single [`Arena::reset`]:
+///
+/// ```
+/// use parser_json_rs::{Arena, ArenaValue, parse_in};
+///
+/// let mut arena = Arena::new();
+/// for input in [r#"{"id": 1}"#, r#"{"id": 2}"#] {
+///     let value = parse_in(&arena, input).unwrap();
+///     assert!(matches!(value.get("id"), Some(ArenaValue::Number(_))));
+/// }
+/// arena.reset();
+/// ```
+pub fn parse_in<'a>(arena: &'a Arena, input: &str) -> Result<ArenaValue<'a>, JsonError> {
+    let builder = ArenaBuilder {
+        arena,
+        keys: RefCell::new(Map::default()),
+    };
+    let options = ParserOptions::default();
+    parser::parse_with(&builder, options.lexer(input)?, &options.limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, ArenaValue, parse_in};
+    use crate::{JsonError, TokenParseError, parse};
+
+    #[test]
+    fn parse_in_matches_parse() {
+        let input = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e", "f": true}"#;
+        let arena = Arena::new();
+        let value = parse_in(&arena, input).unwrap();
+
+        assert_eq!(value.to_value(), parse(input).unwrap());
+        assert_eq!(value.get("c"), Some(&ArenaValue::String("d\"e")));
+        assert_eq!(value, parse_in(&arena, input).unwrap());
+    }
+
+    #[test]
+    fn parse_in_keeps_the_last_duplicate_key() {
+        let arena = Arena::new();
+        let value = parse_in(&arena, r#"{"a": 1, "a": 2}"#).unwrap();
+
+        assert_eq!(value, ArenaValue::Object(&[("a", ArenaValue::Number(2.0))]));
+    }
+
+    #[test]
+    fn parse_in_interns_keys() {
+        let arena = Arena::new();
+        let value = parse_in(&arena, r#"[{"id": 1}, {"id": 2}, {"i\u0064": 3}]"#).unwrap();
+        let ArenaValue::Array(values) = value else {
+            panic!("expected an array");
+        };
+        let keys: Vec<&str> = values
+            .iter()
+            .map(|value| match value {
+                ArenaValue::Object(members) => members[0].0,
+                _ => panic!("expected an object"),
+            })
+            .collect();
+
+        assert!(keys.iter().all(|key| core::ptr::eq(*key, keys[0])));
+    }
+
+    #[test]
+    fn parse_in_reports_errors() {
+        let arena = Arena::new();
+
+        assert_eq!(
+            parse_in(&arena, "[1,"),
+            Err(JsonError::Parse(TokenParseError::UnexpectedEnd))
+        );
+    }
+}
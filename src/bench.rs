@@ -0,0 +1,208 @@
+// Throughput measurements over caller supplied corpora, for tracking the
+// performance of JSON workloads across crate upgrades
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{JsonError, Lexer, Limits, Value, parse, parser, ser};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator counting every allocation, install it in the benchmark
+/// binary to have allocations reported in each [`Measurement`]
+///
+/// ```
+/// use parser_json_rs::bench::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOC: CountingAllocator = CountingAllocator;
+/// # fn main() {}
+/// ```
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        // SAFETY: forwarded unchanged from the caller
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        // SAFETY: forwarded unchanged from the caller
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        // SAFETY: forwarded unchanged from the caller
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded unchanged from the caller
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+fn count(size: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+}
+
+/// Part of the pipeline being measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// splitting text into tokens
+    Tokenize,
+
+    /// text all the way to a [`Value`](crate::Value), as done by [`parse`](crate::parse)
+    Parse,
+
+    /// a [`Value`](crate::Value) back to compact JSON text
+    Serialize,
+}
+
+/// Outcome of running one [`Stage`] over a corpus
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub stage: Stage,
+
+    /// number of passes made over the whole corpus
+    pub iterations: u32,
+
+    /// input bytes processed across all iterations
+    pub bytes: u64,
+
+    pub elapsed: Duration,
+
+    /// allocations made across all iterations, `None` unless
+    /// [`CountingAllocator`] is the global allocator
+    pub allocations: Option<u64>,
+
+    /// bytes requested by those allocations
+    pub allocated_bytes: Option<u64>,
+}
+
+impl Measurement {
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Tokenize every document of `corpus`, `iterations` times over
+pub fn measure_tokenize(corpus: &[&str], iterations: u32) -> Result<Measurement, JsonError> {
+    measure(Stage::Tokenize, corpus, iterations, |_, doc| {
+        for token in Lexer::new(doc) {
+            black_box(token?);
+        }
+        Ok(())
+    })
+}
+
+/// Parse every document of `corpus` into a `Value`, `iterations` times over
+pub fn measure_parse(corpus: &[&str], iterations: u32) -> Result<Measurement, JsonError> {
+    measure(Stage::Parse, corpus, iterations, |_, doc| {
+        black_box(parser::parse::<Value, _, _>(
+            Lexer::new(doc),
+            &Limits::default(),
+        )?);
+        Ok(())
+    })
+}
+
+/// Serialize every document of `corpus`, parsed beforehand, `iterations`
+/// times over. Throughput is counted in bytes of the original documents.
+pub fn measure_serialize(corpus: &[&str], iterations: u32) -> Result<Measurement, JsonError> {
+    let values = corpus
+        .iter()
+        .map(|doc| parse(doc))
+        .collect::<Result<Vec<Value>, JsonError>>()?;
+    let mut out = String::new();
+
+    measure(Stage::Serialize, corpus, iterations, |i, _| {
+        out.clear();
+        ser::write_value(&mut out, &values[i]).expect("writing to a String never fails");
+        black_box(&out);
+        Ok(())
+    })
+}
+
+/// Run every [`Stage`] over `corpus`
+pub fn measure_all(corpus: &[&str], iterations: u32) -> Result<Vec<Measurement>, JsonError> {
+    Ok(vec![
+        measure_tokenize(corpus, iterations)?,
+        measure_parse(corpus, iterations)?,
+        measure_serialize(corpus, iterations)?,
+    ])
+}
+
+fn measure(
+    stage: Stage,
+    corpus: &[&str],
+    iterations: u32,
+    mut run: impl FnMut(usize, &str) -> Result<(), JsonError>,
+) -> Result<Measurement, JsonError> {
+    let installed = INSTALLED.load(Ordering::Relaxed);
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        for (i, doc) in corpus.iter().enumerate() {
+            run(i, black_box(doc))?;
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let corpus_bytes: u64 = corpus.iter().map(|doc| doc.len() as u64).sum();
+
+    Ok(Measurement {
+        stage,
+        iterations,
+        bytes: corpus_bytes * u64::from(iterations),
+        elapsed,
+        allocations: installed.then(|| ALLOCATIONS.load(Ordering::Relaxed) - allocations_before),
+        allocated_bytes: installed.then(|| ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes_before),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stage, measure_all, measure_parse};
+
+    #[test]
+    fn measure_counts_corpus_bytes() {
+        let corpus = [r#"{"a": [1, 2, 3]}"#, "[true, null]"];
+        let measurement = measure_parse(&corpus, 3).unwrap();
+
+        assert_eq!(measurement.stage, Stage::Parse);
+        assert_eq!(measurement.bytes, 3 * 28);
+        // the test binary doesn't install the counting allocator
+        assert_eq!(measurement.allocations, None);
+    }
+
+    #[test]
+    fn measure_all_stages() {
+        let stages: Vec<Stage> = measure_all(&["[]"], 1)
+            .unwrap()
+            .iter()
+            .map(|m| m.stage)
+            .collect();
+
+        assert_eq!(
+            stages,
+            vec![Stage::Tokenize, Stage::Parse, Stage::Serialize]
+        );
+    }
+
+    #[test]
+    fn measure_reports_invalid_documents() {
+        assert!(measure_parse(&["[1,"], 1).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+// `pjson diff`: comparing two documents by what they hold
+
+use std::process::ExitCode;
+
+use parser_json_rs::{
+    NonFinite, Parser, PrettyOptions, Value, WriteError, WriteOptions, diff, patch,
+};
+
+use super::{Args, DIALECT_HELP, INVALID, load};
+
+const HELP: &str = "usage: pjson diff [options] <old> <new>
+
+Compare two documents by their values, so formatting, escaping and the order
+of object members make no difference, and print one line per change. Exits
+with 0 if they are the same, 1 if they differ and 2 if either can't be read.
+
+options:
+  --patch         print an RFC 6902 JSON Patch turning <old> into <new>
+  --compact, -c   print the patch on one line";
+
+pub fn run(mut args: Args) -> Result<ExitCode, String> {
+    if args.flag("--help") {
+        println!("{HELP}\n{DIALECT_HELP}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let as_patch = args.flag("--patch");
+    let compact = args.flag("--compact") | args.flag("-c");
+    let mut parser = Parser::builder().syntax(args.syntax()?).build();
+    let files = args.files()?;
+    let [old, new] = files.as_slice() else {
+        return Err(String::from("`diff` compares two files"));
+    };
+    let (old, new) = match (load(&mut parser, old), load(&mut parser, new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(failure), _) | (_, Err(failure)) => return Ok(ExitCode::from(failure)),
+    };
+    if old == new {
+        return Ok(ExitCode::SUCCESS);
+    }
+    let report = report(&old, &new, as_patch, compact).map_err(|err| err.to_string())?;
+    print!("{report}");
+    Ok(ExitCode::from(INVALID))
+}
+
+/// What differs between `old` and `new`, as a change list or a patch,
+/// unless the patch holds a number JSON can't
+fn report(old: &Value, new: &Value, as_patch: bool, compact: bool) -> Result<String, WriteError> {
+    if !as_patch {
+        return Ok(diff(old, new)
+            .iter()
+            .map(|change| format!("{change}\n"))
+            .collect());
+    }
+    let patch = patch::diff(old, new).to_value();
+    // sorted, so `op` leads each operation and the output is the same from
+    // run to run, and refusing NaN and the infinities rather than writing
+    // them as null
+    let options = WriteOptions {
+        non_finite: NonFinite::Error,
+        sort_keys: true,
+        ..WriteOptions::default()
+    };
+    let compact_text = patch.to_string_with(options)?;
+    let mut text = if compact {
+        compact_text
+    } else {
+        patch.to_string_pretty_with(PrettyOptions {
+            sort_keys: true,
+            ..PrettyOptions::default()
+        })
+    };
+    text.push('\n');
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::report;
+    use parser_json_rs::{WriteError, json};
+
+    #[test]
+    fn report_changes_or_patch() {
+        let old = json!({"a": 1, "b": [true], "c": "x"});
+        let new = json!({"c": "x", "a": 2, "b": [true, null]});
+
+        assert_eq!(
+            report(&old, &new, false, false).unwrap(),
+            "changed /a from 1 to 2\nadded /b/1: null\n"
+        );
+        assert_eq!(
+            report(&old, &new, true, true).unwrap(),
+            "[{\"op\":\"replace\",\"path\":\"/a\",\"value\":2},{\"op\":\"add\",\"path\":\"/b/1\",\"value\":null}]\n"
+        );
+    }
+
+    #[test]
+    fn patch_with_numbers_json_cant_hold_is_refused() {
+        let old = json!([1]);
+        let new = json!([f64::INFINITY]);
+
+        for compact in [false, true] {
+            assert_eq!(
+                report(&old, &new, true, compact),
+                Err(WriteError::NonFinite(f64::INFINITY))
+            );
+        }
+    }
+}
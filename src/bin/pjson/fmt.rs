@@ -0,0 +1,196 @@
+// `pjson fmt`: rewriting documents pretty-printed or minified
+
+use std::fs;
+use std::process::ExitCode;
+
+use parser_json_rs::{
+    NonFinite, Parser, PrettyOptions, SpannedValue, Syntax, WriteError, WriteOptions,
+    parse_spanned_with_comments,
+};
+
+use super::{Args, DIALECT_HELP, FAILED, INVALID, display_name, parse, read};
+
+const HELP: &str = "usage: pjson fmt [options] [files]
+
+Rewrite every file pretty-printed, or minified with `--minify`. Standard
+input is written to standard output. Object members keep their order unless
+`--sort-keys` is given. Comments and the way numbers and strings were spelled
+are not kept, and a file holding a number JSON can't, such as 1e400 or NaN,
+is left as it is and reported.
+
+options:
+  --check         change nothing, exiting with 1 if any file would change
+  --minify        leave out all whitespace
+  --indent N      indent each level by N spaces, 2 by default
+  --width N       keep arrays and objects on one line if they end by column N
+  --sort-keys     order object members by key";
+
+/// How documents are written out
+struct Layout {
+    minify: bool,
+    pretty: PrettyOptions,
+}
+
+pub fn run(mut args: Args) -> Result<ExitCode, String> {
+    if args.flag("--help") {
+        println!("{HELP}\n{DIALECT_HELP}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let check = args.flag("--check");
+    let layout = Layout {
+        minify: args.flag("--minify"),
+        pretty: PrettyOptions {
+            indent: args.number("--indent")?,
+            width: args.number("--width")?,
+            sort_keys: args.flag("--sort-keys"),
+            ..PrettyOptions::default()
+        },
+    };
+    let syntax = args.syntax()?;
+    let mut parser = Parser::builder().syntax(syntax).build();
+    let mut code = 0;
+    for path in args.files()? {
+        let bytes = match read(&path) {
+            Ok(bytes) => bytes,
+            Err(message) => {
+                eprintln!("pjson: {message}");
+                code = FAILED;
+                continue;
+            }
+        };
+        if let Err(report) = parse(&mut parser, &bytes) {
+            eprint!("{}: {report}", display_name(&path));
+            code = code.max(INVALID);
+            continue;
+        }
+        let formatted = match format(&document(&bytes, syntax), &layout) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}: {err}", display_name(&path));
+                code = code.max(INVALID);
+                continue;
+            }
+        };
+        let unchanged = formatted.as_bytes() == bytes;
+        if check {
+            if !unchanged {
+                eprintln!("{}: not formatted", display_name(&path));
+                code = code.max(INVALID);
+            }
+        } else if path == "-" {
+            print!("{formatted}");
+        } else if !unchanged && let Err(err) = fs::write(&path, formatted) {
+            eprintln!("pjson: {path}: {err}");
+            code = FAILED;
+        }
+    }
+    Ok(ExitCode::from(code))
+}
+
+/// The document in `bytes`, already checked to be valid, with its object
+/// members in the order they were written
+fn document(bytes: &[u8], syntax: Syntax) -> SpannedValue {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|text| parse_spanned_with_comments(text, syntax).ok())
+        .expect("the document was parsed already")
+}
+
+/// The text of a formatted document, ending with a newline, unless it holds
+/// a number JSON can't
+fn format(document: &SpannedValue, layout: &Layout) -> Result<String, WriteError> {
+    // the compact writer is the one refusing NaN and the infinities rather
+    // than writing them as null
+    let compact = document.to_string_with(WriteOptions {
+        non_finite: NonFinite::Error,
+        sort_keys: layout.pretty.sort_keys,
+        ..WriteOptions::default()
+    })?;
+    let mut text = if layout.minify {
+        compact
+    } else {
+        document.to_string_pretty_with(layout.pretty)
+    };
+    text.push('\n');
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, document, format};
+    use parser_json_rs::{PrettyOptions, Syntax, WriteError};
+
+    fn layout(minify: bool, sort_keys: bool) -> Layout {
+        Layout {
+            minify,
+            pretty: PrettyOptions {
+                sort_keys,
+                width: Some(20),
+                ..PrettyOptions::default()
+            },
+        }
+    }
+
+    #[test]
+    fn format_pretty_or_minified() {
+        let doc = document(br#"{"b": [1, 2], "a": {"c": null}}"#, Syntax::default());
+
+        assert_eq!(
+            format(&doc, &layout(false, true)).unwrap(),
+            "{\n  \"a\": {\"c\": null},\n  \"b\": [1, 2]\n}\n"
+        );
+        assert_eq!(
+            format(&doc, &layout(true, true)).unwrap(),
+            "{\"a\":{\"c\":null},\"b\":[1,2]}\n"
+        );
+    }
+
+    #[test]
+    fn members_keep_their_order_unless_sorted() {
+        let doc = document(
+            br#"{"b": 1, "c": {"z": 1, "y": 2}, "a": 3}"#,
+            Syntax::default(),
+        );
+
+        assert_eq!(
+            format(&doc, &layout(true, false)).unwrap(),
+            "{\"b\":1,\"c\":{\"z\":1,\"y\":2},\"a\":3}\n"
+        );
+        assert_eq!(
+            format(&doc, &layout(true, true)).unwrap(),
+            "{\"a\":3,\"b\":1,\"c\":{\"y\":2,\"z\":1}}\n"
+        );
+    }
+
+    #[test]
+    fn formatted_text_formats_to_itself() {
+        let input = br#"{"e": 5, "d": {"z": 1, "y": 2, "x": 3}, "c": 3, "b": 2, "a": 1}"#;
+        for minify in [false, true] {
+            for sort_keys in [false, true] {
+                let layout = layout(minify, sort_keys);
+                let text = format(&document(input, Syntax::default()), &layout).unwrap();
+
+                // what `--check` compares after a run of `fmt`
+                let reformatted = format(&document(text.as_bytes(), Syntax::default()), &layout);
+                assert_eq!(reformatted.unwrap(), text);
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_json_cant_hold_are_refused() {
+        let doc = document(b"[1e400]", Syntax::default());
+        assert_eq!(
+            format(&doc, &layout(false, false)),
+            Err(WriteError::NonFinite(f64::INFINITY))
+        );
+
+        let doc = document(b"[NaN, -Infinity]", Syntax::json5());
+        for minify in [false, true] {
+            assert!(matches!(
+                format(&doc, &layout(minify, false)),
+                Err(WriteError::NonFinite(n)) if n.is_nan()
+            ));
+        }
+    }
+}
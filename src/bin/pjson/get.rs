@@ -0,0 +1,119 @@
+// `pjson get`: picking values out of documents, as a small `jq`
+
+use std::process::ExitCode;
+
+use parser_json_rs::jsonpath::JsonPath;
+use parser_json_rs::{Parser, Value};
+
+use super::{Args, DIALECT_HELP, INVALID, load};
+
+const HELP: &str = "usage: pjson get [options] <query> [files]
+
+Print the values the query picks out of every file, one after the other.
+A query starting with `$` is a JSONPath, as in `$.items[*].name`, and one
+starting with `/` a JSON Pointer, as in `/items/0/name`, the empty pointer
+`''` being the whole document. Exits with 1 if nothing matched in some file.
+
+options:
+  --raw, -r       print strings without quotes or escapes
+  --compact, -c   print each value on one line";
+
+/// A query given on the command line
+enum Query {
+    Pointer(String),
+    Path(JsonPath),
+}
+
+impl Query {
+    fn new(query: String) -> Result<Self, String> {
+        if query.starts_with('$') {
+            JsonPath::compile(&query)
+                .map(Query::Path)
+                .map_err(|err| format!("`{query}`: {err}"))
+        } else if query.is_empty() || query.starts_with('/') {
+            Ok(Query::Pointer(query))
+        } else {
+            Err(format!(
+                "`{query}` is neither a JSON Pointer, starting with `/`, nor a JSONPath, starting with `$`"
+            ))
+        }
+    }
+
+    /// The values picked out of `root`, in document order
+    fn select<'v>(&self, root: &'v Value) -> Vec<&'v Value> {
+        match self {
+            Query::Pointer(pointer) => root.pointer(pointer).into_iter().collect(),
+            Query::Path(path) => path.query(root).into_iter().map(|m| m.value).collect(),
+        }
+    }
+}
+
+pub fn run(mut args: Args) -> Result<ExitCode, String> {
+    if args.flag("--help") {
+        println!("{HELP}\n{DIALECT_HELP}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let raw = args.flag("--raw") | args.flag("-r");
+    let compact = args.flag("--compact") | args.flag("-c");
+    let mut parser = Parser::builder().syntax(args.syntax()?).build();
+    let query = Query::new(args.operand("query")?)?;
+    let mut code = 0;
+    for path in args.files()? {
+        let value = match load(&mut parser, &path) {
+            Ok(value) => value,
+            Err(failure) => {
+                code = code.max(failure);
+                continue;
+            }
+        };
+        let selected = query.select(&value);
+        if selected.is_empty() {
+            code = code.max(INVALID);
+        }
+        for value in selected {
+            println!("{}", show(value, raw, compact));
+        }
+    }
+    Ok(ExitCode::from(code))
+}
+
+/// How a picked value is printed
+fn show(value: &Value, raw: bool, compact: bool) -> String {
+    match value {
+        Value::String(s) if raw => s.to_string(),
+        _ if compact => value.to_string(),
+        _ => value.to_string_pretty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Query, show};
+    use parser_json_rs::json;
+
+    #[test]
+    fn pointers_and_paths_pick_values() {
+        let value = json!({"items": [{"name": "a"}, {"name": "b\nc"}, {"id": 3}]});
+        let select = |query: &str| {
+            Query::new(query.to_string()).map(|query| {
+                query
+                    .select(&value)
+                    .into_iter()
+                    .map(|value| show(value, true, true))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(select("/items/1/name"), Ok(vec![String::from("b\nc")]));
+        assert_eq!(
+            select("$.items[*].name"),
+            Ok(vec![String::from("a"), String::from("b\nc")])
+        );
+        assert_eq!(select("$.items[2]"), Ok(vec![String::from(r#"{"id":3}"#)]));
+        assert_eq!(select("/items/3"), Ok(vec![]));
+        assert!(select("items").is_err());
+        assert!(select("$.items[").is_err());
+        assert_eq!(show(&json!("a"), false, true), r#""a""#);
+        assert_eq!(show(&json!([1]), false, false), "[\n  1\n]");
+    }
+}
@@ -0,0 +1,288 @@
+// `pjson`, checking and reworking JSON documents from the command line
+
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+use parser_json_rs::{Diagnostic, Parser, Syntax, TokenizeError, Value};
+
+mod diff;
+mod fmt;
+mod get;
+mod patch;
+mod validate;
+
+const USAGE: &str = "usage: pjson <command> [options] [files]
+
+commands:
+  validate  check documents are well formed
+  fmt       pretty-print or minify documents in place
+  get       print the values a JSON Pointer or JSONPath picks out
+  diff      compare two documents, ignoring formatting and key order
+  patch     apply a JSON Patch or a merge patch to a document
+
+Files are read from standard input when none or `-` is given.
+Run `pjson <command> --help` for the options of a command.";
+
+/// The options choosing which extensions to JSON are accepted
+const DIALECT_HELP: &str = "  --strict        RFC 8259 JSON only, refusing a byte order mark too
+  --jsonc         allow comments
+  --json5         allow everything JSON5 does, also `--lenient`";
+
+/// Exit code for input that isn't as it should be
+const INVALID: u8 = 1;
+/// Exit code for a command that couldn't run, wrong arguments or unreadable
+/// files
+const FAILED: u8 = 2;
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(FAILED);
+    }
+    let command = args.remove(0);
+    let args = Args(args);
+    let result = match command.as_str() {
+        "validate" => validate::run(args),
+        "fmt" => fmt::run(args),
+        "get" => get::run(args),
+        "diff" => diff::run(args),
+        "patch" => patch::run(args),
+        "-h" | "--help" => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(format!("unknown command `{command}`, see `pjson --help`")),
+    };
+    result.unwrap_or_else(|message| {
+        eprintln!("pjson: {message}");
+        ExitCode::from(FAILED)
+    })
+}
+
+/// The arguments after the command, taken out as the command asks for them
+///
+/// Everything after a `--` is a file name, however it looks.
+struct Args(Vec<String>);
+
+impl Args {
+    /// How many arguments come before a `--`
+    fn options_end(&self) -> usize {
+        self.0
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(self.0.len())
+    }
+
+    /// Whether the flag `name` was given, taking it out
+    fn flag(&mut self, name: &str) -> bool {
+        let end = self.options_end();
+        match self.0[..end].iter().position(|arg| arg == name) {
+            Some(at) => {
+                self.0.remove(at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The value of the option `name`, given as `name value` or
+    /// `name=value`, taking it out
+    fn option(&mut self, name: &str) -> Result<Option<String>, String> {
+        let end = self.options_end();
+        for at in 0..end {
+            if self.0[at] == name {
+                if at + 1 == end {
+                    return Err(format!("`{name}` needs a value"));
+                }
+                self.0.remove(at);
+                return Ok(Some(self.0.remove(at)));
+            }
+            if let Some(value) = self.0[at]
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                let value = value.to_string();
+                self.0.remove(at);
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The value of the option `name` read as a number
+    fn number(&mut self, name: &str) -> Result<Option<usize>, String> {
+        self.option(name)?
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("`{name}` needs a number, not `{value}`"))
+            })
+            .transpose()
+    }
+
+    /// The first argument that isn't an option, taking it out, once the
+    /// options have been
+    fn operand(&mut self, what: &str) -> Result<String, String> {
+        let end = self.options_end();
+        let at = self.0[..end]
+            .iter()
+            .position(|arg| !arg.starts_with('-') || arg == "-")
+            .or((end + 1 < self.0.len()).then_some(end + 1))
+            .ok_or_else(|| format!("missing {what}"))?;
+        Ok(self.0.remove(at))
+    }
+
+    /// Which extensions to JSON the dialect options ask for
+    fn syntax(&mut self) -> Result<Syntax, String> {
+        let strict = self.flag("--strict");
+        let jsonc = self.flag("--jsonc");
+        let json5 = self.flag("--json5") | self.flag("--lenient");
+        match (strict, jsonc, json5) {
+            (false, false, false) => Ok(Syntax::default()),
+            (true, false, false) => Ok(Syntax {
+                reject_bom: true,
+                ..Syntax::default()
+            }),
+            (false, true, false) => Ok(Syntax::jsonc()),
+            (false, false, true) => Ok(Syntax::json5()),
+            _ => Err(String::from(
+                "`--strict`, `--jsonc` and `--json5` can't be combined",
+            )),
+        }
+    }
+
+    /// The arguments left, which are file names, standard input if there
+    /// are none
+    fn files(mut self) -> Result<Vec<String>, String> {
+        let end = self.options_end();
+        if let Some(unknown) = self.0[..end]
+            .iter()
+            .find(|arg| arg.starts_with('-') && *arg != "-")
+        {
+            return Err(format!("unknown option `{unknown}`"));
+        }
+        if end < self.0.len() {
+            self.0.remove(end);
+        }
+        if self.0.is_empty() {
+            self.0.push(String::from("-"));
+        }
+        Ok(self.0)
+    }
+}
+
+/// How a file is called in messages
+fn display_name(path: &str) -> &str {
+    if path == "-" { "<stdin>" } else { path }
+}
+
+/// The contents of the file at `path`, or of standard input for `-`
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    let read = if path == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+    } else {
+        fs::read(path)
+    };
+    read.map_err(|err| format!("{}: {err}", display_name(path)))
+}
+
+/// Parse a document, or render what is wrong with it against its text
+fn parse(parser: &mut Parser, bytes: &[u8]) -> Result<Value, String> {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => {
+            let at = err.valid_up_to();
+            let diagnostic = Diagnostic {
+                error: TokenizeError::InvalidUtf8(at).into(),
+                span: at..at + 1,
+            };
+            return Err(diagnostic.render(&String::from_utf8_lossy(bytes)));
+        }
+    };
+    parser
+        .parse_with_diagnostic(text)
+        .map_err(|diagnostic| diagnostic.render(text))
+}
+
+/// Read and parse the document at `path`, printing what went wrong and
+/// giving the exit code for it if anything did
+fn load(parser: &mut Parser, path: &str) -> Result<Value, u8> {
+    let bytes = read(path).map_err(|message| {
+        eprintln!("pjson: {message}");
+        FAILED
+    })?;
+    parse(parser, &bytes).map_err(|report| {
+        eprint!("{}: {report}", display_name(path));
+        INVALID
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Args, parse};
+    use parser_json_rs::{Parser, Syntax, json};
+
+    fn args(args: &[&str]) -> Args {
+        Args(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn arguments_are_taken_out_as_asked_for() {
+        let mut given = args(&[
+            "--indent=4",
+            "a.json",
+            "--json5",
+            "--width",
+            "80",
+            "--",
+            "--b",
+        ]);
+
+        assert_eq!(given.number("--indent"), Ok(Some(4)));
+        assert_eq!(given.option("--width"), Ok(Some(String::from("80"))));
+        assert_eq!(given.option("--width"), Ok(None));
+        assert_eq!(given.syntax(), Ok(Syntax::json5()));
+        assert_eq!(
+            given.files(),
+            Ok(vec![String::from("a.json"), String::from("--b")])
+        );
+
+        assert_eq!(args(&[]).files(), Ok(vec![String::from("-")]));
+        let mut given = args(&["--raw", "/a", "b.json"]);
+        assert_eq!(given.operand("query"), Ok(String::from("/a")));
+        assert_eq!(given.files(), Err(String::from("unknown option `--raw`")));
+        let mut given = args(&["--", "-a", "b.json"]);
+        assert_eq!(given.operand("query"), Ok(String::from("-a")));
+        assert_eq!(given.files(), Ok(vec![String::from("b.json")]));
+        assert!(args(&["--"]).operand("query").is_err());
+        assert!(args(&["--jsonc", "--lenient"]).syntax().is_err());
+        assert!(args(&["--indent", "four"]).number("--indent").is_err());
+        assert!(args(&["--colour"]).files().is_err());
+    }
+
+    #[test]
+    fn parse_renders_errors() {
+        let mut parser = Parser::new();
+
+        assert_eq!(parse(&mut parser, b"[1, 2]"), Ok(json!([1, 2])));
+        assert_eq!(
+            parse(&mut parser, b"[1, 2,]"),
+            Err(String::from(
+                "error: expected a value
+ --> line 1, column 7
+  |
+1 | [1, 2,]
+  |       ^
+"
+            ))
+        );
+        assert!(
+            parse(&mut parser, b"[\"\xff\"]")
+                .unwrap_err()
+                .starts_with("error: invalid UTF-8\n --> line 1, column 3")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// `pjson patch`: applying a JSON Patch or a merge patch to a document
+
+use std::fs;
+use std::process::ExitCode;
+
+use parser_json_rs::patch::{self, Patch};
+use parser_json_rs::{Parser, Value, diff, merge_patch};
+
+use super::{Args, DIALECT_HELP, INVALID, load};
+
+const HELP: &str = "usage: pjson patch [options] <patch> [file]
+
+Apply a patch to the document in the file and print the result. A patch that
+is an array is an RFC 6902 JSON Patch, anything else an RFC 7386 merge patch.
+Exits with 1 if the patch doesn't apply, leaving the document as it was.
+
+options:
+  --merge         read the patch as a merge patch even if it is an array
+  --dry-run       print what the patch would change instead of the result
+  --in-place      write the result back to the file
+  --compact, -c   print the result on one line";
+
+pub fn run(mut args: Args) -> Result<ExitCode, String> {
+    if args.flag("--help") {
+        println!("{HELP}\n{DIALECT_HELP}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let merge = args.flag("--merge");
+    let dry_run = args.flag("--dry-run");
+    let in_place = args.flag("--in-place");
+    let compact = args.flag("--compact") | args.flag("-c");
+    let mut parser = Parser::builder().syntax(args.syntax()?).build();
+    let patch_path = args.operand("patch file")?;
+    let files = args.files()?;
+    let [path] = files.as_slice() else {
+        return Err(String::from("`patch` applies to one file"));
+    };
+    if in_place && path == "-" {
+        return Err(String::from("`--in-place` needs a file"));
+    }
+    let (patch, document) = match (load(&mut parser, &patch_path), load(&mut parser, path)) {
+        (Ok(patch), Ok(document)) => (patch, document),
+        (Err(failure), _) | (_, Err(failure)) => return Ok(ExitCode::from(failure)),
+    };
+    let patched = match patched(&document, &patch, merge) {
+        Ok(patched) => patched,
+        Err(message) => {
+            eprintln!("{patch_path}: {message}");
+            return Ok(ExitCode::from(INVALID));
+        }
+    };
+
+    if dry_run {
+        for change in diff(&document, &patched) {
+            println!("{change}");
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut text = if compact {
+        patched.to_string()
+    } else {
+        patched.to_string_pretty()
+    };
+    text.push('\n');
+    if in_place {
+        fs::write(path, text).map_err(|err| format!("{path}: {err}"))?;
+    } else {
+        print!("{text}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `document` with `patch` applied, as a merge patch if `merge` is set or
+/// the patch isn't an array
+fn patched(document: &Value, patch: &Value, merge: bool) -> Result<Value, String> {
+    let mut patched = document.clone();
+    if merge || !patch.is_array() {
+        merge_patch::apply(&mut patched, patch);
+    } else {
+        let patch = Patch::from_value(patch).map_err(|err| err.to_string())?;
+        patch::apply(&mut patched, &patch).map_err(|err| err.to_string())?;
+    }
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::patched;
+    use parser_json_rs::json;
+
+    #[test]
+    fn json_patch_or_merge_patch() {
+        let document = json!({"a": 1, "b": [true]});
+
+        assert_eq!(
+            patched(
+                &document,
+                &json!([{"op": "add", "path": "/b/-", "value": false}]),
+                false
+            ),
+            Ok(json!({"a": 1, "b": [true, false]}))
+        );
+        assert_eq!(
+            patched(&document, &json!({"a": null, "c": 2}), false),
+            Ok(json!({"b": [true], "c": 2}))
+        );
+        assert_eq!(patched(&document, &json!([1]), true), Ok(json!([1])));
+        assert_eq!(
+            patched(
+                &document,
+                &json!([{"op": "test", "path": "/a", "value": 2}]),
+                false
+            ),
+            Err(String::from("test of `/a` failed"))
+        );
+    }
+}
@@ -0,0 +1,39 @@
+// `pjson validate`: checking documents are well formed, for CI
+
+use std::process::ExitCode;
+
+use parser_json_rs::Parser;
+
+use super::{Args, DIALECT_HELP, FAILED, INVALID, display_name, parse, read};
+
+const HELP: &str = "usage: pjson validate [options] [files]
+
+Check every file holds one well-formed document, printing what is wrong with
+those that don't. Exits with 0 if all are valid, 1 if any isn't and 2 if any
+can't be read.
+
+options:";
+
+pub fn run(mut args: Args) -> Result<ExitCode, String> {
+    if args.flag("--help") {
+        println!("{HELP}\n{DIALECT_HELP}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut parser = Parser::builder().syntax(args.syntax()?).build();
+    let mut code = 0;
+    for path in args.files()? {
+        match read(&path) {
+            Ok(bytes) => {
+                if let Err(report) = parse(&mut parser, &bytes) {
+                    eprint!("{}: {report}", display_name(&path));
+                    code = code.max(INVALID);
+                }
+            }
+            Err(message) => {
+                eprintln!("pjson: {message}");
+                code = FAILED;
+            }
+        }
+    }
+    Ok(ExitCode::from(code))
+}
@@ -0,0 +1,116 @@
+// A DOM borrowing its strings from the input wherever escapes allow it
+
+use alloc::borrow::Cow;
+
+use super::Map;
+use super::Value;
+use super::parser::Node;
+use super::prelude::*;
+
+/// Borrowed counterpart of [`Value`], strings and keys without escape
+/// sequences point straight into the parsed input instead of being copied
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    Null,
+    Boolean(bool),
+    String(Cow<'a, str>),
+    Number(f64),
+    Array(Vec<ValueRef<'a>>),
+    Object(Map<Cow<'a, str>, ValueRef<'a>>),
+}
+
+impl ValueRef<'_> {
+    /// Copy every borrowed string, detaching the value from the input
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Null => Value::Null,
+            ValueRef::Boolean(b) => Value::Boolean(b),
+            ValueRef::String(s) => Value::String(s.into()),
+            ValueRef::Number(n) => Value::Number(n),
+            ValueRef::Array(arr) => {
+                Value::Array(arr.into_iter().map(ValueRef::into_owned).collect())
+            }
+            ValueRef::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> Node<'a> for ValueRef<'a> {
+    type Key = Cow<'a, str>;
+
+    fn null() -> Self {
+        ValueRef::Null
+    }
+
+    fn boolean(b: bool) -> Self {
+        ValueRef::Boolean(b)
+    }
+
+    fn number(n: f64) -> Self {
+        ValueRef::Number(n)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        ValueRef::String(s)
+    }
+
+    fn key(s: Cow<'a, str>) -> Cow<'a, str> {
+        s
+    }
+
+    fn array(arr: Vec<Self>) -> Self {
+        ValueRef::Array(arr)
+    }
+
+    fn object(map: Map<Cow<'a, str>, Self>) -> Self {
+        ValueRef::Object(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Map;
+    use crate::prelude::*;
+    use alloc::borrow::Cow;
+
+    use super::ValueRef;
+    use crate::{Value, parse_borrowed};
+
+    #[test]
+    fn strings_without_escapes_are_borrowed() {
+        let input = r#"{"plain": "text", "escaped": "a\nb"}"#;
+        let ValueRef::Object(map) = parse_borrowed(input).unwrap() else {
+            panic!("expected an object");
+        };
+
+        assert!(matches!(
+            map["plain"],
+            ValueRef::String(Cow::Borrowed("text"))
+        ));
+        assert!(matches!(&map["escaped"], ValueRef::String(Cow::Owned(s)) if s == "a\nb"));
+        assert!(map.keys().all(|key| matches!(key, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn into_owned_matches_parse() {
+        let input = r#"[null, true, 1.5, "x", {"k": ["v"]}]"#;
+        let mut map = Map::default();
+        map.insert(
+            String::from("k"),
+            Value::Array(vec![Value::String("v".into())]),
+        );
+        let expected = Value::Array(vec![
+            Value::Null,
+            Value::Boolean(true),
+            Value::Number(1.5),
+            Value::String("x".into()),
+            Value::Object(map),
+        ]);
+
+        assert_eq!(parse_borrowed(input).unwrap().into_owned(), expected);
+    }
+}
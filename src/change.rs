@@ -0,0 +1,182 @@
+// Differences between two values as a list of changes for people to read,
+// e.g. in test failures and audit logs, rather than a patch to apply
+
+use core::fmt;
+
+use super::Map;
+use super::Value;
+use super::pointer::escape_token;
+use super::prelude::*;
+use super::ser::{NonFinite, write_canonical};
+
+/// A value that differs between two documents, see [`diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// JSON Pointer to the value
+    pub pointer: String,
+    /// The value in the first document, `None` if it was added
+    pub old: Option<Value>,
+    /// The value in the second document, `None` if it was removed
+    pub new: Option<Value>,
+}
+
+/// A value written as canonical JSON, for people to read, so with non-finite
+/// numbers as `null` rather than failing
+struct Canonical<'a>(&'a Value);
+
+impl fmt::Display for Canonical<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_canonical(f, self.0, NonFinite::Null)
+    }
+}
+
+/// `changed /a from 1 to 2`, `added /b: true` or `removed /c: null`, with
+/// values written as canonical JSON
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "the document"
+        } else {
+            &self.pointer
+        };
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(
+                f,
+                "changed {pointer} from {} to {}",
+                Canonical(old),
+                Canonical(new)
+            ),
+            (None, Some(new)) => write!(f, "added {pointer}: {}", Canonical(new)),
+            (Some(old), None) => write!(f, "removed {pointer}: {}", Canonical(old)),
+            (None, None) => write!(f, "nothing at {pointer}"),
+        }
+    }
+}
+
+/// The values that differ between `old` and `new`, in document order
+///
+/// Objects are compared member by member in key order and arrays element by
+/// element by position, so every change names the place it happened. Values
+/// of different types are a single change.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at(old, new, String::new(), &mut changes);
+    changes
+}
+
+fn diff_at(old: &Value, new: &Value, pointer: String, changes: &mut Vec<Change>) {
+    match (old, new) {
+        _ if old == new => {}
+        (Value::Object(old), Value::Object(new)) => diff_objects(old, new, &pointer, changes),
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                let element = format!("{pointer}/{i}");
+                diff_members(old.get(i), new.get(i), element, changes);
+            }
+        }
+        _ => changes.push(Change {
+            pointer,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+    }
+}
+
+fn diff_objects(
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    pointer: &str,
+    changes: &mut Vec<Change>,
+) {
+    let mut keys: Vec<&String> = old
+        .keys()
+        .chain(new.keys().filter(|key| !old.contains_key(*key)))
+        .collect();
+    keys.sort();
+
+    for key in keys {
+        let member = format!("{pointer}/{}", escape_token(key));
+        diff_members(old.get(key), new.get(key), member, changes);
+    }
+}
+
+fn diff_members(
+    old: Option<&Value>,
+    new: Option<&Value>,
+    pointer: String,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) => diff_at(old, new, pointer, changes),
+        (old, new) => changes.push(Change {
+            pointer,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, diff};
+    use crate::prelude::*;
+
+    #[test]
+    fn diff_reports_each_change() {
+        let old = json!({"name": "a", "tags": ["x", "y"], "meta": {"v": 1, "gone": true}});
+        let new = json!({"name": "b", "tags": ["x"], "meta": {"v": 1, "new/key": null}});
+        let changes = diff(&old, &new);
+
+        assert_eq!(
+            changes,
+            [
+                Change {
+                    pointer: String::from("/meta/gone"),
+                    old: Some(json!(true)),
+                    new: None,
+                },
+                Change {
+                    pointer: String::from("/meta/new~1key"),
+                    old: None,
+                    new: Some(json!(null)),
+                },
+                Change {
+                    pointer: String::from("/name"),
+                    old: Some(json!("a")),
+                    new: Some(json!("b")),
+                },
+                Change {
+                    pointer: String::from("/tags/1"),
+                    old: Some(json!("y")),
+                    new: None,
+                },
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn changes_display_for_people() {
+        let lines: Vec<String> = diff(&json!({"a": [1], "b": 2}), &json!({"a": {"k": 1}, "c": 3}))
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            lines,
+            [
+                r#"changed /a from [1] to {"k":1}"#,
+                "removed /b: 2",
+                "added /c: 3"
+            ]
+        );
+        assert_eq!(
+            diff(&json!(1), &json!(2))[0].to_string(),
+            "changed the document from 1 to 2"
+        );
+        assert_eq!(
+            diff(&json!([1]), &json!([f64::NAN]))[0].to_string(),
+            "changed /0 from 1 to null"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// Recognizing compressed input by its first bytes and decompressing it
+
+use std::io::{self, Cursor, Read};
+
+/// The first bytes of every gzip member
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The first bytes of every zstd frame
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The input of `reader`, decompressed if it starts with the magic bytes of
+/// a format whose feature is enabled and passed through as it is otherwise
+pub(crate) fn decompress<'r>(mut reader: impl Read + 'r) -> io::Result<Box<dyn Read + 'r>> {
+    let mut magic = [0; 4];
+    let len = read_prefix(&mut reader, &mut magic)?;
+    let prefix = &magic[..len];
+    // put the bytes looked at back in front of the rest
+    let reader = Cursor::new(magic).take(len as u64).chain(reader);
+
+    #[cfg(feature = "gzip")]
+    if prefix.starts_with(&GZIP_MAGIC) {
+        // gzip files may hold several members, `gzip -d` joins them
+        return Ok(Box::new(flate2::read::MultiGzDecoder::new(reader)));
+    }
+    #[cfg(feature = "zstd")]
+    if prefix == ZSTD_MAGIC {
+        return Ok(Box::new(zstd::Decoder::new(reader)?));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Fill `buf` from `reader` as far as the input goes, returning how many
+/// bytes were read
+fn read_prefix(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, parse_from_reader};
+
+    const INPUT: &str = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e"}"#;
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn parse_gzip_input() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let gzip = |input: &str| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(input.as_bytes()).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        assert_eq!(parse_from_reader(&gzip(INPUT)[..]), parse(INPUT));
+        let mut members = gzip("[1, ");
+        members.extend(gzip("2]"));
+        assert_eq!(parse_from_reader(&members[..]), parse("[1, 2]"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn size_limit_counts_decompressed_bytes() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        use crate::parse_from_reader_with_options;
+        use crate::{JsonError, Limit, Limits, Parser, TokenParseError};
+
+        // a few hundred bytes expanding to 10 MB of whitespace
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(b"[").unwrap();
+        encoder.write_all(&vec![b' '; 10_000_000]).unwrap();
+        encoder.write_all(b"]").unwrap();
+        let bomb = encoder.finish().unwrap();
+        let limits = Limits {
+            max_document_size: 1 << 20,
+            ..Limits::default()
+        };
+
+        assert_eq!(
+            parse_from_reader_with_options(&bomb[..], Parser::builder().limits(limits)),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                Limit::DocumentSize
+            )))
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn parse_zstd_input() {
+        let compressed = zstd::encode_all(INPUT.as_bytes(), 0).unwrap();
+
+        assert_eq!(parse_from_reader(&compressed[..]), parse(INPUT));
+    }
+
+    #[test]
+    fn uncompressed_input_passes_through() {
+        assert_eq!(parse_from_reader(INPUT.as_bytes()), parse(INPUT));
+        assert_eq!(parse_from_reader(&b"1"[..]), parse("1"));
+        assert_eq!(parse_from_reader(&b""[..]), parse(""));
+    }
+}
@@ -0,0 +1,102 @@
+// Reading several JSON values written back to back in one input
+
+use super::tokenize::{SliceSource, TokenStream};
+use super::{JsonError, ParserOptions, Value};
+
+/// Iterator over the top-level values of an input holding any number of
+/// them, separated by whitespace or nothing at all, as in `{"a":1}{"a":2}`
+///
+/// Iteration stops at the end of the input or after the first error.
+pub struct StreamDeserializer<'a> {
+    options: ParserOptions,
+    tokens: TokenStream<'a, SliceSource<'a>>,
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> StreamDeserializer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let options = ParserOptions::default();
+        StreamDeserializer {
+            options,
+            tokens: TokenStream::with_syntax(SliceSource::new(input.as_bytes()), options.syntax),
+            offset: 0,
+            failed: false,
+        }
+    }
+
+    /// Byte offset just past the last value parsed successfully, which is
+    /// where the input stopped making sense after an error
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Iterator for StreamDeserializer<'_> {
+    type Item = Result<Value, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.options.parse_next(&mut self.tokens) {
+            Ok(value) => {
+                self.offset = self.tokens.span().end;
+                value.map(Ok)
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamDeserializer;
+    use crate::prelude::*;
+    use crate::{JsonError, TokenParseError, Value, parse};
+
+    #[test]
+    fn values_back_to_back() {
+        let input = r#"{"a":1}{"a":2} [3]
+"x"  "#;
+        let mut values = StreamDeserializer::new(input);
+        let mut ends = Vec::new();
+        while let Some(value) = values.next() {
+            value.unwrap();
+            ends.push(values.byte_offset());
+        }
+
+        assert_eq!(ends, vec![7, 14, 18, 22]);
+        let values: Result<Vec<Value>, JsonError> = StreamDeserializer::new(input).collect();
+        assert_eq!(
+            values,
+            Ok(vec![
+                parse(r#"{"a":1}"#).unwrap(),
+                parse(r#"{"a":2}"#).unwrap(),
+                parse("[3]").unwrap(),
+                parse(r#""x""#).unwrap(),
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_values() {
+        assert_eq!(StreamDeserializer::new(" \n").next(), None);
+    }
+
+    #[test]
+    fn stop_at_first_error() {
+        let mut values = StreamDeserializer::new("[1] [2 3] [4]");
+
+        assert_eq!(values.next(), Some(Ok(parse("[1]").unwrap())));
+        assert_eq!(
+            values.next(),
+            Some(Err(JsonError::Parse(TokenParseError::ExpectedComma)))
+        );
+        assert_eq!(values.byte_offset(), 3);
+        assert_eq!(values.next(), None);
+    }
+}
@@ -0,0 +1,383 @@
+// Conversions between Rust types and `Value`
+
+use alloc::borrow::Cow;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::str::FromStr;
+
+use super::Map;
+use super::map::HashMap;
+use super::pointer::escape_token;
+use super::prelude::*;
+use super::string::{from_string, into_string};
+use super::{JsonError, Value};
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(n: f32) -> Self {
+        Value::Number(f64::from(n))
+    }
+}
+
+/// Numbers are stored as `f64`, integers beyond 2^53 in magnitude lose
+/// precision
+macro_rules! from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::Number(n as f64)
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(from_string(s))
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::String(from_string(c.to_string()))
+    }
+}
+
+impl From<Cow<'_, str>> for Value {
+    fn from(s: Cow<'_, str>) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Clone + Into<Value>> From<&[T]> for Value {
+    fn from(values: &[T]) -> Self {
+        Value::Array(values.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>, S> From<HashMap<String, T, S>> for Value {
+    fn from(map: HashMap<String, T, S>) -> Self {
+        Value::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// `None` becomes null
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Null, Into::into)
+    }
+}
+
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Value::Array(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        Value::Object(iter.into_iter().collect())
+    }
+}
+
+/// Appends to an array, turning a null value into an empty array first.
+/// Panics for anything else.
+impl Extend<Value> for Value {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        if self.is_null() {
+            *self = Value::Array(Vec::new());
+        }
+        match self {
+            Value::Array(values) => values.extend(iter),
+            value => panic!("cannot extend {} with elements", value.kind()),
+        }
+    }
+}
+
+/// Adds or replaces members of an object, turning a null value into an empty
+/// object first. Panics for anything else.
+impl Extend<(String, Value)> for Value {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        if self.is_null() {
+            *self = Value::Object(Map::default());
+        }
+        match self {
+            Value::Object(map) => map.extend(iter),
+            value => panic!("cannot extend {} with members", value.kind()),
+        }
+    }
+}
+
+/// `text.parse::<Value>()`, the same as [`parse`](crate::parse)
+impl FromStr for Value {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, JsonError> {
+        crate::parse(s)
+    }
+}
+
+/// Why a [`Value`] could not be converted into a Rust type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError {
+    /// JSON Pointer to the value that didn't fit, empty for the top level
+    pub path: String,
+    /// the type converted into
+    pub expected: &'static str,
+    /// what was found instead
+    pub found: String,
+}
+
+impl ConversionError {
+    /// The error for finding `value` where `expected` was wanted, for
+    /// [`FromJson`](crate::FromJson) implementations
+    pub fn new(expected: &'static str, value: &Value) -> Self {
+        let found = match value {
+            Value::Number(n) => format!("the number {n}"),
+            value => value.kind().to_owned(),
+        };
+        ConversionError {
+            path: String::new(),
+            expected,
+            found,
+        }
+    }
+
+    /// The error for the member `segment` of the value being converted
+    pub(crate) fn within(mut self, segment: &str) -> Self {
+        self.path = format!("/{}{}", escape_token(segment), self.path);
+        self
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", self.expected)?;
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.path)?;
+        }
+        write!(f, ", found {}", self.found)
+    }
+}
+
+impl core::error::Error for ConversionError {}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        value
+            .as_bool()
+            .ok_or_else(|| ConversionError::new("a boolean", &value))
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::String(s) => Ok(into_string(s)),
+            value => Err(ConversionError::new("a string", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        value
+            .as_f64()
+            .ok_or_else(|| ConversionError::new("a number", &value))
+    }
+}
+
+/// Any number is accepted, rounded to the nearest `f32`
+impl TryFrom<Value> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        f64::try_from(value).map(|n| n as f32)
+    }
+}
+
+/// Integers convert from whole numbers within their range only
+macro_rules! try_into_integer {
+    ($wide:ident: $($ty:ty),*) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = ConversionError;
+
+                fn try_from(value: Value) -> Result<Self, ConversionError> {
+                    value
+                        .$wide()
+                        .and_then(|n| <$ty>::try_from(n).ok())
+                        .ok_or_else(|| ConversionError::new(stringify!($ty), &value))
+                }
+            }
+        )*
+    };
+}
+
+try_into_integer!(as_i64: i8, i16, i32, i64, isize);
+try_into_integer!(as_u64: u8, u16, u32, u64, usize);
+
+impl<T: TryFrom<Value, Error = ConversionError>> TryFrom<Value> for Vec<T> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        let Value::Array(values) = value else {
+            return Err(ConversionError::new("an array", &value));
+        };
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| T::try_from(value).map_err(|err| err.within(&i.to_string())))
+            .collect()
+    }
+}
+
+impl<T, S> TryFrom<Value> for HashMap<String, T, S>
+where
+    T: TryFrom<Value, Error = ConversionError>,
+    S: BuildHasher + Default,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, ConversionError> {
+        let Value::Object(map) = value else {
+            return Err(ConversionError::new("an object", &value));
+        };
+        map.into_iter()
+            .map(|(key, value)| match T::try_from(value) {
+                Ok(value) => Ok((key, value)),
+                Err(err) => Err(err.within(&key)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use alloc::borrow::Cow;
+
+    use super::ConversionError;
+    use crate::map::HashMap;
+    use crate::{JsonError, TokenParseError, Value};
+
+    #[test]
+    fn from_scalars() {
+        assert_eq!(Value::from(true), Value::Boolean(true));
+        assert_eq!(Value::from(-7i64), Value::Number(-7.0));
+        assert_eq!(Value::from(7u8), Value::Number(7.0));
+        assert_eq!(Value::from(0.5f32), Value::Number(0.5));
+        assert_eq!(Value::from("a"), Value::String("a".into()));
+        assert_eq!(Value::from('b'), Value::String("b".into()));
+        assert_eq!(Value::from(Cow::Borrowed("c")), Value::String("c".into()));
+    }
+
+    #[test]
+    fn from_collections() {
+        let map = HashMap::from([(String::from("a"), vec![Some(1), None])]);
+
+        assert_eq!(Value::from(map), json!({"a": [1, null]}));
+        assert_eq!(Value::from(&["x", "y"][..]), json!(["x", "y"]));
+        assert_eq!(Value::from(None::<bool>), Value::Null);
+    }
+
+    #[test]
+    fn try_from_scalars() {
+        assert_eq!(String::try_from(json!("a")), Ok(String::from("a")));
+        assert_eq!(bool::try_from(json!(false)), Ok(false));
+        assert_eq!(u8::try_from(json!(255)), Ok(255));
+        assert_eq!(i64::try_from(json!(-4)), Ok(-4));
+        assert_eq!(f64::try_from(json!(0.5)), Ok(0.5));
+
+        let err = u8::try_from(json!(256)).unwrap_err();
+        assert_eq!(err.to_string(), "expected u8, found the number 256");
+        let err = i32::try_from(json!(1.5)).unwrap_err();
+        assert_eq!(err.to_string(), "expected i32, found the number 1.5");
+        let err = String::try_from(json!([1])).unwrap_err();
+        assert_eq!(err.to_string(), "expected a string, found an array");
+    }
+
+    #[test]
+    fn try_from_collections() {
+        let map: HashMap<String, Vec<u16>> = json!({"a": [1, 2], "b": []}).try_into().unwrap();
+        assert_eq!(map["a"], vec![1, 2]);
+
+        let err = HashMap::<String, Vec<u16>>::try_from(json!({"a/b": [1, "x"]})).unwrap_err();
+        assert_eq!(
+            err,
+            ConversionError {
+                path: String::from("/a~1b/1"),
+                expected: "u16",
+                found: String::from("a string"),
+            }
+        );
+        assert_eq!(err.to_string(), "expected u16 at /a~1b/1, found a string");
+    }
+
+    #[test]
+    fn from_str_parses() {
+        let value: Value = r#"{"a": [1, null]}"#.parse().unwrap();
+        assert_eq!(value, json!({"a": [1, null]}));
+        assert_eq!(
+            "[1] 2".parse::<Value>(),
+            Err(JsonError::Parse(TokenParseError::TrailingTokens))
+        );
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let mut array: Value = (1..=2).map(Value::from).collect();
+        array.extend([Value::Null]);
+        assert_eq!(array, json!([1, 2, null]));
+
+        let mut object: Value = [("a", 1)]
+            .into_iter()
+            .map(|(k, v)| (String::from(k), Value::from(v)))
+            .collect();
+        object.extend([(String::from("b"), json!(true))]);
+        assert_eq!(object, json!({"a": 1, "b": true}));
+
+        let mut value = Value::Null;
+        value.extend([(String::from("c"), Value::Null)]);
+        assert_eq!(value, json!({"c": null}));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot extend an object with elements")]
+    fn extend_object_with_elements_panics() {
+        json!({}).extend([Value::Null]);
+    }
+}
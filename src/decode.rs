@@ -0,0 +1,215 @@
+// Turning raw input bytes into text the tokenizer can work with
+
+use super::prelude::*;
+use super::tokenize::TokenizeError;
+
+/// How invalid byte sequences in byte input are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Utf8Mode {
+    /// reject the document at the first invalid byte sequence
+    #[default]
+    Strict,
+
+    /// replace every invalid byte sequence with U+FFFD and keep going
+    Replace,
+}
+
+/// Text decoded from bytes along with the byte offsets, in the original
+/// input, of every sequence that was replaced with U+FFFD
+#[derive(Debug, PartialEq)]
+pub(crate) struct Decoded {
+    pub text: String,
+    pub replacements: Vec<usize>,
+}
+
+/// The encodings byte input can be in, told apart as
+/// [RFC 4627](https://www.rfc-editor.org/rfc/rfc4627#section-3) describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    /// The encoding of `input`, from its byte order mark if it has one and
+    /// otherwise from the zero bytes among the first four, JSON text
+    /// starting with two ASCII characters
+    ///
+    /// ```
+    /// use parser_json_rs::Encoding;
+    ///
+    /// assert_eq!(Encoding::detect(b"[1]"), Encoding::Utf8);
+    /// assert_eq!(Encoding::detect(b"[\0001\0]\0"), Encoding::Utf16Le);
+    /// assert_eq!(Encoding::detect(b"\xfe\xff\0[\01\0]"), Encoding::Utf16Be);
+    /// ```
+    pub fn detect(input: &[u8]) -> Encoding {
+        match input {
+            [0xef, 0xbb, 0xbf, ..] => Encoding::Utf8,
+            [0, 0, 0xfe, 0xff, ..] | [0, 0, 0, _, ..] => Encoding::Utf32Be,
+            [0xff, 0xfe, 0, 0, ..] | [_, 0, 0, 0, ..] => Encoding::Utf32Le,
+            [0xfe, 0xff, ..] | [0, _, ..] => Encoding::Utf16Be,
+            [0xff, 0xfe, ..] | [_, 0, ..] => Encoding::Utf16Le,
+            _ => Encoding::Utf8,
+        }
+    }
+}
+
+/// Decode `input` in the encoding [detected](Encoding::detect) for it
+pub(crate) fn decode(input: &[u8], mode: Utf8Mode) -> Result<Decoded, TokenizeError> {
+    match Encoding::detect(input) {
+        Encoding::Utf8 => decode_utf8(input, mode),
+        encoding => decode_wide(input, encoding, mode),
+    }
+}
+
+fn decode_utf8(input: &[u8], mode: Utf8Mode) -> Result<Decoded, TokenizeError> {
+    let mut text = String::with_capacity(input.len());
+    let mut replacements = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let rest = &input[offset..];
+        match core::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                break;
+            }
+            Err(err) => {
+                let valid_up_to = err.valid_up_to();
+                // everything before `valid_up_to` is known to be valid
+                text.push_str(core::str::from_utf8(&rest[..valid_up_to]).unwrap());
+
+                let bad_offset = offset + valid_up_to;
+                if mode == Utf8Mode::Strict {
+                    return Err(TokenizeError::InvalidUtf8(bad_offset));
+                }
+                text.push(char::REPLACEMENT_CHARACTER);
+                replacements.push(bad_offset);
+
+                // `None` means the input ends in the middle of a sequence
+                match err.error_len() {
+                    Some(len) => offset = bad_offset + len,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(Decoded { text, replacements })
+}
+
+/// Transcode UTF-16 or UTF-32 `input` to UTF-8. A byte order mark is kept,
+/// the lexer skips it like any other.
+fn decode_wide(input: &[u8], encoding: Encoding, mode: Utf8Mode) -> Result<Decoded, TokenizeError> {
+    let unit = match encoding {
+        Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        _ => 4,
+    };
+    let mut text = String::with_capacity(input.len() / unit);
+    let mut replacements = Vec::new();
+    let mut invalid = |offset: usize, text: &mut String| {
+        if mode == Utf8Mode::Strict {
+            return Err(TokenizeError::InvalidEncoding(offset));
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+        replacements.push(offset);
+        Ok(())
+    };
+
+    let chunks = input.chunks_exact(unit);
+    let rest = chunks.remainder();
+    match encoding {
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = chunks.map(|bytes| {
+                let bytes = [bytes[0], bytes[1]];
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes(bytes)
+                } else {
+                    u16::from_be_bytes(bytes)
+                }
+            });
+            let mut offset = 0;
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok(c) => {
+                        text.push(c);
+                        offset += c.len_utf16() * 2;
+                    }
+                    // an unpaired surrogate, one unit long
+                    Err(_) => {
+                        invalid(offset, &mut text)?;
+                        offset += 2;
+                    }
+                }
+            }
+        }
+        _ => {
+            for (i, bytes) in chunks.enumerate() {
+                let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                let code = if encoding == Encoding::Utf32Le {
+                    u32::from_le_bytes(bytes)
+                } else {
+                    u32::from_be_bytes(bytes)
+                };
+                match char::from_u32(code) {
+                    Some(c) => text.push(c),
+                    None => invalid(i * 4, &mut text)?,
+                }
+            }
+        }
+    }
+    if !rest.is_empty() {
+        invalid(input.len() - rest.len(), &mut text)?;
+    }
+
+    Ok(Decoded { text, replacements })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decoded, Utf8Mode, decode};
+    use crate::prelude::*;
+    use crate::tokenize::TokenizeError;
+
+    #[test]
+    fn decode_valid_input_unchanged() {
+        let expected = Decoded {
+            text: String::from("olá"),
+            replacements: vec![],
+        };
+
+        assert_eq!(
+            decode("olá".as_bytes(), Utf8Mode::Replace).unwrap(),
+            expected
+        );
+        assert_eq!(
+            decode("olá".as_bytes(), Utf8Mode::Strict).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn decode_strict_rejects_invalid_byte() {
+        let input = b"\"ab\xffc\"";
+
+        assert_eq!(
+            decode(input, Utf8Mode::Strict),
+            Err(TokenizeError::InvalidUtf8(3))
+        );
+    }
+
+    #[test]
+    fn decode_replace_records_offsets() {
+        // a lone continuation byte, then a truncated 3 byte sequence at the end
+        let input = b"a\x80b\xe3\x81";
+        let expected = Decoded {
+            text: String::from("a\u{fffd}b\u{fffd}"),
+            replacements: vec![1, 3],
+        };
+
+        assert_eq!(decode(input, Utf8Mode::Replace).unwrap(), expected);
+    }
+}
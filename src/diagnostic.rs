@@ -0,0 +1,218 @@
+// Parse errors with where they happened, rendered against the source
+
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+use super::line_index::LineIndex;
+use super::parser::TokenParseError;
+use super::prelude::*;
+use super::tokenize::{SliceSource, TokenStream, TokenizeError};
+use super::{JsonError, Limit, ParserOptions, Value};
+
+/// An error and the byte range of the input it is about
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub error: JsonError,
+    /// the token the error was found at, empty at the end of the input
+    pub span: Range<usize>,
+}
+
+impl Diagnostic {
+    /// A short description of what is wrong, for people
+    pub fn message(&self) -> String {
+        match &self.error {
+            JsonError::Tokenize(err) => match err {
+                TokenizeError::UnrecognizedToken => String::from("unrecognized token"),
+                TokenizeError::UnfinishedLiteralValue => {
+                    String::from("expected `true`, `false` or `null`")
+                }
+                TokenizeError::MisspelledLiteral(literal) => {
+                    format!("unknown literal, did you mean `{literal}`?")
+                }
+                TokenizeError::ParseNumberError(_) => String::from("invalid number"),
+                TokenizeError::UnclosedQuotes => String::from("string is never closed"),
+                TokenizeError::UnexpectedEof => String::from("unexpected end of input"),
+                TokenizeError::CharNotRecognized(c) => format!("`{c}` can't start a value"),
+                TokenizeError::InvalidUtf8(_) => String::from("invalid UTF-8"),
+                TokenizeError::InvalidEncoding(_) => String::from("invalid UTF-16 or UTF-32"),
+            },
+            JsonError::Parse(err) => String::from(match err {
+                TokenParseError::EmptyInput => "expected a value, found nothing",
+                TokenParseError::UnexpectedEnd => "the input ends before the value does",
+                TokenParseError::ExpectedValue => "expected a value",
+                TokenParseError::UnfinishedEscape => "`\\u` escape is missing hex digits",
+                TokenParseError::InvalidHexValue => "`\\u` escape has a non-hex digit",
+                TokenParseError::InvalidCodePointValue => "`\\u` escape isn't a character",
+                TokenParseError::ExpectedComma => {
+                    "expected `,` or a closing bracket after the value"
+                }
+                TokenParseError::ExpectedProperty => "expected a quoted key",
+                TokenParseError::ExpectedColon => "expected `:` after the key",
+                TokenParseError::DepthLimitExceeded => "arrays and objects nested too deeply",
+                TokenParseError::TrailingTokens => "expected the end of the input after the value",
+                TokenParseError::LimitExceeded(limit) => match limit {
+                    Limit::DocumentSize => "the document is too large",
+                    Limit::StringLength => "the string is too long",
+                    Limit::ArrayLength => "the array has too many elements",
+                    Limit::ObjectLength => "the object has too many members",
+                },
+            }),
+            #[cfg(feature = "std")]
+            JsonError::Io(err) => err.to_string(),
+            JsonError::Conversion(err) => err.to_string(),
+        }
+    }
+
+    /// The message above the line of `source` the error is on, with carets
+    /// under the offending text:
+    ///
+    /// ```text
+    /// error: expected `,` or a closing bracket after the value
+    ///  --> line 2, column 10
+    ///   |
+    /// 2 |   "a": 1 "b": 2
+    ///   |          ^^^
+    /// ```
+    ///
+    /// `source` is the input that was parsed.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::new();
+        self.render_to(&mut out, source)
+            .expect("writing to a String never fails");
+        out
+    }
+
+    fn render_to(&self, out: &mut String, source: &str) -> fmt::Result {
+        writeln!(out, "error: {}", self.message())?;
+        let index = LineIndex::new(source);
+        let Some(start) = index.line_col(self.span.start) else {
+            return Ok(());
+        };
+        let line = index
+            .line_text(start.line)
+            .expect("positions are on a line");
+
+        // the carets run to the end of the span, or of the line if it
+        // spans several
+        let end = match index.line_col(self.span.end) {
+            Some(end) if end.line == start.line => end.column,
+            _ => line.chars().count() + 1,
+        };
+        let width = start.line.to_string().len();
+        let gutter = " ".repeat(width);
+        // tabs are kept so the carets line up however they are displayed
+        let indent: String = line
+            .chars()
+            .take(start.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(
+            out,
+            "{gutter}--> line {}, column {}",
+            start.line, start.column
+        )?;
+        writeln!(out, "{gutter} |")?;
+        writeln!(out, "{} | {line}", start.line)?;
+        writeln!(
+            out,
+            "{gutter} | {indent}{}",
+            "^".repeat(end.saturating_sub(start.column).max(1))
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message(), self.span.start)
+    }
+}
+
+impl core::error::Error for Diagnostic {}
+
+/// Parse a JSON document from text, an error coming with the span of the
+/// input it was found at so it can be [rendered](Diagnostic::render):
+///
+/// ```
+/// use parser_json_rs::parse_with_diagnostic;
+///
+/// let input = "[1, 2\n 3]";
+/// let err = parse_with_diagnostic(input).unwrap_err();
+/// assert_eq!(&input[err.span.clone()], "3");
+/// assert!(err.render(input).starts_with("error: expected `,` or a closing bracket"));
+/// ```
+pub fn parse_with_diagnostic(input: &str) -> Result<Value, Diagnostic> {
+    let options = ParserOptions::default();
+    let mut tokens = TokenStream::with_syntax(SliceSource::new(input.as_bytes()), options.syntax);
+    options
+        .parse_tokens(&mut tokens)
+        .map_err(|error| Diagnostic {
+            error,
+            span: tokens.span(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_with_diagnostic;
+    use crate::parse;
+    use crate::prelude::*;
+
+    fn render(input: &str) -> String {
+        parse_with_diagnostic(input).unwrap_err().render(input)
+    }
+
+    #[test]
+    fn render_points_at_the_bad_token() {
+        assert_eq!(
+            render("{\n  \"a\": 1 \"b\": 2\n}"),
+            "error: expected `,` or a closing bracket after the value
+ --> line 2, column 10
+  |
+2 |   \"a\": 1 \"b\": 2
+  |          ^^^
+"
+        );
+        assert_eq!(
+            render("[\ttru]"),
+            "error: unknown literal, did you mean `true`?
+ --> line 1, column 3
+  |
+1 | [\ttru]
+  |  \t^^^
+"
+        );
+    }
+
+    #[test]
+    fn errors_at_the_end_point_past_the_last_character() {
+        assert_eq!(
+            render("[1,"),
+            "error: the input ends before the value does
+ --> line 1, column 4
+  |
+1 | [1,
+  |    ^
+"
+        );
+        assert_eq!(
+            render(""),
+            "error: expected a value, found nothing
+ --> line 1, column 1
+  |
+1 | \n  | ^
+"
+        );
+    }
+
+    #[test]
+    fn spans_of_errors() {
+        let span = |input: &str| parse_with_diagnostic(input).unwrap_err().span;
+
+        assert_eq!(span("[1] [2]"), 4..5);
+        assert_eq!(span(r#"["\u12"]"#), 1..7);
+        assert_eq!(span("{1: 2}"), 1..2);
+        assert_eq!(span("[é]"), 1..3);
+        assert_eq!(parse_with_diagnostic("[1]"), Ok(parse("[1]").unwrap()));
+    }
+}
@@ -0,0 +1,703 @@
+//! Editing documents in place, keeping their formatting and comments
+//!
+//! A [`Document`] holds every value of the text it was parsed from along
+//! with the whitespace and comments around it, and writes itself back out
+//! byte for byte. Values that are edited are written as compact JSON, while
+//! everything around them is left as it was:
+//!
+//! ```
+//! use parser_json_rs::edit::Document;
+//!
+//! let mut doc = Document::parse("{
+//!     // where to listen
+//!     \"server\": { \"host\": \"localhost\", \"port\": 80 }
+//! }").unwrap();
+//! doc["server"]["port"].set(8080);
+//! doc["server"].insert("tls", false);
+//!
+//! assert_eq!(doc.to_string(), "{
+//!     // where to listen
+//!     \"server\": { \"host\": \"localhost\", \"port\": 8080, \"tls\": false }
+//! }");
+//! ```
+
+use core::fmt::{self, Write};
+use core::iter::Peekable;
+use core::mem;
+use core::ops::{Index, IndexMut, Range};
+
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+use super::ser::{write_string, write_value};
+use super::tokenize::{Lexer, SpannedTokens, Token};
+use super::{DEFAULT_MAX_DEPTH, JsonError, Map, Syntax, ToJson, Value};
+
+/// A parsed document that can be edited and written back out with
+/// everything left unedited unchanged, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    root: Item,
+}
+
+/// A value of a [`Document`] with the whitespace and comments around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// the text between the previous token and the value
+    before: String,
+    node: Node,
+    /// the text between the value and the next token
+    after: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// a literal, number or string, as written
+    Scalar {
+        raw: String,
+        value: Value,
+    },
+    /// `inner` is the text between the brackets when there are no elements
+    Array {
+        elements: Vec<Item>,
+        inner: String,
+    },
+    Object {
+        members: Vec<Member>,
+        inner: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Member {
+    before_key: String,
+    /// the key as written, quotes and escapes included
+    raw_key: String,
+    key: String,
+    /// the text between the key and its colon
+    after_key: String,
+    value: Item,
+}
+
+impl Document {
+    /// Parse a JSON document, `//` and `/* */` comments included
+    pub fn parse(input: &str) -> Result<Document, JsonError> {
+        let mut builder = Builder {
+            input,
+            tokens: Lexer::with_syntax(input, Syntax::jsonc())
+                .spanned()
+                .peekable(),
+            pos: 0,
+        };
+        if builder.tokens.peek().is_none() {
+            return Err(TokenParseError::EmptyInput.into());
+        }
+
+        let mut root = builder.item(0)?;
+        if builder.tokens.next().transpose()?.is_some() {
+            return Err(TokenParseError::TrailingTokens.into());
+        }
+        root.after = input[builder.pos..].to_owned();
+        Ok(Document { root })
+    }
+
+    /// The top-level value
+    pub fn root(&self) -> &Item {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut Item {
+        &mut self.root
+    }
+
+    /// The document as a [`Value`], without its formatting
+    pub fn to_value(&self) -> Value {
+        self.root.to_value()
+    }
+}
+
+/// Writes the document, unchanged but for its edits
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.write(f)
+    }
+}
+
+impl Item {
+    fn new(node: Node) -> Self {
+        Item {
+            before: String::new(),
+            node,
+            after: String::new(),
+        }
+    }
+
+    /// The value without its formatting
+    pub fn to_value(&self) -> Value {
+        match &self.node {
+            Node::Scalar { value, .. } => value.clone(),
+            Node::Array { elements, .. } => {
+                Value::Array(elements.iter().map(Item::to_value).collect())
+            }
+            Node::Object { members, .. } => {
+                let mut map = Map::default();
+                for member in members {
+                    map.insert(member.key.clone(), member.value.to_value());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+
+    /// Replace the value, keeping the whitespace and comments around it
+    pub fn set(&mut self, value: impl ToJson) {
+        self.node = Node::from_value(value.to_json());
+    }
+
+    /// The member `key` of an object, the last one if the key is repeated
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        match &self.node {
+            Node::Object { members, .. } => members
+                .iter()
+                .rev()
+                .find(|member| member.key == key)
+                .map(|member| &member.value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Item> {
+        match &mut self.node {
+            Node::Object { members, .. } => members
+                .iter_mut()
+                .rev()
+                .find(|member| member.key == key)
+                .map(|member| &mut member.value),
+            _ => None,
+        }
+    }
+
+    /// The element at `index` of an array
+    pub fn get_index(&self, index: usize) -> Option<&Item> {
+        match &self.node {
+            Node::Array { elements, .. } => elements.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Item> {
+        match &mut self.node {
+            Node::Array { elements, .. } => elements.get_mut(index),
+            _ => None,
+        }
+    }
+
+    /// Set the member `key` of an object, adding it after the last member
+    /// and formatted like it when missing. Panics for anything but objects.
+    pub fn insert(&mut self, key: &str, value: impl ToJson) -> &mut Item {
+        if let Some(item) = self.get_mut(key) {
+            item.set(value);
+            // reborrowed, returning `item` would keep `self` borrowed
+            return self.get_mut(key).expect("the member was just found");
+        }
+        let kind = self.node.kind();
+        let Node::Object { members, inner } = &mut self.node else {
+            panic!("cannot insert a member into {kind}");
+        };
+
+        let mut raw_key = String::new();
+        write_string(&mut raw_key, key).expect("writing to a String never fails");
+        let mut member = Member {
+            before_key: String::new(),
+            raw_key,
+            key: key.to_owned(),
+            after_key: String::new(),
+            value: Item::new(Node::from_value(value.to_json())),
+        };
+        match members.last_mut() {
+            Some(last) => {
+                let (comment, closing) = split_after(&mut last.value.after);
+                member.before_key = comment + &separator(members.iter().map(|m| &*m.before_key));
+                let last = members.last().expect("members isn't empty");
+                member.after_key.clone_from(&last.after_key);
+                member.value.before.clone_from(&last.value.before);
+                member.value.after = closing;
+            }
+            None => {
+                member.value.before = String::from(" ");
+                member.value.after = mem::take(inner);
+            }
+        }
+        members.push(member);
+        &mut members.last_mut().expect("a member was just added").value
+    }
+
+    /// Remove the member `key` of an object, every one if the key is
+    /// repeated, returning the value of the last. Panics for anything but
+    /// objects.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let kind = self.node.kind();
+        let Node::Object { members, inner } = &mut self.node else {
+            panic!("cannot remove a member from {kind}");
+        };
+
+        let mut removed = None;
+        while let Some(i) = members.iter().position(|member| member.key == key) {
+            let member = members.remove(i);
+            // whatever was around the member at either end of the object
+            // now goes around its neighbour
+            if i == 0
+                && let Some(first) = members.first_mut()
+            {
+                first.before_key = member.before_key;
+            }
+            removed = Some(member.value.to_value());
+            if i == members.len() {
+                match members.last_mut() {
+                    Some(last) => last.value.after = member.value.after,
+                    None => *inner = member.value.after,
+                }
+            }
+        }
+        removed
+    }
+
+    /// Add an element at the end of an array, formatted like the last one.
+    /// Panics for anything but arrays.
+    pub fn push(&mut self, value: impl ToJson) -> &mut Item {
+        let kind = self.node.kind();
+        let Node::Array { elements, inner } = &mut self.node else {
+            panic!("cannot push onto {kind}");
+        };
+
+        let mut element = Item::new(Node::from_value(value.to_json()));
+        match elements.last_mut() {
+            Some(last) => {
+                let (comment, closing) = split_after(&mut last.after);
+                element.before = comment + &separator(elements.iter().map(|e| &*e.before));
+                element.after = closing;
+            }
+            None => element.after = mem::take(inner),
+        }
+        elements.push(element);
+        elements.last_mut().expect("an element was just added")
+    }
+
+    fn write(&self, out: &mut impl Write) -> fmt::Result {
+        out.write_str(&self.before)?;
+        match &self.node {
+            Node::Scalar { raw, .. } => out.write_str(raw)?,
+            Node::Array { elements, inner } => {
+                out.write_char('[')?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(',')?;
+                    }
+                    element.write(out)?;
+                }
+                out.write_str(inner)?;
+                out.write_char(']')?;
+            }
+            Node::Object { members, inner } => {
+                out.write_char('{')?;
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(',')?;
+                    }
+                    out.write_str(&member.before_key)?;
+                    out.write_str(&member.raw_key)?;
+                    out.write_str(&member.after_key)?;
+                    out.write_char(':')?;
+                    member.value.write(out)?;
+                }
+                out.write_str(inner)?;
+                out.write_char('}')?;
+            }
+        }
+        out.write_str(&self.after)
+    }
+}
+
+/// Writes the value with the whitespace and comments around it
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f)
+    }
+}
+
+/// `doc["key"]`, panicking when there is no such member
+impl Index<&str> for Item {
+    type Output = Item;
+
+    fn index(&self, key: &str) -> &Item {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no member {key:?} to index"))
+    }
+}
+
+/// `doc["key"]` for editing, adding the member as null when missing.
+/// Panics for anything but objects.
+impl IndexMut<&str> for Item {
+    fn index_mut(&mut self, key: &str) -> &mut Item {
+        if self.get(key).is_none() {
+            return self.insert(key, Value::Null);
+        }
+        self.get_mut(key).expect("the member was just found")
+    }
+}
+
+/// `doc[0]`, panicking when there is no such element
+impl Index<usize> for Item {
+    type Output = Item;
+
+    fn index(&self, index: usize) -> &Item {
+        self.get_index(index)
+            .unwrap_or_else(|| panic!("no element {index} to index"))
+    }
+}
+
+impl IndexMut<usize> for Item {
+    fn index_mut(&mut self, index: usize) -> &mut Item {
+        self.get_index_mut(index)
+            .unwrap_or_else(|| panic!("no element {index} to index"))
+    }
+}
+
+impl Index<&str> for Document {
+    type Output = Item;
+
+    fn index(&self, key: &str) -> &Item {
+        &self.root[key]
+    }
+}
+
+impl IndexMut<&str> for Document {
+    fn index_mut(&mut self, key: &str) -> &mut Item {
+        &mut self.root[key]
+    }
+}
+
+impl Index<usize> for Document {
+    type Output = Item;
+
+    fn index(&self, index: usize) -> &Item {
+        &self.root[index]
+    }
+}
+
+impl IndexMut<usize> for Document {
+    fn index_mut(&mut self, index: usize) -> &mut Item {
+        &mut self.root[index]
+    }
+}
+
+impl Node {
+    /// Name of the kind of value, for messages
+    fn kind(&self) -> &'static str {
+        match self {
+            Node::Scalar { value, .. } => value.kind(),
+            Node::Array { .. } => "an array",
+            Node::Object { .. } => "an object",
+        }
+    }
+
+    /// A node written as compact JSON
+    fn from_value(value: Value) -> Node {
+        match value {
+            Value::Array(values) => Node::Array {
+                elements: values
+                    .into_iter()
+                    .map(|value| Item::new(Node::from_value(value)))
+                    .collect(),
+                inner: String::new(),
+            },
+            Value::Object(map) => Node::Object {
+                members: map
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let mut raw_key = String::new();
+                        write_string(&mut raw_key, &key).expect("writing to a String never fails");
+                        Member {
+                            before_key: String::new(),
+                            raw_key,
+                            key,
+                            after_key: String::new(),
+                            value: Item::new(Node::from_value(value)),
+                        }
+                    })
+                    .collect(),
+                inner: String::new(),
+            },
+            value => {
+                let mut raw = String::new();
+                write_value(&mut raw, &value).expect("writing to a String never fails");
+                Node::Scalar { raw, value }
+            }
+        }
+    }
+}
+
+/// Split the text after the last element or member of a container, which
+/// runs up to its closing bracket, into what stays with that element, a
+/// comment on its line, and the line break and indentation before the
+/// bracket, taking both out of `after`
+fn split_after(after: &mut String) -> (String, String) {
+    let text = mem::take(after);
+    let (comment, closing) = text.split_at(last_line_break(&text).unwrap_or(text.len()));
+    if comment.trim().is_empty() {
+        (String::new(), text)
+    } else {
+        (comment.to_owned(), closing.to_owned())
+    }
+}
+
+/// Where the last line break in the trivia `text` is, leaving out those
+/// inside block comments
+fn last_line_break(text: &str) -> Option<usize> {
+    let mut last = None;
+    let mut rest = text;
+    while let Some(at) = rest.find(['\n', '/']) {
+        let start = text.len() - rest.len() + at;
+        rest = &rest[at..];
+        if rest.starts_with('\n') {
+            last = Some(start);
+            rest = &rest[1..];
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        } else {
+            rest = &rest[1..];
+        }
+    }
+    last
+}
+
+/// The text to put before a new element or member, copied from the ones
+/// before it, given the text before each of those
+fn separator<'s>(befores: impl ExactSizeIterator<Item = &'s str>) -> String {
+    let count = befores.len();
+    match befores.last() {
+        // the first element follows the bracket, only a line break carries over
+        Some(before) if count > 1 || before.contains('\n') => before.to_owned(),
+        _ => String::from(" "),
+    }
+}
+
+/// Builds the items of a document from its tokens, keeping the text
+/// between them
+struct Builder<'a> {
+    input: &'a str,
+    tokens: Peekable<SpannedTokens<'a>>,
+    /// where the last token taken ends
+    pos: usize,
+}
+
+impl<'a> Builder<'a> {
+    /// Take the next token, returning it with its span and the text before it
+    fn next(&mut self) -> Result<(Token<'a>, Range<usize>, String), JsonError> {
+        let token = self.tokens.next().ok_or(TokenParseError::UnexpectedEnd)??;
+        let before = self.input[self.pos..token.span.start].to_owned();
+        self.pos = token.span.end;
+        Ok((token.token, token.span, before))
+    }
+
+    fn next_is(&mut self, expected: &Token<'_>) -> bool {
+        matches!(self.tokens.peek(), Some(Ok(token)) if token.token == *expected)
+    }
+
+    /// Build the next value, `depth` being the number of arrays and objects
+    /// it is in
+    fn item(&mut self, depth: usize) -> Result<Item, JsonError> {
+        let (token, span, before) = self.next()?;
+        let node = match token {
+            Token::LeftSquareBracket | Token::LeftCurlyBracket if depth == DEFAULT_MAX_DEPTH => {
+                return Err(TokenParseError::DepthLimitExceeded.into());
+            }
+            Token::LeftSquareBracket => self.array(depth)?,
+            Token::LeftCurlyBracket => self.object(depth)?,
+            Token::Null => self.scalar(span, Value::Null),
+            Token::False => self.scalar(span, Value::Boolean(false)),
+            Token::True => self.scalar(span, Value::Boolean(true)),
+            Token::Number(n) => self.scalar(span, Value::Number(n)),
+            Token::String(raw) => {
+                let value = Value::String(unescape(raw)?.into());
+                self.scalar(span, value)
+            }
+            _ => return Err(TokenParseError::ExpectedValue.into()),
+        };
+
+        Ok(Item {
+            before,
+            node,
+            after: String::new(),
+        })
+    }
+
+    fn scalar(&self, span: Range<usize>, value: Value) -> Node {
+        Node::Scalar {
+            raw: self.input[span].to_owned(),
+            value,
+        }
+    }
+
+    /// Build an array whose `[` was just taken
+    fn array(&mut self, depth: usize) -> Result<Node, JsonError> {
+        if self.next_is(&Token::RightSquareBracket) {
+            let (_, _, inner) = self.next()?;
+            return Ok(Node::Array {
+                elements: Vec::new(),
+                inner,
+            });
+        }
+
+        let mut elements = Vec::new();
+        loop {
+            let mut element = self.item(depth + 1)?;
+            let (token, _, after) = self.next()?;
+            element.after = after;
+            elements.push(element);
+            match token {
+                Token::Comma => {}
+                Token::RightSquareBracket => break,
+                _ => return Err(TokenParseError::ExpectedComma.into()),
+            }
+        }
+        Ok(Node::Array {
+            elements,
+            inner: String::new(),
+        })
+    }
+
+    /// Build an object whose `{` was just taken
+    fn object(&mut self, depth: usize) -> Result<Node, JsonError> {
+        if self.next_is(&Token::RightCurlyBracket) {
+            let (_, _, inner) = self.next()?;
+            return Ok(Node::Object {
+                members: Vec::new(),
+                inner,
+            });
+        }
+
+        let mut members = Vec::new();
+        loop {
+            let (token, key_span, before_key) = self.next()?;
+            let Token::String(raw) = token else {
+                return Err(TokenParseError::ExpectedProperty.into());
+            };
+            let (token, _, after_key) = self.next()?;
+            if token != Token::Colon {
+                return Err(TokenParseError::ExpectedColon.into());
+            }
+            let mut value = self.item(depth + 1)?;
+            let (token, _, after) = self.next()?;
+            value.after = after;
+            members.push(Member {
+                before_key,
+                raw_key: self.input[key_span].to_owned(),
+                key: unescape(raw)?.into_owned(),
+                after_key,
+                value,
+            });
+            match token {
+                Token::Comma => {}
+                Token::RightCurlyBracket => break,
+                _ => return Err(TokenParseError::ExpectedComma.into()),
+            }
+        }
+        Ok(Node::Object {
+            members,
+            inner: String::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Document;
+    use crate::prelude::*;
+    use crate::{parse, parse_jsonc};
+
+    const CONFIG: &str = "// settings
+{
+    \"name\" : \"app\",   // shown in the title
+    \"ports\": [ 80,
+               443 ],
+    /* empty for now */
+    \"extra\": {},
+    \"escaped\": \"\\u0041\"
+}
+";
+
+    #[test]
+    fn unedited_documents_are_written_back_unchanged() {
+        let doc = Document::parse(CONFIG).unwrap();
+
+        assert_eq!(doc.to_string(), CONFIG);
+        assert_eq!(doc.to_value(), parse_jsonc(CONFIG).unwrap());
+        assert_eq!(doc["escaped"].to_value(), json!("A"));
+        for input in ["", "[1, 2", "[1 2]", "{\"a\" 1}", "{1: 2}", "[1] 2"] {
+            assert_eq!(Document::parse(input).err(), parse(input).err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn edits_leave_the_rest_unchanged() {
+        let mut doc = Document::parse(CONFIG).unwrap();
+        doc["name"].set("web");
+        doc["ports"].push(8080);
+        doc["extra"].insert("debug", json!({"level": 2}));
+        doc["ports"][0].set(json!(null));
+        doc.root_mut().insert("added", true);
+
+        assert_eq!(
+            doc.to_string(),
+            "// settings
+{
+    \"name\" : \"web\",   // shown in the title
+    \"ports\": [ null,
+               443,
+               8080 ],
+    /* empty for now */
+    \"extra\": {\"debug\": {\"level\":2}},
+    \"escaped\": \"\\u0041\",
+    \"added\": true
+}
+"
+        );
+    }
+
+    #[test]
+    fn inserting_after_a_commented_member_keeps_the_comment_in_place() {
+        let mut doc = Document::parse("{\n  \"a\": 1 // one\n}").unwrap();
+        doc["b"].set(2);
+
+        assert_eq!(doc.to_string(), "{\n  \"a\": 1, // one\n  \"b\": 2\n}");
+        assert_eq!(doc.to_value(), json!({"a": 1, "b": 2}));
+    }
+
+    #[test]
+    fn line_breaks_inside_block_comments_stay_with_the_comment() {
+        let mut doc = Document::parse("{\"a\": 1 /* note\n */}").unwrap();
+        doc.root_mut().insert("b", 2);
+        assert_eq!(doc.to_string(), "{\"a\": 1, /* note\n */ \"b\": 2}");
+        assert_eq!(doc.to_value(), json!({"a": 1, "b": 2}));
+
+        let mut doc = Document::parse("[1 /* x\n */]").unwrap();
+        doc.root_mut().push(2);
+        assert_eq!(doc.to_string(), "[1, /* x\n */ 2]");
+        assert_eq!(doc.to_value(), json!([1, 2]));
+
+        let mut doc = Document::parse("[\n  1 /* x\n */ // y\n]").unwrap();
+        doc.root_mut().push(2);
+        assert_eq!(doc.to_string(), "[\n  1, /* x\n */ // y\n  2\n]");
+    }
+
+    #[test]
+    fn removing_members_keeps_the_brackets_in_place() {
+        let mut doc = Document::parse("{\n  \"a\": 1,\n  \"b\": [],\n  \"a\": 3\n}").unwrap();
+
+        assert_eq!(doc.root_mut().remove("a"), Some(json!(3)));
+        assert_eq!(doc.to_string(), "{\n  \"b\": []\n}");
+        assert_eq!(doc.root_mut().remove("missing"), None);
+        doc.root_mut().remove("b");
+        assert_eq!(doc.to_string(), "{\n}");
+    }
+}
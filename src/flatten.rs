@@ -0,0 +1,181 @@
+// Converting between nested values and single-level objects keyed by path,
+// for key/value stores and spreadsheets
+
+use super::Map;
+use super::Value;
+use super::prelude::*;
+
+/// Step of a flattened key: `.name` or `[index]`
+enum Segment<'k> {
+    Key(&'k str),
+    Index(usize),
+}
+
+/// The segments of a flattened key
+///
+/// A trailing `[digits]` is an index and anything else between dots is a
+/// key, so keys that themselves contain dots or end in brackets don't
+/// survive a round trip. Indices above `max_index` are left in the key.
+fn segments(key: &str, max_index: usize) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    for (n, mut part) in key.split('.').enumerate() {
+        let mut indices = Vec::new();
+        while let Some((name, index)) = part
+            .strip_suffix(']')
+            .and_then(|inner| inner.rsplit_once('['))
+        {
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                break;
+            }
+            let Some(index) = index.parse().ok().filter(|&i| i <= max_index) else {
+                break;
+            };
+            indices.push(index);
+            part = name;
+        }
+        // the root itself has no name
+        if !(n == 0 && part.is_empty() && (!indices.is_empty() || key.is_empty())) {
+            segments.push(Segment::Key(part));
+        }
+        segments.extend(indices.into_iter().rev().map(Segment::Index));
+    }
+    segments
+}
+
+fn flatten_into(value: &Value, key: String, flat: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (name, member) in map {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{key}.{name}")
+                };
+                flatten_into(member, key, flat);
+            }
+        }
+        Value::Array(values) if !values.is_empty() => {
+            for (i, element) in values.iter().enumerate() {
+                flatten_into(element, format!("{key}[{i}]"), flat);
+            }
+        }
+        value => {
+            flat.insert(key, value.clone());
+        }
+    }
+}
+
+impl Value {
+    /// A single-level object with a member for every scalar and empty
+    /// container in this value, keyed by its path like `a.b[0].c`
+    ///
+    /// A scalar at the root is keyed by the empty string.
+    pub fn flatten(&self) -> Value {
+        let mut flat = Map::default();
+        flatten_into(self, String::new(), &mut flat);
+        Value::Object(flat)
+    }
+
+    /// The nested value a [`flatten`](Value::flatten)ed object came from
+    ///
+    /// Arrays are padded with nulls up to the highest index given, and where
+    /// keys disagree on whether something is a container, the longer path
+    /// wins. An index can't be more than the number of keys, which is all a
+    /// flattened array needs; a bigger one is kept as part of the name, so
+    /// a key like `a[99999999999]` doesn't pad an array out to that length.
+    /// Anything but an object is returned unchanged.
+    pub fn unflatten(&self) -> Value {
+        let Value::Object(flat) = self else {
+            return self.clone();
+        };
+        // sorted so containers replace scalars at the same path
+        let mut keys: Vec<&String> = flat.keys().collect();
+        keys.sort();
+
+        let mut root = Value::Null;
+        for key in keys {
+            let mut slot = &mut root;
+            for segment in segments(key, flat.len()) {
+                slot = match segment {
+                    Segment::Key(name) => {
+                        if !slot.is_object() {
+                            *slot = Value::Object(Map::default());
+                        }
+                        slot.entry(name).or_insert(Value::Null)
+                    }
+                    Segment::Index(i) => {
+                        if !slot.is_array() {
+                            *slot = Value::Array(Vec::new());
+                        }
+                        let values = slot.as_array_mut().expect("made an array above");
+                        if values.len() <= i {
+                            values.resize(i + 1, Value::Null);
+                        }
+                        &mut values[i]
+                    }
+                };
+            }
+            *slot = flat[key].clone();
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn flatten_keys_by_path() {
+        let value = json!({"a": {"b": [{"c": 1}, 2]}, "d": [], "e": {}, "f": null});
+
+        assert_eq!(
+            value.flatten(),
+            json!({"a.b[0].c": 1, "a.b[1]": 2, "d": [], "e": {}, "f": null})
+        );
+        assert_eq!(json!([[true]]).flatten(), json!({"[0][0]": true}));
+        assert_eq!(json!("x").flatten(), json!({"": "x"}));
+    }
+
+    #[test]
+    fn unflatten_round_trips() {
+        for value in [
+            json!({"a": {"b": [{"c": 1}, 2]}, "d": [], "e": {}, "f": null}),
+            json!([[true], {"x": [null, 0]}]),
+            json!(1),
+        ] {
+            assert_eq!(value.flatten().unflatten(), value);
+        }
+    }
+
+    #[test]
+    fn unflatten_pads_and_resolves_conflicts() {
+        let flat = json!({"a[2]": 1, "b": 1, "b.c": 2, "k[x]": 3});
+
+        assert_eq!(
+            flat.unflatten(),
+            json!({"a": [null, null, 1], "b": {"c": 2}, "k[x]": 3})
+        );
+        assert_eq!(Value::Null.unflatten(), Value::Null);
+    }
+
+    #[test]
+    fn unflatten_keeps_huge_indices_in_the_name() {
+        let flat = json!({
+            "a[99999999999]": 1,
+            "b[18446744073709551615]": 2,
+            "c[0][3]": 3,
+            "d[1]": 4
+        });
+
+        assert_eq!(
+            flat.unflatten(),
+            json!({
+                "a[99999999999]": 1,
+                "b[18446744073709551615]": 2,
+                "c": [[null, null, null, 3]],
+                "d": [null, 4]
+            })
+        );
+    }
+}
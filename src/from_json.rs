@@ -0,0 +1,208 @@
+// Reading parsed values into Rust types without serde
+
+use alloc::collections::BTreeMap;
+use core::hash::BuildHasher;
+
+use super::convert::ConversionError;
+use super::map::HashMap;
+use super::prelude::*;
+use super::{JsonError, Value};
+
+/// A type that can be read from a [`Value`]
+///
+/// Implemented for the standard library's scalars, strings, `Option`s (null
+/// being `None`), `Vec`s and string-keyed maps. Structs read their fields
+/// with [`Value::field`], which reports where a mismatch happened:
+///
+/// ```
+/// use parser_json_rs::{FromJson, JsonError, Value, parse};
+///
+/// struct Server {
+///     host: String,
+///     port: u16,
+///     tags: Option<Vec<String>>,
+/// }
+///
+/// impl FromJson for Server {
+///     fn from_json(value: &Value) -> Result<Self, JsonError> {
+///         Ok(Server {
+///             host: value.field("host")?,
+///             port: value.field("port")?,
+///             tags: value.field("tags")?,
+///         })
+///     }
+/// }
+///
+/// let server = Server::from_json(&parse(r#"{"host": "a", "port": 80}"#).unwrap()).unwrap();
+/// assert_eq!((server.host.as_str(), server.port, server.tags), ("a", 80, None));
+///
+/// let err = Server::from_json(&parse(r#"{"host": "a", "port": -1}"#).unwrap());
+/// assert!(matches!(err, Err(JsonError::Conversion(e)) if e.path == "/port"));
+/// ```
+pub trait FromJson: Sized {
+    fn from_json(value: &Value) -> Result<Self, JsonError>;
+}
+
+impl Value {
+    /// The member `key` of an object read as a `T`, reading a missing member
+    /// as null so that optional fields can be left out
+    pub fn field<T: FromJson>(&self, key: &str) -> Result<T, JsonError> {
+        let Value::Object(members) = self else {
+            return Err(ConversionError::new("an object", self).into());
+        };
+        T::from_json(members.get(key).unwrap_or(&Value::Null)).map_err(|err| within(err, key))
+    }
+}
+
+/// Add `segment` to the path of a conversion error
+pub fn within(err: JsonError, segment: &str) -> JsonError {
+    match err {
+        JsonError::Conversion(err) => JsonError::Conversion(err.within(segment)),
+        err => err,
+    }
+}
+
+impl FromJson for Value {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        Ok(value
+            .as_bool()
+            .ok_or_else(|| ConversionError::new("a boolean", value))?)
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value.as_str() {
+            Some(s) => Ok(s.to_owned()),
+            None => Err(ConversionError::new("a string", value).into()),
+        }
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        Ok(value
+            .as_f64()
+            .ok_or_else(|| ConversionError::new("a number", value))?)
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        f64::from_json(value).map(|n| n as f32)
+    }
+}
+
+macro_rules! from_json_integer {
+    ($wide:ident: $($ty:ty),*) => {
+        $(
+            impl FromJson for $ty {
+                fn from_json(value: &Value) -> Result<Self, JsonError> {
+                    Ok(value
+                        .$wide()
+                        .and_then(|n| <$ty>::try_from(n).ok())
+                        .ok_or_else(|| ConversionError::new(stringify!($ty), value))?)
+                }
+            }
+        )*
+    };
+}
+
+from_json_integer!(as_i64: i8, i16, i32, i64, isize);
+from_json_integer!(as_u64: u8, u16, u32, u64, usize);
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        let Value::Array(values) = value else {
+            return Err(ConversionError::new("an array", value).into());
+        };
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| T::from_json(value).map_err(|err| within(err, &i.to_string())))
+            .collect()
+    }
+}
+
+impl<T: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, T, S> {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        members(value)
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        members(value)
+    }
+}
+
+/// The members of an object read as `T`s into a map
+fn members<T: FromJson, M: FromIterator<(String, T)>>(value: &Value) -> Result<M, JsonError> {
+    let Value::Object(members) = value else {
+        return Err(ConversionError::new("an object", value).into());
+    };
+    members
+        .iter()
+        .map(|(key, value)| match T::from_json(value) {
+            Ok(value) => Ok((key.clone(), value)),
+            Err(err) => Err(within(err, key)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use alloc::collections::BTreeMap;
+
+    use super::FromJson;
+    use crate::map::HashMap;
+    use crate::{ConversionError, JsonError, Value};
+
+    #[test]
+    fn from_json_primitives_and_containers() {
+        assert_eq!(u8::from_json(&json!(7)), Ok(7));
+        assert_eq!(Option::<bool>::from_json(&json!(null)), Ok(None));
+        assert_eq!(
+            Vec::<Option<String>>::from_json(&json!(["a", null])),
+            Ok(vec![Some(String::from("a")), None])
+        );
+        let map = BTreeMap::<String, f64>::from_json(&json!({"b": 2, "a": 1})).unwrap();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [(String::from("a"), 1.0), (String::from("b"), 2.0)]
+        );
+        assert!(HashMap::<String, Value>::from_json(&json!([])).is_err());
+    }
+
+    #[test]
+    fn from_json_errors_carry_the_path() {
+        let value = json!({"servers": [{"port": 80}, {"port": "x"}]});
+        let err = value.field::<Vec<HashMap<String, u16>>>("servers");
+
+        assert_eq!(
+            err,
+            Err(JsonError::Conversion(ConversionError {
+                path: String::from("/servers/1/port"),
+                expected: "u16",
+                found: String::from("a string"),
+            }))
+        );
+        assert_eq!(value.field::<Option<u8>>("missing"), Ok(None));
+    }
+}
@@ -0,0 +1,366 @@
+// Structural hashing and equality of streamed documents
+
+use alloc::collections::BTreeMap;
+use std::io::Read;
+
+use super::parser::TokenParseError;
+use super::prelude::*;
+use super::stream::{Events, JsonEvent, events_from_reader};
+use super::string::from_string;
+use super::tokenize::Token;
+use super::{JsonError, Map, Value};
+
+const NULL_TAG: u8 = 0;
+const BOOLEAN_TAG: u8 = 1;
+const NUMBER_TAG: u8 = 2;
+const STRING_TAG: u8 = 3;
+const ARRAY_TAG: u8 = 4;
+const OBJECT_TAG: u8 = 5;
+const KEY_TAG: u8 = 6;
+
+/// 64-bit FNV-1a, chosen because its output is fixed by specification and
+/// so stays the same across platforms and releases
+struct Fnv(u64);
+
+impl Fnv {
+    fn new(tag: u8) -> Self {
+        let mut fnv = Fnv(0xcbf2_9ce4_8422_2325);
+        fnv.write(&[tag]);
+        fnv
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Spread the bits of an object member hash before members are summed, so
+/// that the order-independent sum doesn't cancel out related members
+fn mix(mut h: u64) -> u64 {
+    h ^= h >> 30;
+    h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h ^= h >> 27;
+    h = h.wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+enum Frame {
+    Array(Fnv),
+    /// the hash of the value of each key so far, a repeated key replacing
+    /// the value before it as it does when parsing
+    Object {
+        members: BTreeMap<String, u64>,
+        key: Option<String>,
+    },
+}
+
+fn hash_events<'a, I, E>(mut events: Events<I>) -> Result<u64, JsonError>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    let mut stack: Vec<Frame> = Vec::new();
+
+    for event in &mut events {
+        let hash = match event? {
+            JsonEvent::StartArray => {
+                stack.push(Frame::Array(Fnv::new(ARRAY_TAG)));
+                continue;
+            }
+            JsonEvent::StartObject => {
+                stack.push(Frame::Object {
+                    members: BTreeMap::new(),
+                    key: None,
+                });
+                continue;
+            }
+            JsonEvent::Key(name) => {
+                if let Some(Frame::Object { key, .. }) = stack.last_mut() {
+                    *key = Some(name);
+                }
+                continue;
+            }
+            JsonEvent::EndArray => match stack.pop() {
+                Some(Frame::Array(fnv)) => fnv.finish(),
+                _ => unreachable!("events are balanced"),
+            },
+            JsonEvent::EndObject => match stack.pop() {
+                Some(Frame::Object { members, .. }) => {
+                    let mut sum = 0u64;
+                    for (key, hash) in &members {
+                        let mut member = Fnv::new(KEY_TAG);
+                        member.write(key.as_bytes());
+                        member.write_u64(*hash);
+                        sum = sum.wrapping_add(mix(member.finish()));
+                    }
+                    let mut fnv = Fnv::new(OBJECT_TAG);
+                    fnv.write_u64(members.len() as u64);
+                    fnv.write_u64(sum);
+                    fnv.finish()
+                }
+                _ => unreachable!("events are balanced"),
+            },
+            JsonEvent::Null => Fnv::new(NULL_TAG).finish(),
+            JsonEvent::Boolean(b) => {
+                let mut fnv = Fnv::new(BOOLEAN_TAG);
+                fnv.write(&[u8::from(b)]);
+                fnv.finish()
+            }
+            JsonEvent::Number(n) => {
+                let mut fnv = Fnv::new(NUMBER_TAG);
+                // `-0` and `0` are the same number
+                let n = if n == 0.0 { 0.0 } else { n };
+                fnv.write(&n.to_bits().to_le_bytes());
+                fnv.finish()
+            }
+            JsonEvent::String(s) => {
+                let mut fnv = Fnv::new(STRING_TAG);
+                fnv.write(s.as_bytes());
+                fnv.finish()
+            }
+        };
+
+        // a value is complete, fold it into its container
+        match stack.last_mut() {
+            None => {
+                events.finish()?;
+                return Ok(hash);
+            }
+            Some(Frame::Array(fnv)) => fnv.write_u64(hash),
+            Some(Frame::Object { members, key }) => {
+                let key = key.take().expect("values in objects follow a key");
+                members.insert(key, hash);
+            }
+        }
+    }
+
+    Err(TokenParseError::UnexpectedEnd.into())
+}
+
+/// Canonical structural hash of the JSON document read from `reader`
+///
+/// Object members are combined regardless of their order, strings are hashed
+/// after resolving escapes and numbers by value, so documents differing only
+/// in key order, whitespace or escaping hash the same. A repeated key counts
+/// with its last value, as when parsing, and anything but whitespace after the
+/// document is an error. The hash does not depend on the platform or crate
+/// version and can be stored with a payload.
+///
+/// The keys of the objects being read are held until each object ends, so
+/// memory use grows with the width of the objects, not the whole document.
+pub fn stream_hash<R: Read>(reader: R) -> Result<u64, JsonError> {
+    hash_events(events_from_reader(reader))
+}
+
+/// Whether the JSON documents read from `a` and `b` are structurally equal,
+/// by the same rules as [`stream_hash`]
+///
+/// The documents are read side by side and reading stops at the first
+/// difference, so input that is malformed past it compares unequal rather
+/// than failing. Arrays and other values are compared as they stream, while
+/// each object is read whole from both documents and compared as a map, as
+/// its members can come in any order. Memory use grows with the size of the
+/// largest object.
+pub fn stream_equal<A: Read, B: Read>(a: A, b: B) -> Result<bool, JsonError> {
+    let mut a = events_from_reader(a);
+    let mut b = events_from_reader(b);
+    let mut depth = 0usize;
+
+    loop {
+        match (next_event(&mut a)?, next_event(&mut b)?) {
+            (JsonEvent::StartObject, JsonEvent::StartObject) => {
+                let equal = read_object(&mut a)? == read_object(&mut b)?;
+                if !equal {
+                    return Ok(false);
+                }
+            }
+            (x, y) if x != y => return Ok(false),
+            (JsonEvent::StartArray, _) => {
+                depth += 1;
+                continue;
+            }
+            (JsonEvent::EndArray, _) => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            a.finish()?;
+            b.finish()?;
+            return Ok(true);
+        }
+    }
+}
+
+fn next_event<'a, I, E>(events: &mut Events<I>) -> Result<JsonEvent, JsonError>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    events.next().ok_or(TokenParseError::UnexpectedEnd)?
+}
+
+/// A container of [`read_object`] whose members are still being read
+enum Open {
+    Array(Vec<Value>),
+    Object(Map<String, Value>, Option<String>),
+}
+
+/// Read the rest of the object whose start was the last event
+fn read_object<'a, I, E>(events: &mut Events<I>) -> Result<Value, JsonError>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    let mut stack = vec![Open::Object(Map::default(), None)];
+
+    loop {
+        let value = match next_event(events)? {
+            JsonEvent::StartArray => {
+                stack.push(Open::Array(Vec::new()));
+                continue;
+            }
+            JsonEvent::StartObject => {
+                stack.push(Open::Object(Map::default(), None));
+                continue;
+            }
+            JsonEvent::Key(name) => {
+                if let Some(Open::Object(_, key)) = stack.last_mut() {
+                    *key = Some(name);
+                }
+                continue;
+            }
+            JsonEvent::EndArray | JsonEvent::EndObject => match stack.pop() {
+                Some(Open::Array(values)) => Value::Array(values),
+                Some(Open::Object(map, _)) => Value::Object(map),
+                None => unreachable!("events are balanced"),
+            },
+            JsonEvent::Null => Value::Null,
+            JsonEvent::Boolean(b) => Value::Boolean(b),
+            JsonEvent::Number(n) => Value::Number(n),
+            JsonEvent::String(s) => Value::String(from_string(s)),
+        };
+
+        match stack.last_mut() {
+            None => return Ok(value),
+            Some(Open::Array(values)) => values.push(value),
+            Some(Open::Object(map, key)) => {
+                let key = key.take().expect("values in objects follow a key");
+                map.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stream_equal, stream_hash};
+    use crate::parse;
+    use crate::parser::TokenParseError;
+
+    fn hash(input: &str) -> u64 {
+        stream_hash(input.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn hash_ignores_key_order_and_whitespace() {
+        let a = r#"{"a": [1, 2], "b": {"c": null, "d": true}}"#;
+        let b = "{\"b\":{\"d\":true,\"c\":null},\n  \"a\":[1,2]}\n";
+
+        assert_eq!(hash(a), hash(b));
+    }
+
+    #[test]
+    fn hash_resolves_escapes() {
+        assert_eq!(hash(r#"["\u0041"]"#), hash(r#"["A"]"#));
+    }
+
+    #[test]
+    fn hash_respects_array_order() {
+        assert_ne!(hash("[1, 2]"), hash("[2, 1]"));
+    }
+
+    #[test]
+    fn hash_distinguishes_types() {
+        assert_ne!(hash("[null]"), hash("[false]"));
+        assert_ne!(hash(r#"["1"]"#), hash("[1]"));
+        assert_ne!(hash("[]"), hash("{}"));
+    }
+
+    #[test]
+    fn hash_keeps_key_value_pairing() {
+        assert_ne!(hash(r#"{"a": 1, "b": 2}"#), hash(r#"{"a": 2, "b": 1}"#));
+    }
+
+    #[test]
+    fn equal_streams() {
+        let a = r#"{"id": 7, "tags": ["x", "y"]}"#;
+        let b = r#"{"tags": ["x", "y"], "id": 7.0}"#;
+        let c = r#"{"tags": ["y", "x"], "id": 7}"#;
+
+        assert!(stream_equal(a.as_bytes(), b.as_bytes()).unwrap());
+        assert!(!stream_equal(a.as_bytes(), c.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn equal_streams_by_the_hash_rules() {
+        let equal = |a: &str, b: &str| stream_equal(a.as_bytes(), b.as_bytes()).unwrap();
+
+        assert!(equal(r#"[{"a": 1, "a": 2}, -0]"#, r#"[{"a": 2}, 0]"#));
+        assert!(equal(
+            r#"{"a": {"b": ["\u0041"]}}"#,
+            r#"{"a": {"b": ["A"]}}"#
+        ));
+        assert!(!equal(r#"{"a": [1, {"b": 2}]}"#, r#"{"a": [1, {"b": 3}]}"#));
+        assert!(!equal("[1, 2]", "[1, 2, 3]"));
+        assert!(!equal("[[]]", "[{}]"));
+    }
+
+    #[test]
+    fn equal_streams_stop_at_the_first_difference() {
+        assert_eq!(
+            stream_equal("[1, 2]".as_bytes(), "[2, x".as_bytes()),
+            Ok(false)
+        );
+        assert_eq!(
+            stream_equal("[1, 2]".as_bytes(), "[1, x".as_bytes()).err(),
+            parse("[1, x").err()
+        );
+    }
+
+    #[test]
+    fn hash_rejects_malformed_input() {
+        assert!(stream_hash("[1, 2".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn hash_rejects_trailing_data() {
+        assert_eq!(
+            stream_hash("1 2".as_bytes()),
+            Err(TokenParseError::TrailingTokens.into())
+        );
+        assert_eq!(stream_hash("1 xyz".as_bytes()).err(), parse("1 xyz").err());
+        assert!(stream_hash("[1] [2]".as_bytes()).is_err());
+        assert!(stream_equal("[1]".as_bytes(), "[1] [2] garbage".as_bytes()).is_err());
+        assert_eq!(hash("[1] \n"), hash("[1]"));
+    }
+
+    #[test]
+    fn hash_keeps_the_last_duplicate_key() {
+        assert_eq!(hash(r#"{"a": 1, "a": 2}"#), hash(r#"{"a": 2}"#));
+        assert_ne!(hash(r#"{"a": 1, "a": 2}"#), hash(r#"{"a": 1}"#));
+        assert_eq!(
+            hash(r#"{"b": [], "a": 1, "b": null}"#),
+            hash(r#"{"a": 1, "b": null}"#)
+        );
+    }
+}
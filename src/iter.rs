@@ -0,0 +1,174 @@
+// Iterating the elements of arrays and the members of objects
+
+use core::slice;
+
+use super::Value;
+use super::map;
+use super::prelude::*;
+
+/// The elements of an array, see [`Value::members`]
+#[derive(Debug, Clone)]
+pub struct Members<'a>(slice::Iter<'a, Value>);
+
+/// The elements of an array, see [`Value::members_mut`]
+#[derive(Debug)]
+pub struct MembersMut<'a>(slice::IterMut<'a, Value>);
+
+/// The keys and values of an object, see [`Value::entries`]
+#[derive(Debug, Clone)]
+pub struct Entries<'a>(Option<map::Iter<'a, String, Value>>);
+
+/// The keys and values of an object, see [`Value::entries_mut`]
+#[derive(Debug)]
+pub struct EntriesMut<'a>(Option<map::IterMut<'a, String, Value>>);
+
+impl<'a> Iterator for Members<'a> {
+    type Item = &'a Value;
+
+    fn next(&mut self) -> Option<&'a Value> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> Iterator for MembersMut<'a> {
+    type Item = &'a mut Value;
+
+    fn next(&mut self) -> Option<&'a mut Value> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0
+            .as_ref()
+            .map_or((0, Some(0)), |iter| iter.size_hint())
+    }
+}
+
+impl<'a> Iterator for EntriesMut<'a> {
+    type Item = (&'a String, &'a mut Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0
+            .as_ref()
+            .map_or((0, Some(0)), |iter| iter.size_hint())
+    }
+}
+
+impl ExactSizeIterator for Members<'_> {}
+impl ExactSizeIterator for MembersMut<'_> {}
+impl ExactSizeIterator for Entries<'_> {}
+impl ExactSizeIterator for EntriesMut<'_> {}
+
+impl Value {
+    /// The elements of an array, nothing for any other value
+    pub fn members(&self) -> Members<'_> {
+        match self {
+            Value::Array(values) => Members(values.iter()),
+            _ => Members([].iter()),
+        }
+    }
+
+    pub fn members_mut(&mut self) -> MembersMut<'_> {
+        match self {
+            Value::Array(values) => MembersMut(values.iter_mut()),
+            _ => MembersMut([].iter_mut()),
+        }
+    }
+
+    /// The keys and values of an object, in key order with the `sorted-keys`
+    /// feature and in no particular order otherwise, nothing for any other
+    /// value
+    pub fn entries(&self) -> Entries<'_> {
+        match self {
+            Value::Object(map) => Entries(Some(map.iter())),
+            _ => Entries(None),
+        }
+    }
+
+    pub fn entries_mut(&mut self) -> EntriesMut<'_> {
+        match self {
+            Value::Object(map) => EntriesMut(Some(map.iter_mut())),
+            _ => EntriesMut(None),
+        }
+    }
+}
+
+/// `for element in &value`, the same as [`Value::members`]
+impl<'a> IntoIterator for &'a Value {
+    type Item = &'a Value;
+    type IntoIter = Members<'a>;
+
+    fn into_iter(self) -> Members<'a> {
+        self.members()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Value {
+    type Item = &'a mut Value;
+    type IntoIter = MembersMut<'a>;
+
+    fn into_iter(self) -> MembersMut<'a> {
+        self.members_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+    use crate::prelude::*;
+
+    #[test]
+    fn members_of_arrays_only() {
+        let mut value = json!([1, "a", null]);
+        for element in &mut value {
+            if element.is_null() {
+                *element = json!(0);
+            }
+        }
+
+        let members: Vec<&Value> = value.members().collect();
+        assert_eq!(members, [&json!(1), &json!("a"), &json!(0)]);
+        assert_eq!((&value).into_iter().len(), 3);
+        assert_eq!(json!({"a": 1}).members().count(), 0);
+        assert_eq!(json!("abc").members_mut().count(), 0);
+    }
+
+    #[test]
+    fn entries_of_objects_only() {
+        let mut value = json!({"a": 1, "b": 2});
+        for (_, member) in value.entries_mut() {
+            *member = json!(member.as_f64().unwrap() * 10.0);
+        }
+
+        let mut entries: Vec<(&String, &Value)> = value.entries().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        assert_eq!(
+            entries,
+            [
+                (&String::from("a"), &json!(10)),
+                (&String::from("b"), &json!(20))
+            ]
+        );
+        assert_eq!(json!([1]).entries().len(), 0);
+    }
+}
@@ -0,0 +1,801 @@
+//! [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) queries over a [`Value`]
+//!
+//! ```
+//! use parser_json_rs::{jsonpath::JsonPath, parse};
+//!
+//! let store = parse(r#"{"book": [{"title": "a", "price": 8}, {"title": "b", "price": 12}]}"#).unwrap();
+//! let path = JsonPath::compile("$.book[?(@.price < 10)].title").unwrap();
+//! let paths: Vec<String> = path.query(&store).into_iter().map(|m| m.path).collect();
+//!
+//! assert_eq!(paths, vec!["$['book'][0]['title']"]);
+//! ```
+//!
+//! Members of an object are visited in the order of their keys, so results
+//! don't depend on how the object happens to be stored.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use super::Value;
+use super::prelude::*;
+use super::string::from_string;
+
+/// Why a JSONPath expression could not be compiled
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    /// the expression doesn't start with `$`
+    ExpectedRoot,
+    /// byte offset of a character that makes no sense where it is
+    Unexpected(usize),
+    /// the expression stops in the middle of a segment or filter
+    UnexpectedEnd,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::ExpectedRoot => write!(f, "JSONPath must start with `$`"),
+            PathError::Unexpected(offset) => write!(f, "unexpected character at offset {offset}"),
+            PathError::UnexpectedEnd => write!(f, "JSONPath ends unexpectedly"),
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
+
+/// A compiled JSONPath expression, to be evaluated any number of times
+#[derive(Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+/// A value matched by a query
+#[derive(Debug, PartialEq)]
+pub struct Match<'v> {
+    /// normalized path of the value, as in `$['store']['book'][0]`
+    pub path: String,
+    pub value: &'v Value,
+}
+
+impl JsonPath {
+    /// Compile `path`, supporting names, wildcards, indices, slices, filters
+    /// and recursive descent
+    pub fn compile(path: &str) -> Result<Self, PathError> {
+        let mut compiler = Compiler { path, pos: 0 };
+        if !compiler.eat(b'$') {
+            return Err(PathError::ExpectedRoot);
+        }
+        let segments = compiler.segments()?;
+        match compiler.peek() {
+            None => Ok(JsonPath { segments }),
+            Some(_) => Err(PathError::Unexpected(compiler.pos)),
+        }
+    }
+
+    /// Every value of `root` the path matches, in document order
+    pub fn query<'v>(&self, root: &'v Value) -> Vec<Match<'v>> {
+        select(&self.segments, root, root)
+            .into_iter()
+            .map(|node| Match {
+                path: normalized_path(&node.path),
+                value: node.value,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Segment {
+    /// whether the selectors apply to every descendant, as in `..name`
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Expr),
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// a query matching anything at all
+    Exists(Query),
+    Compare(Operand, CompareOp, Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq)]
+enum Operand {
+    Literal(Value),
+    Query(Query),
+}
+
+/// Query inside a filter, from the root `$` or the current value `@`
+#[derive(Debug, PartialEq)]
+struct Query {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+struct Compiler<'p> {
+    path: &'p str,
+    pos: usize,
+}
+
+impl<'p> Compiler<'p> {
+    fn peek(&self) -> Option<u8> {
+        self.path.as_bytes().get(self.pos).copied()
+    }
+
+    fn rest(&self) -> &'p str {
+        &self.path[self.pos..]
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), PathError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let found = self.rest().starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|byte| matches!(byte, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> PathError {
+        if self.pos < self.path.len() {
+            PathError::Unexpected(self.pos)
+        } else {
+            PathError::UnexpectedEnd
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+        loop {
+            let segment = if self.eat_str("..") {
+                let selectors = if self.peek() == Some(b'[') {
+                    self.bracketed()?
+                } else {
+                    vec![self.dotted()?]
+                };
+                Segment {
+                    descendant: true,
+                    selectors,
+                }
+            } else if self.eat(b'.') {
+                Segment {
+                    descendant: false,
+                    selectors: vec![self.dotted()?],
+                }
+            } else if self.peek() == Some(b'[') {
+                Segment {
+                    descendant: false,
+                    selectors: self.bracketed()?,
+                }
+            } else {
+                return Ok(segments);
+            };
+            segments.push(segment);
+        }
+    }
+
+    /// `*` or a member name following a dot
+    fn dotted(&mut self) -> Result<Selector, PathError> {
+        if self.eat(b'*') {
+            return Ok(Selector::Wildcard);
+        }
+        let len = self
+            .rest()
+            .char_indices()
+            .find(|&(i, c)| {
+                !(c == '_'
+                    || c.is_ascii_alphabetic()
+                    || !c.is_ascii()
+                    || (i > 0 && c.is_ascii_digit()))
+            })
+            .map_or(self.rest().len(), |(i, _)| i);
+        if len == 0 {
+            return Err(self.unexpected());
+        }
+        let name = self.rest()[..len].to_owned();
+        self.pos += len;
+        Ok(Selector::Name(name))
+    }
+
+    /// `[selector, ...]`
+    fn bracketed(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect(b'[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(selectors);
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some(b'\'' | b'"') => Ok(Selector::Name(self.string()?)),
+            Some(b'*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some(b'?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.or()?))
+            }
+            Some(b'-' | b'0'..=b'9' | b':') => self.index_or_slice(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, PathError> {
+        let start = self.integer()?;
+        self.skip_whitespace();
+        if !self.eat(b':') {
+            return start.map(Selector::Index).ok_or_else(|| self.unexpected());
+        }
+        self.skip_whitespace();
+        let end = self.integer()?;
+        self.skip_whitespace();
+        let step = if self.eat(b':') {
+            self.skip_whitespace();
+            self.integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    /// An optional integer, without leading zeros
+    fn integer(&mut self) -> Result<Option<i64>, PathError> {
+        let start = self.pos;
+        let negative = self.eat(b'-');
+        let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        let text = &self.rest()[..digits];
+        self.pos += digits;
+
+        match text {
+            "" if !negative => Ok(None),
+            "0" if !negative => Ok(Some(0)),
+            "" | "0" => Err(PathError::Unexpected(start)),
+            _ if text.starts_with('0') => Err(PathError::Unexpected(start)),
+            _ => self.path[start..self.pos]
+                .parse()
+                .map(Some)
+                .map_err(|_| PathError::Unexpected(start)),
+        }
+    }
+
+    /// A single or double quoted string literal
+    fn string(&mut self) -> Result<String, PathError> {
+        let quote = self.peek().ok_or(PathError::UnexpectedEnd)?;
+        self.pos += 1;
+        let mut string = String::new();
+        loop {
+            let c = self.rest().chars().next().ok_or(PathError::UnexpectedEnd)?;
+            let at = self.pos;
+            self.pos += c.len_utf8();
+            match c {
+                c if c == char::from(quote) => return Ok(string),
+                '\\' => string.push(self.escape(at)?),
+                c => string.push(c),
+            }
+        }
+    }
+
+    /// The character escaped by the backslash at `at`
+    fn escape(&mut self, at: usize) -> Result<char, PathError> {
+        let Some(byte) = self.peek() else {
+            return Err(PathError::UnexpectedEnd);
+        };
+        self.pos += 1;
+        let c = match byte {
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'/' | b'\\' | b'\'' | b'"' => char::from(byte),
+            b'u' => {
+                let high = self.hex()?;
+                let code_points = if (0xd800..0xdc00).contains(&high) {
+                    if !self.eat_str("\\u") {
+                        return Err(PathError::Unexpected(at));
+                    }
+                    vec![high, self.hex()?]
+                } else {
+                    vec![high]
+                };
+                return char::decode_utf16(code_points)
+                    .next()
+                    .and_then(Result::ok)
+                    .ok_or(PathError::Unexpected(at));
+            }
+            _ => return Err(PathError::Unexpected(at)),
+        };
+        Ok(c)
+    }
+
+    fn hex(&mut self) -> Result<u16, PathError> {
+        let digits = self.rest().get(..4).ok_or(PathError::UnexpectedEnd)?;
+        let value = u16::from_str_radix(digits, 16).map_err(|_| PathError::Unexpected(self.pos))?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn or(&mut self) -> Result<Expr, PathError> {
+        let mut expr = self.and()?;
+        while self.eat_str("||") {
+            self.skip_whitespace();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, PathError> {
+        let mut expr = self.unary()?;
+        while self.eat_str("&&") {
+            self.skip_whitespace();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    /// A negated, parenthesized, comparison or existence expression, with
+    /// the whitespace following it skipped
+    fn unary(&mut self) -> Result<Expr, PathError> {
+        let expr = if self.eat(b'!') {
+            self.skip_whitespace();
+            Expr::Not(Box::new(self.unary()?))
+        } else if self.eat(b'(') {
+            self.skip_whitespace();
+            let expr = self.or()?;
+            self.expect(b')')?;
+            expr
+        } else {
+            let start = self.pos;
+            let left = self.operand()?;
+            self.skip_whitespace();
+            match self.compare_op() {
+                Some(op) => {
+                    self.skip_whitespace();
+                    Expr::Compare(left, op, self.operand()?)
+                }
+                None => match left {
+                    Operand::Query(query) => Expr::Exists(query),
+                    Operand::Literal(_) => return Err(PathError::Unexpected(start)),
+                },
+            }
+        };
+        self.skip_whitespace();
+        Ok(expr)
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        ops.into_iter()
+            .find(|(text, _)| self.eat_str(text))
+            .map(|(_, op)| op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, PathError> {
+        let literal = match self.peek() {
+            Some(b'@' | b'$') => {
+                let absolute = self.peek() == Some(b'$');
+                self.pos += 1;
+                let segments = self.segments()?;
+                return Ok(Operand::Query(Query { absolute, segments }));
+            }
+            Some(b'\'' | b'"') => Value::String(from_string(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => Value::Number(self.number()?),
+            _ if self.eat_str("true") => Value::Boolean(true),
+            _ if self.eat_str("false") => Value::Boolean(false),
+            _ if self.eat_str("null") => Value::Null,
+            _ => return Err(self.unexpected()),
+        };
+        Ok(Operand::Literal(literal))
+    }
+
+    /// A number literal, with optional fraction and exponent
+    fn number(&mut self) -> Result<f64, PathError> {
+        let start = self.pos;
+        let mut previous = None;
+        for byte in self.rest().bytes() {
+            let sign_allowed = matches!(previous, None | Some(b'e' | b'E'));
+            if !(byte.is_ascii_digit()
+                || matches!(byte, b'.' | b'e' | b'E')
+                || (matches!(byte, b'-' | b'+') && sign_allowed))
+            {
+                break;
+            }
+            previous = Some(byte);
+            self.pos += 1;
+        }
+        self.path[start..self.pos]
+            .parse()
+            .map_err(|_| PathError::Unexpected(start))
+    }
+}
+
+/// One step of the path to a matched value
+#[derive(Clone)]
+enum Step<'v> {
+    Name(&'v str),
+    Index(usize),
+}
+
+#[derive(Clone)]
+struct Node<'v> {
+    path: Vec<Step<'v>>,
+    value: &'v Value,
+}
+
+impl<'v> Node<'v> {
+    fn child(&self, step: Step<'v>, value: &'v Value) -> Node<'v> {
+        let mut path = self.path.clone();
+        path.push(step);
+        Node { path, value }
+    }
+
+    /// Members of an object by key order, or elements of an array
+    fn children(&self) -> Vec<Node<'v>> {
+        match self.value {
+            Value::Object(map) => {
+                let mut members: Vec<(&String, &Value)> = map.iter().collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                members
+                    .into_iter()
+                    .map(|(key, value)| self.child(Step::Name(key), value))
+                    .collect()
+            }
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| self.child(Step::Index(i), value))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// This node followed by all of its descendants, in document order
+    fn descendants(&self) -> Vec<Node<'v>> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            let mut children = node.children();
+            children.reverse();
+            stack.extend(children);
+            nodes.push(node);
+        }
+        nodes
+    }
+}
+
+fn select<'v>(segments: &[Segment], start: &'v Value, root: &'v Value) -> Vec<Node<'v>> {
+    let mut nodes = vec![Node {
+        path: Vec::new(),
+        value: start,
+    }];
+    for segment in segments {
+        let inputs = if segment.descendant {
+            nodes.iter().flat_map(Node::descendants).collect()
+        } else {
+            nodes
+        };
+        nodes = inputs
+            .iter()
+            .flat_map(|node| {
+                segment
+                    .selectors
+                    .iter()
+                    .flat_map(|selector| apply(selector, node, root))
+            })
+            .collect();
+    }
+    nodes
+}
+
+fn apply<'v>(selector: &Selector, node: &Node<'v>, root: &'v Value) -> Vec<Node<'v>> {
+    match (selector, node.value) {
+        (Selector::Name(name), Value::Object(map)) => map
+            .get_key_value(name)
+            .map(|(key, value)| node.child(Step::Name(key), value))
+            .into_iter()
+            .collect(),
+        (Selector::Wildcard, _) => node.children(),
+        (Selector::Index(index), Value::Array(values)) => {
+            let len = values.len() as i64;
+            let index = if *index < 0 { len + index } else { *index };
+            match usize::try_from(index)
+                .ok()
+                .and_then(|i| values.get(i).map(|v| (i, v)))
+            {
+                Some((i, value)) => vec![node.child(Step::Index(i), value)],
+                None => Vec::new(),
+            }
+        }
+        (Selector::Slice { start, end, step }, Value::Array(values)) => {
+            slice_indices(values.len(), *start, *end, step.unwrap_or(1))
+                .into_iter()
+                .map(|i| node.child(Step::Index(i), &values[i]))
+                .collect()
+        }
+        (Selector::Filter(expr), _) => node
+            .children()
+            .into_iter()
+            .filter(|child| test(expr, child.value, root))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Indices selected by `[start:end:step]` from an array of `len` elements
+fn slice_indices(len: usize, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let len = len as i64;
+    let normalize = |i: i64| if i < 0 { len + i } else { i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = Some(lower);
+        // a step past the end of the range can overflow, which ends it too
+        while let Some(index) = i.filter(|&i| i < upper) {
+            indices.push(index as usize);
+            i = index.checked_add(step);
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = end.map_or(-1, |end| normalize(end).clamp(-1, len - 1));
+        let mut i = Some(upper);
+        while let Some(index) = i.filter(|&i| lower < i) {
+            indices.push(index as usize);
+            i = index.checked_add(step);
+        }
+    }
+    indices
+}
+
+fn test(expr: &Expr, current: &Value, root: &Value) -> bool {
+    match expr {
+        Expr::Or(a, b) => test(a, current, root) || test(b, current, root),
+        Expr::And(a, b) => test(a, current, root) && test(b, current, root),
+        Expr::Not(expr) => !test(expr, current, root),
+        Expr::Exists(query) => !evaluate(query, current, root).is_empty(),
+        Expr::Compare(left, op, right) => {
+            let left = operand(left, current, root);
+            let right = operand(right, current, root);
+            match op {
+                CompareOp::Eq => left == right,
+                CompareOp::Ne => left != right,
+                CompareOp::Lt => less(left, right),
+                CompareOp::Le => less(left, right) || left == right,
+                CompareOp::Gt => less(right, left),
+                CompareOp::Ge => less(right, left) || left == right,
+            }
+        }
+    }
+}
+
+fn evaluate<'v>(query: &Query, current: &'v Value, root: &'v Value) -> Vec<Node<'v>> {
+    let start = if query.absolute { root } else { current };
+    select(&query.segments, start, root)
+}
+
+/// The value of an operand, `None` unless a query matches exactly one value
+fn operand<'v>(operand: &'v Operand, current: &'v Value, root: &'v Value) -> Option<&'v Value> {
+    match operand {
+        Operand::Literal(value) => Some(value),
+        Operand::Query(query) => match evaluate(query, current, root).as_slice() {
+            [node] => Some(node.value),
+            _ => None,
+        },
+    }
+}
+
+/// Only numbers and strings are ordered, anything else compares false
+fn less(a: Option<&Value>, b: Option<&Value>) -> bool {
+    let ordering = match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a.partial_cmp(b),
+        (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+        _ => None,
+    };
+    ordering == Some(Ordering::Less)
+}
+
+/// `$['name'][0]...`, with quotes, backslashes and control characters in
+/// names escaped
+fn normalized_path(path: &[Step]) -> String {
+    let mut out = String::from("$");
+    for step in path {
+        match step {
+            Step::Index(i) => out.push_str(&format!("[{i}]")),
+            Step::Name(name) => {
+                out.push_str("['");
+                for c in name.chars() {
+                    match c {
+                        '\'' => out.push_str("\\'"),
+                        '\\' => out.push_str("\\\\"),
+                        '\u{8}' => out.push_str("\\b"),
+                        '\u{c}' => out.push_str("\\f"),
+                        '\n' => out.push_str("\\n"),
+                        '\r' => out.push_str("\\r"),
+                        '\t' => out.push_str("\\t"),
+                        c if c < ' ' => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+                        c => out.push(c),
+                    }
+                }
+                out.push_str("']");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonPath, PathError};
+    use crate::prelude::*;
+    use crate::{Value, parse};
+
+    const STORE: &str = r#"{"store": {
+        "book": [
+            {"category": "reference", "author": "Rees", "title": "Sayings", "price": 8.95},
+            {"category": "fiction", "author": "Waugh", "title": "Sword", "price": 12.99},
+            {"category": "fiction", "author": "Melville", "title": "Moby Dick", "isbn": "0-553", "price": 8.99},
+            {"category": "fiction", "author": "Tolkien", "title": "The Lord", "isbn": "0-395", "price": 22.99}
+        ],
+        "bicycle": {"color": "red", "price": 399}
+    }}"#;
+
+    fn paths(path: &str, json: &str) -> Vec<String> {
+        let value = parse(json).unwrap();
+        JsonPath::compile(path)
+            .unwrap()
+            .query(&value)
+            .into_iter()
+            .map(|m| m.path)
+            .collect()
+    }
+
+    #[test]
+    fn query_names_and_indices() {
+        assert_eq!(
+            paths("$.store.book[0].title", STORE),
+            vec!["$['store']['book'][0]['title']"]
+        );
+        assert_eq!(
+            paths("$['store']['book'][-1, 1]['author']", STORE),
+            vec![
+                "$['store']['book'][3]['author']",
+                "$['store']['book'][1]['author']",
+            ]
+        );
+        assert!(paths("$.store.nothing[0]", STORE).is_empty());
+    }
+
+    #[test]
+    fn query_filters() {
+        let value = parse(STORE).unwrap();
+        let path = JsonPath::compile("$.store.book[?(@.price<10)].title").unwrap();
+        let titles: Vec<&Value> = path.query(&value).into_iter().map(|m| m.value).collect();
+
+        assert_eq!(
+            titles,
+            vec![
+                &Value::String("Sayings".into()),
+                &Value::String("Moby Dick".into()),
+            ]
+        );
+        assert_eq!(
+            paths("$..book[?@.isbn && @.price > 10].author", STORE),
+            vec!["$['store']['book'][3]['author']"]
+        );
+        assert_eq!(
+            paths("$.store.book[?!(@.category == 'fiction')]", STORE),
+            vec!["$['store']['book'][0]"]
+        );
+        assert_eq!(
+            paths("$.store.book[?@.price == $.store.book[0].price]", STORE),
+            vec!["$['store']['book'][0]"]
+        );
+    }
+
+    #[test]
+    fn query_wildcards_and_descendants() {
+        assert_eq!(
+            paths("$.store.*", STORE),
+            vec!["$['store']['bicycle']", "$['store']['book']"]
+        );
+        assert_eq!(paths("$..price", STORE).len(), 5);
+        assert_eq!(
+            paths("$..[0]", r#"{"a": [1, [2]], "b": [[3]]}"#),
+            vec!["$['a'][0]", "$['a'][1][0]", "$['b'][0]", "$['b'][0][0]"]
+        );
+    }
+
+    #[test]
+    fn query_slices() {
+        let array = "[0, 1, 2, 3, 4, 5]";
+
+        assert_eq!(paths("$[1:3]", array), vec!["$[1]", "$[2]"]);
+        assert_eq!(paths("$[::2]", array), vec!["$[0]", "$[2]", "$[4]"]);
+        assert_eq!(paths("$[-2:]", array), vec!["$[4]", "$[5]"]);
+        assert_eq!(paths("$[::-2]", array), vec!["$[5]", "$[3]", "$[1]"]);
+        assert!(paths("$[::0]", array).is_empty());
+        assert_eq!(paths("$[1::9223372036854775807]", array), vec!["$[1]"]);
+        assert_eq!(paths("$[4::-9223372036854775808]", array), vec!["$[4]"]);
+    }
+
+    #[test]
+    fn normalized_paths_escape_names() {
+        assert_eq!(
+            paths(r#"$["it's\n"]"#, r#"{"it's\n": 1}"#),
+            vec![r"$['it\'s\n']"]
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        assert_eq!(JsonPath::compile("store"), Err(PathError::ExpectedRoot));
+        assert_eq!(JsonPath::compile("$.store["), Err(PathError::UnexpectedEnd));
+        assert_eq!(JsonPath::compile("$[01]"), Err(PathError::Unexpected(2)));
+        assert_eq!(JsonPath::compile("$.a b"), Err(PathError::Unexpected(3)));
+        assert_eq!(JsonPath::compile("$[?(1)]"), Err(PathError::Unexpected(4)));
+    }
+}
@@ -0,0 +1,357 @@
+// On-demand access: reading single fields out of a document without parsing
+// the rest of it
+
+use alloc::borrow::Cow;
+use core::ops::Range;
+
+use super::convert::ConversionError;
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+use super::tokenize::{SliceSource, Token, TokenStream};
+use super::{JsonError, Value};
+
+type Tokens<'a> = TokenStream<'a, SliceSource<'a>>;
+
+/// A document whose values are only read when asked for
+///
+/// Creating one only lexes the input and checks that its brackets match up
+/// around a single value. Looking up a field steps over every member before
+/// it bracket by bracket, without unescaping strings or building anything,
+/// so the cost of reading a few fields barely depends on the size of the
+/// subtrees left unread:
+///
+/// ```
+/// use parser_json_rs::{JsonError, LazyDocument};
+///
+/// # fn main() -> Result<(), JsonError> {
+/// let input = r#"{"user": {"id": 7, "history": [1, 2, 3]}, "log": ["..."]}"#;
+/// let doc = LazyDocument::new(input)?;
+/// assert_eq!(doc.field("user")?.field("id")?.as_u64(), Some(7));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Syntax errors inside the document that the up-front check doesn't catch,
+/// a missing colon for example, are reported by the lookups that run into
+/// them, or not at all when nothing reads that part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyDocument<'a> {
+    root: LazyValue<'a>,
+}
+
+/// A value of a [`LazyDocument`], held as its source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyValue<'a> {
+    json: &'a str,
+}
+
+impl<'a> LazyDocument<'a> {
+    /// Lex `input` and check that its brackets enclose exactly one value
+    pub fn new(input: &'a str) -> Result<Self, JsonError> {
+        let mut tokens = Tokens::new(SliceSource::new(input.as_bytes()));
+        let span = skip_value(&mut tokens)?;
+        if tokens.next().transpose()?.is_some() {
+            return Err(TokenParseError::TrailingTokens.into());
+        }
+
+        Ok(LazyDocument {
+            root: LazyValue { json: &input[span] },
+        })
+    }
+
+    /// The top-level value
+    pub fn root(&self) -> LazyValue<'a> {
+        self.root
+    }
+
+    /// The member `key` of the top-level object, see [`LazyValue::field`]
+    pub fn field(&self, key: &str) -> Result<LazyValue<'a>, JsonError> {
+        self.root.field(key)
+    }
+
+    /// The element at `index` of the top-level array, see
+    /// [`LazyValue::index`]
+    pub fn index(&self, index: usize) -> Result<LazyValue<'a>, JsonError> {
+        self.root.index(index)
+    }
+}
+
+impl<'a> LazyValue<'a> {
+    /// The source text of the value
+    pub fn get(&self) -> &'a str {
+        self.json
+    }
+
+    /// The member `key` of an object, the last one if the key is repeated as
+    /// when parsing into a [`Value`]
+    ///
+    /// A missing member or a value that isn't an object is a
+    /// [`JsonError::Conversion`], its path relative to this value.
+    pub fn field(&self, key: &str) -> Result<LazyValue<'a>, JsonError> {
+        let mut tokens = self.tokens();
+        let first = next_token(&mut tokens)?;
+        if first != Token::LeftCurlyBracket {
+            return Err(ConversionError::new("an object", &shape(&first)).into());
+        }
+
+        let mut found = None;
+        if !self.is_empty_container() {
+            loop {
+                let Token::String(raw) = next_token(&mut tokens)? else {
+                    return Err(TokenParseError::ExpectedProperty.into());
+                };
+                if next_token(&mut tokens)? != Token::Colon {
+                    return Err(TokenParseError::ExpectedColon.into());
+                }
+                let span = skip_value(&mut tokens)?;
+                if unescape(raw)? == key {
+                    found = Some(span);
+                }
+                match next_token(&mut tokens)? {
+                    Token::Comma => {}
+                    Token::RightCurlyBracket => break,
+                    _ => return Err(TokenParseError::ExpectedComma.into()),
+                }
+            }
+        }
+
+        match found {
+            Some(span) => Ok(self.slice(span)),
+            None => Err(missing("a member").within(key).into()),
+        }
+    }
+
+    /// The element at `index` of an array, stepping over the ones before it
+    ///
+    /// An index out of bounds or a value that isn't an array is a
+    /// [`JsonError::Conversion`], its path relative to this value.
+    pub fn index(&self, index: usize) -> Result<LazyValue<'a>, JsonError> {
+        let mut tokens = self.tokens();
+        let first = next_token(&mut tokens)?;
+        if first != Token::LeftSquareBracket {
+            return Err(ConversionError::new("an array", &shape(&first)).into());
+        }
+
+        if !self.is_empty_container() {
+            for i in 0.. {
+                let span = skip_value(&mut tokens)?;
+                if i == index {
+                    return Ok(self.slice(span));
+                }
+                match next_token(&mut tokens)? {
+                    Token::Comma => {}
+                    Token::RightSquareBracket => break,
+                    _ => return Err(TokenParseError::ExpectedComma.into()),
+                }
+            }
+        }
+        Err(missing("an element").within(&index.to_string()).into())
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.json == "null"
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.json {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.tokens().next()? {
+            Ok(Token::Number(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The number if it is whole and within `i64` range, as [`Value::as_i64`]
+    pub fn as_i64(&self) -> Option<i64> {
+        Value::Number(self.as_f64()?).as_i64()
+    }
+
+    /// The number if it is whole and within `u64` range, as [`Value::as_u64`]
+    pub fn as_u64(&self) -> Option<u64> {
+        Value::Number(self.as_f64()?).as_u64()
+    }
+
+    /// The string with its escapes resolved, borrowed from the input when it
+    /// has none
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        match self.tokens().next()? {
+            Ok(Token::String(raw)) => unescape(raw).ok(),
+            _ => None,
+        }
+    }
+
+    /// Build the DOM for the value after all
+    pub fn parse(&self) -> Result<Value, JsonError> {
+        super::parse(self.json)
+    }
+
+    fn tokens(&self) -> Tokens<'a> {
+        Tokens::new(SliceSource::new(self.json.as_bytes()))
+    }
+
+    fn slice(&self, span: Range<usize>) -> LazyValue<'a> {
+        LazyValue {
+            json: &self.json[span],
+        }
+    }
+
+    /// Whether the value is `[]` or `{}`, possibly with whitespace inside
+    fn is_empty_container(&self) -> bool {
+        self.json[1..].trim_start().len() == 1
+    }
+}
+
+fn next_token<'a>(tokens: &mut Tokens<'a>) -> Result<Token<'a>, JsonError> {
+    Ok(tokens.next().ok_or(TokenParseError::UnexpectedEnd)??)
+}
+
+/// Step over the next value, only checking that its brackets match, and
+/// return the byte range it covers
+fn skip_value(tokens: &mut Tokens<'_>) -> Result<Range<usize>, JsonError> {
+    let mut open: Vec<Token> = Vec::new();
+    let mut start = None;
+
+    loop {
+        let token = match tokens.next() {
+            Some(token) => token?,
+            None if start.is_none() => return Err(TokenParseError::EmptyInput.into()),
+            None => return Err(TokenParseError::UnexpectedEnd.into()),
+        };
+        let span = tokens.span();
+        let start = *start.get_or_insert(span.start);
+
+        match token {
+            Token::LeftSquareBracket => open.push(Token::RightSquareBracket),
+            Token::LeftCurlyBracket => open.push(Token::RightCurlyBracket),
+            // the guard pops the innermost open bracket, matching or not
+            Token::RightSquareBracket | Token::RightCurlyBracket
+                if open.pop().as_ref() != Some(&token) =>
+            {
+                return Err(TokenParseError::ExpectedComma.into());
+            }
+            Token::Comma | Token::Colon if open.is_empty() => {
+                return Err(TokenParseError::ExpectedValue.into());
+            }
+            _ => {}
+        }
+        if open.is_empty() {
+            return Ok(start..span.end);
+        }
+    }
+}
+
+/// A value of the same kind as the one starting with `token`, for errors
+fn shape(token: &Token) -> Value {
+    match token {
+        Token::True => Value::Boolean(true),
+        Token::False => Value::Boolean(false),
+        Token::Number(n) => Value::Number(*n),
+        Token::String(_) => Value::String(Default::default()),
+        Token::LeftSquareBracket => Value::Array(Vec::new()),
+        Token::LeftCurlyBracket => Value::Object(Default::default()),
+        _ => Value::Null,
+    }
+}
+
+/// The error for a member or element that isn't there
+fn missing(expected: &'static str) -> ConversionError {
+    ConversionError {
+        path: String::new(),
+        expected,
+        found: String::from("nothing"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LazyDocument;
+    use crate::prelude::*;
+    use crate::{ConversionError, JsonError, TokenParseError, Value};
+
+    #[test]
+    fn read_single_fields() {
+        let doc = LazyDocument::new(
+            r#" {"user": {"id": 7, "name": "a\"b", "tags": ["x", {"y": null}]}, "n": -1.5} "#,
+        )
+        .unwrap();
+        let user = doc.field("user").unwrap();
+
+        assert_eq!(user.field("id").unwrap().as_u64(), Some(7));
+        assert_eq!(user.field("name").unwrap().as_str().unwrap(), "a\"b");
+        assert!(
+            user.field("tags")
+                .unwrap()
+                .index(1)
+                .unwrap()
+                .field("y")
+                .unwrap()
+                .is_null()
+        );
+        assert_eq!(doc.field("n").unwrap().as_f64(), Some(-1.5));
+        assert_eq!(doc.field("n").unwrap().as_i64(), None);
+        assert_eq!(
+            user.field("tags").unwrap().parse(),
+            Ok(crate::parse(r#"["x", {"y": null}]"#).unwrap())
+        );
+        assert_eq!(LazyDocument::new(" [1] ").unwrap().root().get(), "[1]");
+    }
+
+    #[test]
+    fn missing_fields_and_wrong_kinds() {
+        let doc = LazyDocument::new(r#"{"a": [1], "b": {}}"#).unwrap();
+
+        assert_eq!(
+            doc.field("c"),
+            Err(JsonError::Conversion(ConversionError {
+                path: String::from("/c"),
+                expected: "a member",
+                found: String::from("nothing"),
+            }))
+        );
+        assert!(doc.field("b").unwrap().field("c").is_err());
+        assert!(doc.field("a").unwrap().index(1).is_err());
+        let Err(JsonError::Conversion(err)) = doc.field("a").unwrap().field("x") else {
+            panic!("expected a conversion error");
+        };
+        assert_eq!(err.to_string(), "expected an object, found an array");
+        assert_eq!(doc.field("a").unwrap().index(0).unwrap().as_bool(), None);
+    }
+
+    #[test]
+    fn structure_is_checked_up_front() {
+        assert_eq!(
+            LazyDocument::new("[1, {]"),
+            Err(JsonError::Parse(TokenParseError::ExpectedComma))
+        );
+        assert_eq!(
+            LazyDocument::new(r#"{"a": [1}"#),
+            Err(JsonError::Parse(TokenParseError::ExpectedComma))
+        );
+        assert_eq!(
+            LazyDocument::new("[1] 2"),
+            Err(JsonError::Parse(TokenParseError::TrailingTokens))
+        );
+        assert_eq!(
+            LazyDocument::new(" "),
+            Err(JsonError::Parse(TokenParseError::EmptyInput))
+        );
+    }
+
+    #[test]
+    fn errors_in_unread_parts_surface_when_read() {
+        let doc = LazyDocument::new(r#"{"ok": 1, "bad": {"x" 2}}"#).unwrap();
+
+        assert!(doc.root().parse().is_err());
+        assert_eq!(
+            doc.field("bad").unwrap().field("x"),
+            Err(JsonError::Parse(TokenParseError::ExpectedColon))
+        );
+        assert_eq!(doc.field("ok").unwrap().parse(), Ok(Value::Number(1.0)));
+    }
+}
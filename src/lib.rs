@@ -1,10 +1,144 @@
+//! A JSON parser and toolkit
+//!
+//! Everything built on `std::io` needs the default `std` feature. Without
+//! it, and with the `alloc` feature instead, the crate is `no_std`: parsing,
+//! serializing and working with values only need an allocator.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("either the `std` or the `alloc` feature must be enabled");
+
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+mod access;
+#[cfg(feature = "arena")]
+mod arena;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod borrowed;
+mod change;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compress;
+mod concat;
+mod convert;
+mod decode;
+mod diagnostic;
+pub mod edit;
+mod flatten;
+mod from_json;
+#[cfg(feature = "std")]
+mod hash;
+mod iter;
+pub mod jsonpath;
+mod lazy;
+mod limits;
+mod line_index;
+mod map;
+mod merge;
+pub mod merge_patch;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "std")]
+pub mod ndjson;
 mod parser;
+pub mod patch;
+mod pointer;
+mod pretty;
+mod push;
+mod raw;
+#[cfg(feature = "std")]
+mod reader;
+mod recover;
+mod repair;
+pub mod schema;
+mod ser;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "serde_json")]
+mod serde_json_impl;
+mod spanned;
+pub mod stream;
+mod string;
+mod syntax;
+mod tape;
+mod to_json;
 mod tokenize;
+mod validate;
+pub mod visit;
+
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+use prelude::*;
+
+pub use access::{Member, ValueIndex};
+#[cfg(feature = "arena")]
+pub use arena::{Arena, ArenaValue, parse_in};
+pub use borrowed::ValueRef;
+pub use change::{Change, diff};
+pub use concat::StreamDeserializer;
+pub use convert::ConversionError;
+pub use decode::{Encoding, Utf8Mode};
+pub use diagnostic::{Diagnostic, parse_with_diagnostic};
+pub use from_json::FromJson;
+#[cfg(feature = "std")]
+pub use hash::{stream_equal, stream_hash};
+pub use iter::{Entries, EntriesMut, Members, MembersMut};
+pub use lazy::{LazyDocument, LazyValue};
+pub use limits::{Limit, Limits};
+pub use line_index::{LineCol, LineIndex};
+pub use map::{Map, ObjectHasher};
+pub use merge::{ArrayMerge, MergeStrategy, NullMerge, ObjectMerge};
+#[cfg(feature = "mmap")]
+pub use mmap::{MappedFile, parse_file_mmap};
+pub use parser::{Parser, ParserOptions, TokenParseError};
+#[cfg(feature = "derive")]
+pub use parser_json_rs_derive::{FromJson, ToJson};
+pub use pointer::PointerError;
+pub use pretty::PrettyOptions;
+pub use push::{Progress, PushParser};
+pub use raw::RawValue;
+#[cfg(feature = "std")]
+pub use reader::ReaderLexer;
+pub use recover::{LossyParse, diagnose, parse_lossy};
+pub use repair::{Fix, FixKind, Repaired, repair};
+pub use ser::{NonFinite, WriteError, WriteOptions};
+pub use spanned::{
+    SpannedMember, SpannedNode, SpannedValue, parse_spanned, parse_spanned_with_comments,
+};
+pub use string::JsonString;
+pub use syntax::Syntax;
+pub use tape::{Tape, TapeEntries, TapeMembers, TapeValue, parse_tape};
+pub use to_json::ToJson;
+pub use tokenize::{Comment, Lexer, SpannedToken, SpannedTokens, Token, TokenizeError};
+pub use validate::validate;
 
-use std::collections::HashMap;
+// lets derived impls name `::parser_json_rs` inside this crate too
+extern crate self as parser_json_rs;
+
+/// Used by code generated by the macros, not public API
+#[doc(hidden)]
+pub mod __private {
+    pub use super::from_json::within;
+    pub use alloc::string::String;
+    pub use alloc::{format, vec};
+}
+
+/// What the standard prelude has that `core`'s lacks, for `no_std` builds
+mod prelude {
+    pub use alloc::borrow::ToOwned;
+    pub use alloc::boxed::Box;
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+    pub use alloc::{format, vec};
+}
 
 /// Representation of a JSON [value](https://www.rfc-editor.org/rfc/rfc8259#section-3)
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     /// literal characters `null`
     Null,
@@ -13,7 +147,7 @@ pub enum Value {
     Boolean(bool),
 
     /// characters within double quotes "..."
-    String(String),
+    String(JsonString),
 
     /// numbers stored as 64-bit floating point
     Number(f64),
@@ -22,5 +156,705 @@ pub enum Value {
     Array(Vec<Value>),
 
     /// String keys with JSON values
-    Object(HashMap<String, Value>),
+    Object(Map<String, Value>),
+}
+
+/// Any error raised while turning input into a [`Value`]
+#[derive(Debug)]
+pub enum JsonError {
+    /// the input could not be split into tokens
+    Tokenize(TokenizeError),
+
+    /// the tokens do not form a valid JSON value
+    Parse(TokenParseError),
+
+    /// reading the input failed
+    #[cfg(feature = "std")]
+    Io(io::Error),
+
+    /// a parsed value doesn't have the shape of the type it is read into
+    Conversion(ConversionError),
+}
+
+impl JsonError {
+    /// A stable identifier of the kind of error, for branching on and for
+    /// telemetry. Codes never change meaning once released; tokenizer errors
+    /// are `E0xx`, parse errors `E1xx`, I/O errors `E2xx` and conversion
+    /// errors `E3xx`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            JsonError::Tokenize(err) => err.code(),
+            JsonError::Parse(err) => err.code(),
+            #[cfg(feature = "std")]
+            JsonError::Io(_) => "E201_IO",
+            JsonError::Conversion(_) => "E301_CONVERSION",
+        }
+    }
+}
+
+impl PartialEq for JsonError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (JsonError::Tokenize(a), JsonError::Tokenize(b)) => a == b,
+            (JsonError::Parse(a), JsonError::Parse(b)) => a == b,
+            // `io::Error` has no equality of its own, its kind is the closest match
+            #[cfg(feature = "std")]
+            (JsonError::Io(a), JsonError::Io(b)) => a.kind() == b.kind(),
+            (JsonError::Conversion(a), JsonError::Conversion(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl From<TokenizeError> for JsonError {
+    fn from(err: TokenizeError) -> Self {
+        JsonError::Tokenize(err)
+    }
+}
+
+impl From<TokenParseError> for JsonError {
+    fn from(err: TokenParseError) -> Self {
+        JsonError::Parse(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for JsonError {
+    fn from(err: io::Error) -> Self {
+        JsonError::Io(err)
+    }
+}
+
+impl From<ConversionError> for JsonError {
+    fn from(err: ConversionError) -> Self {
+        JsonError::Conversion(err)
+    }
+}
+
+/// Result of parsing byte input, see [`parse_bytes_with`]
+#[derive(Debug, PartialEq)]
+pub struct BytesParse {
+    pub value: Value,
+
+    /// byte offsets of every invalid UTF-8 sequence replaced with U+FFFD,
+    /// always empty under [`Utf8Mode::Strict`]
+    pub replacements: Vec<usize>,
+}
+
+/// How deeply arrays and objects may be nested unless configured otherwise
+///
+/// The parser keeps open containers on the heap, so `usize::MAX` lifts the
+/// limit entirely. Code walking the resulting tree recursively, including
+/// `Drop`, still uses the call stack though, which is why there is a default.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Parse a JSON document from text
+pub fn parse(input: &str) -> Result<Value, JsonError> {
+    parse_with_options(input, ParserOptions::default())
+}
+
+/// Parse a JSON document from text with everything `options` configures,
+/// for a single document where keeping a [`Parser`] around isn't worth it
+///
+/// ```
+/// use parser_json_rs::{Parser, Syntax, json, parse_with_options};
+///
+/// let options = Parser::builder().syntax(Syntax::jsonc()).max_depth(4);
+/// assert_eq!(parse_with_options("[1] // one", options)?, json!([1]));
+/// assert!(parse_with_options("[[[[[1]]]]]", options).is_err());
+/// # Ok::<(), parser_json_rs::JsonError>(())
+/// ```
+pub fn parse_with_options(input: &str, options: ParserOptions) -> Result<Value, JsonError> {
+    options.parse(input)
+}
+
+/// Parse a JSON document from text, allowing arrays and objects to be nested
+/// at most `max_depth` levels deep
+pub fn parse_with_max_depth(input: &str, max_depth: usize) -> Result<Value, JsonError> {
+    parse_with_options(input, ParserOptions::default().max_depth(max_depth))
+}
+
+/// Parse a JSON document from text, failing as soon as it goes past one of
+/// `limits`
+pub fn parse_with_limits(input: &str, limits: Limits) -> Result<Value, JsonError> {
+    parse_with_options(input, ParserOptions::default().limits(limits))
+}
+
+/// Parse a document in JSON extended with what `syntax` enables
+pub fn parse_with_syntax(input: &str, syntax: Syntax) -> Result<Value, JsonError> {
+    parse_with_options(input, ParserOptions::default().syntax(syntax))
+}
+
+/// Parse a [JSON5](https://spec.json5.org) document, the superset of JSON
+/// common in hand-written configuration files
+///
+/// ```
+/// use parser_json_rs::{json, parse_json5};
+///
+/// let config = parse_json5("{
+///     // where to listen
+///     host: 'localhost',
+///     port: 0x1F90,
+///     ratio: .5,
+///     tags: ['a', 'b',],
+/// }").unwrap();
+/// assert_eq!(config, json!({"host": "localhost", "port": 8080, "ratio": 0.5, "tags": ["a", "b"]}));
+/// ```
+pub fn parse_json5(input: &str) -> Result<Value, JsonError> {
+    parse_with_syntax(input, Syntax::json5())
+}
+
+/// Parse JSON with `//` and `/* */` comments, see [`Syntax::jsonc`]
+pub fn parse_jsonc(input: &str) -> Result<Value, JsonError> {
+    parse_with_syntax(input, Syntax::jsonc())
+}
+
+/// Parse a document in JSON extended with what `syntax` enables, returning
+/// its comments too
+///
+/// ```
+/// use parser_json_rs::{Syntax, json, parse_with_comments};
+///
+/// let (value, comments) = parse_with_comments("{\"tabSize\": 4} // spaces", Syntax::jsonc())?;
+/// assert_eq!(value, json!({"tabSize": 4}));
+/// assert_eq!(comments[0].text, "// spaces");
+/// # Ok::<(), parser_json_rs::JsonError>(())
+/// ```
+pub fn parse_with_comments(
+    input: &str,
+    syntax: Syntax,
+) -> Result<(Value, Vec<Comment<'_>>), JsonError> {
+    let options = ParserOptions::default().syntax(syntax);
+    let mut lexer = options.lexer(input)?.capture_comments();
+    let value = options.parse_tokens(&mut lexer)?;
+    Ok((value, lexer.take_comments()))
+}
+
+/// Parse the JSON value at the start of `input`, returning it with the
+/// number of bytes it spans so that whatever follows can be handled by the
+/// caller, as when JSON is embedded in a larger protocol
+pub fn parse_prefix(input: &str) -> Result<(Value, usize), JsonError> {
+    let options = ParserOptions::default();
+    let mut tokens = options.slice_tokens(input.as_bytes())?;
+    let value = options.parse_next(&mut tokens)?;
+    let value = value.ok_or(TokenParseError::EmptyInput)?;
+
+    Ok((value, tokens.span().end))
+}
+
+/// Parse a JSON document from text without copying its strings, see [`ValueRef`]
+pub fn parse_borrowed(input: &str) -> Result<ValueRef<'_>, JsonError> {
+    let options = ParserOptions::default();
+    options.parse_tokens(options.lexer(input)?)
+}
+
+/// Parse a JSON document from a reader, lexing it in small buffered chunks so
+/// the input never has to be held in memory as a whole
+///
+/// With the `gzip` or `zstd` feature, input starting with the magic bytes of
+/// that format is decompressed on the fly, so `.json.gz` and `.json.zst`
+/// files can be passed in as they are.
+#[cfg(feature = "std")]
+pub fn parse_from_reader(reader: impl Read) -> Result<Value, JsonError> {
+    parse_from_reader_with_options(reader, ParserOptions::default())
+}
+
+/// [`parse_from_reader`] with everything `options` configures
+///
+/// The document size limit counts the bytes read after decompression, so a
+/// small compressed file can't expand into more than the limit allows:
+///
+/// ```
+/// use parser_json_rs::{JsonError, Limit, Limits, Parser, TokenParseError};
+/// use parser_json_rs::parse_from_reader_with_options;
+///
+/// let limits = Limits { max_document_size: 1024, ..Limits::default() };
+/// let input = format!("[{}1]", "1, ".repeat(1000));
+/// assert_eq!(
+///     parse_from_reader_with_options(input.as_bytes(), Parser::builder().limits(limits)),
+///     Err(JsonError::Parse(TokenParseError::LimitExceeded(Limit::DocumentSize)))
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_from_reader_with_options(
+    reader: impl Read,
+    options: ParserOptions,
+) -> Result<Value, JsonError> {
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    let reader = compress::decompress(reader)?;
+    options.parse_tokens(ReaderLexer::with_options(reader, &options))
+}
+
+/// Parse a JSON document from an async reader, lexing each chunk as it
+/// arrives so the executor is never blocked waiting for input
+#[cfg(feature = "tokio")]
+pub async fn parse_async(reader: impl tokio::io::AsyncRead + Unpin) -> Result<Value, JsonError> {
+    parse_async_with_options(reader, ParserOptions::default()).await
+}
+
+/// [`parse_async`] with everything `options` configures, the document size
+/// limit being checked as each chunk arrives
+#[cfg(feature = "tokio")]
+pub async fn parse_async_with_options(
+    reader: impl tokio::io::AsyncRead + Unpin,
+    options: ParserOptions,
+) -> Result<Value, JsonError> {
+    use tokio::io::AsyncReadExt;
+
+    let mut reader = reader;
+    let mut buf = vec![0; reader::BUFFER_SIZE];
+    let mut parser = PushParser::with_options(options);
+    loop {
+        match reader.read(&mut buf).await? {
+            0 => return parser.close(),
+            len => parser.feed(&buf[..len])?,
+        }
+    }
+}
+
+/// Parse a JSON document from bytes, validating UTF-8 as part of lexing
+/// rather than in a separate pass over the whole input
+///
+/// UTF-16 and UTF-32 input, as .NET tools often write, is
+/// [detected](Encoding::detect) and transcoded to UTF-8 first.
+pub fn parse_bytes(input: impl AsRef<[u8]>) -> Result<Value, JsonError> {
+    parse_bytes_with_options(input, ParserOptions::default())
+}
+
+/// [`parse_bytes`] with everything `options` configures, the document size
+/// limit applying to the input as given
+pub fn parse_bytes_with_options(
+    input: impl AsRef<[u8]>,
+    options: ParserOptions,
+) -> Result<Value, JsonError> {
+    let input = input.as_ref();
+    if Encoding::detect(input) != Encoding::Utf8 {
+        return decode_and_parse(input, Utf8Mode::Strict, options).map(|parsed| parsed.value);
+    }
+    options.parse_tokens(options.slice_tokens(input)?)
+}
+
+/// Parse a JSON document from bytes, replacing every invalid UTF-8 sequence
+/// with U+FFFD instead of failing, to salvage text from logs mixing
+/// encodings
+///
+/// ```
+/// use parser_json_rs::{json, parse_bytes_lossy};
+///
+/// let value = parse_bytes_lossy(b"{\"user\": \"Jos\xe9\"}").unwrap();
+/// assert_eq!(value, json!({"user": "Jos\u{fffd}"}));
+/// ```
+///
+/// [`parse_bytes_with`] also tells where the replacements were made.
+pub fn parse_bytes_lossy(input: impl AsRef<[u8]>) -> Result<Value, JsonError> {
+    parse_bytes_with(input.as_ref(), Utf8Mode::Replace).map(|parsed| parsed.value)
+}
+
+/// Parse a JSON document from bytes, handling invalid UTF-8 according to `mode`
+pub fn parse_bytes_with(input: &[u8], mode: Utf8Mode) -> Result<BytesParse, JsonError> {
+    if mode == Utf8Mode::Strict && Encoding::detect(input) == Encoding::Utf8 {
+        return Ok(BytesParse {
+            value: parse_bytes(input)?,
+            replacements: Vec::new(),
+        });
+    }
+    decode_and_parse(input, mode, ParserOptions::default())
+}
+
+/// Transcode `input` to UTF-8 as `mode` says and parse it with `options`
+fn decode_and_parse(
+    input: &[u8],
+    mode: Utf8Mode,
+    options: ParserOptions,
+) -> Result<BytesParse, JsonError> {
+    options.limits.check_document(input.len())?;
+    let decoded = decode::decode(input, mode)?;
+    let value = options.parse_tokens(options.lexer(&decoded.text)?)?;
+
+    Ok(BytesParse {
+        value,
+        replacements: decoded.replacements,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BytesParse, JsonError, Limit, Limits, Parser, Syntax, TokenParseError, TokenizeError,
+        Utf8Mode, Value, parse, parse_bytes, parse_bytes_lossy, parse_bytes_with,
+        parse_bytes_with_options, parse_json5, parse_prefix, parse_with_max_depth,
+        parse_with_options, parse_with_syntax,
+    };
+    #[cfg(feature = "std")]
+    use super::{parse_from_reader, parse_from_reader_with_options};
+    use crate::prelude::*;
+
+    #[test]
+    fn parse_deeply_nested_input_fails_cleanly() {
+        let input = "[".repeat(100_000);
+        let expected = JsonError::Parse(TokenParseError::DepthLimitExceeded);
+
+        assert_eq!(parse(&input), Err(expected));
+    }
+
+    #[test]
+    fn parse_without_depth_limit() {
+        let depth = 100_000;
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let mut value = parse_with_max_depth(&input, usize::MAX).unwrap();
+
+        // take the tree apart level by level, dropping it recursively would
+        // overflow the stack of the test thread
+        let mut levels = 0;
+        while let Value::Array(mut arr) = value {
+            levels += 1;
+            value = arr.pop().unwrap_or(Value::Null);
+        }
+        assert_eq!(levels, depth);
+    }
+
+    #[test]
+    fn error_codes_are_distinct() {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut errors = vec![
+            JsonError::Tokenize(TokenizeError::UnclosedQuotes),
+            JsonError::Tokenize(TokenizeError::CharNotRecognized('x')),
+            JsonError::Tokenize(TokenizeError::InvalidUtf8(0)),
+            JsonError::Tokenize(TokenizeError::InvalidEncoding(0)),
+            JsonError::Tokenize(TokenizeError::MisspelledLiteral("true")),
+            JsonError::Parse(TokenParseError::EmptyInput),
+            JsonError::Parse(TokenParseError::ExpectedComma),
+            JsonError::Parse(TokenParseError::LimitExceeded(crate::Limit::ArrayLength)),
+            JsonError::Parse(TokenParseError::LimitExceeded(crate::Limit::ObjectLength)),
+        ];
+        #[cfg(feature = "std")]
+        errors.push(JsonError::Io(std::io::Error::other("failed")));
+        let mut codes: Vec<_> = errors.iter().map(JsonError::code).collect();
+
+        assert_eq!(codes[0], "E001_UNCLOSED_STRING");
+        assert_eq!(parse("[1 2]").unwrap_err().code(), "E107_EXPECTED_COMMA");
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn parse_json5_documents() {
+        let input = r#"// the example from spec.json5.org
+{
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+}"#;
+
+        assert_eq!(
+            parse_json5(input),
+            Ok(json!({
+                "unquoted": "and you can quote me on that",
+                "singleQuotes": "I can use \"double quotes\" here",
+                "lineBreaks": "Look, Mom! No \\n's!",
+                "hexadecimal": 912559,
+                "leadingDecimalPoint": 0.8675309,
+                "andTrailing": 8675309,
+                "positiveSign": 1,
+                "trailingComma": "in objects",
+                "andIn": ["arrays"],
+                "backwardsCompatible": "with JSON",
+            }))
+        );
+        assert_eq!(
+            parse_json5("[1,,]"),
+            Err(JsonError::Parse(TokenParseError::ExpectedValue))
+        );
+        assert_eq!(
+            parse_json5("{a: b}"),
+            Err(JsonError::Tokenize(TokenizeError::CharNotRecognized('b')))
+        );
+        assert!(parse("{unquoted: 1}").is_err());
+        assert!(parse("[1,]").is_err());
+        let single_quotes = Syntax {
+            single_quotes: true,
+            ..Syntax::default()
+        };
+        assert_eq!(
+            parse_with_syntax(r#"{'it\'s': ['"x"']}"#, single_quotes),
+            Ok(json!({"it's": ["\"x\""]}))
+        );
+        assert!(parse("['a']").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_trailing_data() {
+        let expected = Err(JsonError::Parse(TokenParseError::TrailingTokens));
+
+        assert_eq!(parse("true xyz"), expected);
+        assert_eq!(parse("[1] [2]"), expected);
+        #[cfg(feature = "std")]
+        assert_eq!(parse_from_reader("{} }".as_bytes()), expected);
+        assert_eq!(parse(" [1]\n "), Ok(Value::Array(vec![Value::Number(1.0)])));
+    }
+
+    #[test]
+    fn parse_skips_a_byte_order_mark() {
+        let input = "\u{feff}{\"a\": 1}";
+
+        assert_eq!(parse(input), Ok(json!({"a": 1})));
+        assert_eq!(parse_bytes(input), Ok(json!({"a": 1})));
+        #[cfg(feature = "std")]
+        assert_eq!(parse_from_reader(input.as_bytes()), Ok(json!({"a": 1})));
+        assert_eq!(
+            parse("\u{feff}"),
+            Err(JsonError::Parse(TokenParseError::EmptyInput))
+        );
+    }
+
+    #[test]
+    fn parse_prefix_reports_consumed_length() {
+        let (value, len) = parse_prefix(
+            r#" {"a": 1}
+rest of the message"#,
+        )
+        .unwrap();
+
+        assert_eq!(value, parse(r#"{"a": 1}"#).unwrap());
+        assert_eq!(len, 9);
+        assert_eq!(
+            parse_prefix("  "),
+            Err(JsonError::Parse(TokenParseError::EmptyInput))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parse_from_reader_matches_parse() {
+        let input = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e"}"#;
+
+        assert_eq!(parse_from_reader(input.as_bytes()), parse(input));
+    }
+
+    #[test]
+    fn byte_and_reader_input_within_options() {
+        let limits = Limits {
+            max_depth: 2,
+            max_document_size: 16,
+            max_string_length: 3,
+            ..Limits::default()
+        };
+        let options = Parser::builder().syntax(Syntax::json5()).limits(limits);
+        let inputs = ["[1, 2,]", "[[[]]]", "['abcd']", "[1,                 2]"];
+
+        for input in inputs {
+            let expected = parse_with_options(input, options);
+            assert_eq!(
+                parse_bytes_with_options(input, options),
+                expected,
+                "{input}"
+            );
+            #[cfg(feature = "std")]
+            assert_eq!(
+                parse_from_reader_with_options(input.as_bytes(), options),
+                expected,
+                "{input}"
+            );
+        }
+
+        // the limit applies to the bytes given, before transcoding
+        let utf16: Vec<u8> = "[1,    2]"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            parse_bytes_with_options(utf16, options),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                Limit::DocumentSize
+            )))
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn parse_async_matches_parse() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let parse_async =
+            |input: &'static str| runtime.block_on(super::parse_async(input.as_bytes()));
+
+        let input = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e"}"#;
+        assert_eq!(parse_async(input), parse(input));
+        assert_eq!(parse_async("[1, tru"), parse("[1, tru"));
+        assert_eq!(parse_async(" "), parse(" "));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn parse_async_within_options() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let options = Parser::builder().limits(Limits {
+            max_depth: 1,
+            max_document_size: 8,
+            ..Limits::default()
+        });
+        let parse_async = |input: &'static str| {
+            runtime.block_on(super::parse_async_with_options(input.as_bytes(), options))
+        };
+
+        for input in ["[1, 2]", "[[1]]", "[1,     2]"] {
+            assert_eq!(
+                parse_async(input),
+                parse_with_options(input, options),
+                "{input}"
+            );
+        }
+    }
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn parse_async_a_byte_at_a_time() {
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+        use tokio::io::{AsyncRead, ReadBuf};
+
+        /// Hands out its input a byte per read, as a slow connection might
+        struct Trickle(&'static [u8]);
+
+        impl AsyncRead for Trickle {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                if let Some((first, rest)) = self.0.split_first() {
+                    buf.put_slice(&[*first]);
+                    self.0 = rest;
+                }
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let options = Parser::builder().syntax(Syntax::json5()).limits(Limits {
+            max_depth: 3,
+            max_string_length: 8,
+            ..Limits::default()
+        });
+        let inputs = [
+            r#"{"a": [1, -2.5e3, {"b": null}], "c": "d\"\u00e9", "t": true}"#,
+            "// comment\n[1, /* inline */ 'two', 0x10,]",
+            "[[[[1]]]]",
+            r#"["too long a string"]"#,
+            "[1, tru",
+            "[1] 2",
+        ];
+
+        for input in inputs {
+            assert_eq!(
+                runtime.block_on(super::parse_async(Trickle(input.as_bytes()))),
+                parse(input),
+                "{input}"
+            );
+            assert_eq!(
+                runtime.block_on(super::parse_async_with_options(
+                    Trickle(input.as_bytes()),
+                    options
+                )),
+                parse_with_options(input, options),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_bytes_validates_utf8_while_lexing() {
+        let valid = "[\"olá\", \"💩\"]".as_bytes().to_vec();
+        let expected = Value::Array(vec![
+            Value::String("olá".into()),
+            Value::String("💩".into()),
+        ]);
+
+        assert_eq!(parse_bytes(&valid), Ok(expected));
+        assert_eq!(
+            parse_bytes(b"[\"ok\", \"\xf0\x9f\"]"),
+            Err(JsonError::Tokenize(TokenizeError::InvalidUtf8(8)))
+        );
+        assert_eq!(
+            parse_bytes(b"[\xe9]"),
+            Err(JsonError::Tokenize(TokenizeError::InvalidUtf8(1)))
+        );
+        assert_eq!(
+            parse_bytes("[é]"),
+            Err(JsonError::Tokenize(TokenizeError::CharNotRecognized('é')))
+        );
+    }
+
+    #[test]
+    fn parse_bytes_transcodes_utf16_and_utf32() {
+        let text = "\u{feff}{\"a\": [\"😀\", 1]}";
+        let expected = Ok(json!({"a": ["😀", 1]}));
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let utf16le: Vec<u8> = utf16.iter().flat_map(|unit| unit.to_le_bytes()).collect();
+        let utf16be: Vec<u8> = utf16[1..]
+            .iter()
+            .flat_map(|unit| unit.to_be_bytes())
+            .collect();
+        let utf32le: Vec<u8> = text
+            .chars()
+            .flat_map(|c| u32::from(c).to_le_bytes())
+            .collect();
+        let utf32be: Vec<u8> = text
+            .chars()
+            .skip(1)
+            .flat_map(|c| u32::from(c).to_be_bytes())
+            .collect();
+
+        for input in [utf16le, utf16be, utf32le, utf32be] {
+            assert_eq!(parse_bytes(&input), expected);
+        }
+        assert_eq!(
+            parse_bytes(b"\"\0\x00\xd8\"\0"),
+            Err(JsonError::Tokenize(TokenizeError::InvalidEncoding(2)))
+        );
+        let lossy = parse_bytes_with(b"\"\0\x00\xd8\"\0", Utf8Mode::Replace).unwrap();
+        assert_eq!(lossy.value, json!("\u{fffd}"));
+        assert_eq!(lossy.replacements, [2]);
+        assert_eq!(parse_bytes(b"1\0"), Ok(json!(1)));
+    }
+
+    #[test]
+    fn parse_bytes_strict_rejects_invalid_utf8() {
+        let input = b"[\"caf\xe9\"]";
+        let expected = JsonError::Tokenize(TokenizeError::InvalidUtf8(5));
+
+        assert_eq!(parse_bytes_with(input, Utf8Mode::Strict), Err(expected));
+    }
+
+    #[test]
+    fn parse_bytes_replace_keeps_document() {
+        let input = b"[\"caf\xe9\", \"ok\"]";
+        let expected = BytesParse {
+            value: Value::Array(vec![
+                Value::String("caf\u{fffd}".into()),
+                Value::String("ok".into()),
+            ]),
+            replacements: vec![5],
+        };
+
+        assert_eq!(parse_bytes_with(input, Utf8Mode::Replace), Ok(expected));
+        assert_eq!(
+            parse_bytes_lossy(b"[\"\xff\xfe\xfd\", \xe9]"),
+            Err(JsonError::Tokenize(TokenizeError::CharNotRecognized(
+                '\u{fffd}'
+            )))
+        );
+        assert_eq!(parse_bytes_lossy(b"\"\xc3\""), Ok(json!("\u{fffd}")));
+    }
 }
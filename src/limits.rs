@@ -0,0 +1,155 @@
+// Bounds on what a document may make the parser allocate
+
+use super::DEFAULT_MAX_DEPTH;
+use super::parser::TokenParseError;
+
+/// Bounds checked while parsing, so that untrusted input can't make the
+/// parser allocate without limit
+///
+/// Every bound but the depth is lifted by default. Set the ones needed with
+/// struct update syntax and parse with
+/// [`parse_with_limits`](crate::parse_with_limits) or
+/// [`Parser::with_limits`](crate::Parser::with_limits), or pass them in
+/// [`ParserOptions`](crate::ParserOptions) to the `*_with_options` functions
+/// parsing bytes, readers and pushed chunks:
+///
+/// ```
+/// use parser_json_rs::{JsonError, Limit, Limits, TokenParseError, parse_with_limits};
+///
+/// let limits = Limits { max_array_length: 2, ..Limits::default() };
+/// assert_eq!(
+///     parse_with_limits("[1, 2, 3]", limits),
+///     Err(JsonError::Parse(TokenParseError::LimitExceeded(Limit::ArrayLength)))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// how deeply arrays and objects may be nested, exceeding it is a
+    /// [`TokenParseError::DepthLimitExceeded`]
+    pub max_depth: usize,
+    /// bytes of input
+    pub max_document_size: usize,
+    /// bytes of a string or key, once its escapes are resolved
+    pub max_string_length: usize,
+    /// elements of an array
+    pub max_array_length: usize,
+    /// members of an object, a repeated key counting once
+    pub max_object_length: usize,
+}
+
+/// The bound of [`Limits`] a document went past
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    DocumentSize,
+    StringLength,
+    ArrayLength,
+    ObjectLength,
+}
+
+impl Limits {
+    /// Only the given depth limit
+    pub(crate) fn depth(max_depth: usize) -> Self {
+        Limits {
+            max_depth,
+            max_document_size: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_length: usize::MAX,
+        }
+    }
+
+    /// Check the size in bytes of a whole document
+    pub(crate) fn check_document(&self, len: usize) -> Result<(), TokenParseError> {
+        check(len, self.max_document_size, Limit::DocumentSize)
+    }
+
+    pub(crate) fn check_string(&self, s: &str) -> Result<(), TokenParseError> {
+        check(s.len(), self.max_string_length, Limit::StringLength)
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::depth(DEFAULT_MAX_DEPTH)
+    }
+}
+
+fn check(len: usize, max: usize, limit: Limit) -> Result<(), TokenParseError> {
+    if len > max {
+        Err(TokenParseError::LimitExceeded(limit))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limit, Limits};
+    use crate::prelude::*;
+    use crate::{JsonError, Parser, TokenParseError, Value, parse, parse_with_limits};
+
+    fn exceeded(limit: Limit) -> Result<Value, JsonError> {
+        Err(JsonError::Parse(TokenParseError::LimitExceeded(limit)))
+    }
+
+    #[test]
+    fn each_limit_is_named_when_exceeded() {
+        let limits = Limits {
+            max_document_size: 32,
+            max_string_length: 3,
+            max_array_length: 2,
+            max_object_length: 1,
+            ..Limits::default()
+        };
+        let parse_within = |input: &str| parse_with_limits(input, limits);
+
+        assert_eq!(
+            parse_within(&format!("[{}]", " ".repeat(31))),
+            exceeded(Limit::DocumentSize)
+        );
+        assert_eq!(parse_within(r#"["abcd"]"#), exceeded(Limit::StringLength));
+        assert_eq!(
+            parse_within(r#"{"abcd": 1}"#),
+            exceeded(Limit::StringLength)
+        );
+        assert_eq!(parse_within("[1, [2, 3, 4]]"), exceeded(Limit::ArrayLength));
+        assert_eq!(
+            parse_within(r#"{"a": 1, "b": 2}"#),
+            exceeded(Limit::ObjectLength)
+        );
+
+        let within = r#"[{"a": "ét"}, [1, 2]]"#;
+        assert_eq!(parse_within(within), parse(within));
+        // a repeated key replaces the member rather than adding one
+        assert_eq!(parse_within(r#"{"a": 1, "a": 2}"#), parse(r#"{"a": 2}"#));
+    }
+
+    #[test]
+    fn default_limits_only_bound_the_depth() {
+        let input = format!("[{}]", "1,".repeat(10_000) + "1");
+
+        assert_eq!(parse_with_limits(&input, Limits::default()), parse(&input));
+        assert_eq!(
+            parse_with_limits(&"[".repeat(1000), Limits::default()),
+            Err(JsonError::Parse(TokenParseError::DepthLimitExceeded))
+        );
+    }
+
+    #[test]
+    fn parser_with_limits() {
+        let mut parser = Parser::with_limits(Limits {
+            max_document_size: 8,
+            ..Limits::default()
+        });
+        let mut value = Value::Null;
+
+        assert_eq!(parser.parse_into("[1, 2]", &mut value), Ok(()));
+        assert_eq!(
+            parser.parse_into("[1, 2, 3]", &mut value),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                Limit::DocumentSize
+            )))
+        );
+        assert_eq!(value, Value::Null);
+    }
+}
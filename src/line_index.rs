@@ -0,0 +1,146 @@
+// Converting between byte offsets and lines and columns of a source text
+
+use super::prelude::*;
+
+/// A position in a text, both counted from 1, the column in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where the lines of a text start, for turning the byte offsets of spans
+/// and errors into the lines and columns people read, and back
+///
+/// A line ends at `\n` or `\r\n`, and columns count characters rather than
+/// bytes:
+///
+/// ```
+/// use parser_json_rs::{LineCol, LineIndex};
+///
+/// let text = "{\r\n  \"é\": x\n}";
+/// let index = LineIndex::new(text);
+/// let offset = text.find('x').unwrap();
+///
+/// assert_eq!(index.line_col(offset), Some(LineCol { line: 2, column: 8 }));
+/// assert_eq!(index.offset(LineCol { line: 2, column: 8 }), Some(offset));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// byte offset of the start of every line
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            text.bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        LineIndex { text, starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The line and column of the character starting at `offset`, `None`
+    /// if `offset` is past the end of the text or inside a character. The
+    /// end of the text and every byte of a line break are at the column
+    /// just past the last character of their line.
+    pub fn line_col(&self, offset: usize) -> Option<LineCol> {
+        if !self.text.is_char_boundary(offset) {
+            return None;
+        }
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let text = self.line(line)?;
+        let within = (offset - self.starts[line]).min(text.len());
+        let column = text[..within].chars().count() + 1;
+
+        Some(LineCol {
+            line: line + 1,
+            column,
+        })
+    }
+
+    /// The byte offset of the character at `position`, `None` if there is
+    /// no such line or the line is shorter. The column just past the last
+    /// character of a line is where its line break starts.
+    pub fn offset(&self, position: LineCol) -> Option<usize> {
+        let line = self.line(position.line.checked_sub(1)?)?;
+        let start = self.starts[position.line - 1];
+        let column = position.column.checked_sub(1)?;
+
+        match line.char_indices().nth(column) {
+            Some((i, _)) => Some(start + i),
+            None if column == line.chars().count() => Some(start + line.len()),
+            None => None,
+        }
+    }
+
+    /// The text of line number `line`, counted from 1, without its line
+    /// break
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        self.line(line.checked_sub(1)?)
+    }
+
+    /// The text of the line at the 0-based `index`, without its line break
+    fn line(&self, index: usize) -> Option<&'a str> {
+        let start = *self.starts.get(index)?;
+        let end = self
+            .starts
+            .get(index + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        let line = &self.text[start..end];
+        Some(line.strip_suffix('\r').unwrap_or(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineCol, LineIndex};
+
+    fn at(line: usize, column: usize) -> LineCol {
+        LineCol { line, column }
+    }
+
+    #[test]
+    fn offsets_to_lines_and_columns() {
+        let text = "ab\r\ncé\n\nd";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_col(0), Some(at(1, 1)));
+        assert_eq!(index.line_col(2), Some(at(1, 3)));
+        assert_eq!(index.line_col(3), Some(at(1, 3)));
+        assert_eq!(index.line_col(4), Some(at(2, 1)));
+        assert_eq!(index.line_col(5), Some(at(2, 2)));
+        assert_eq!(index.line_col(6), None);
+        assert_eq!(index.line_col(7), Some(at(2, 3)));
+        assert_eq!(index.line_col(8), Some(at(3, 1)));
+        assert_eq!(index.line_col(9), Some(at(4, 1)));
+        assert_eq!(index.line_col(10), Some(at(4, 2)));
+        assert_eq!(index.line_col(11), None);
+    }
+
+    #[test]
+    fn lines_and_columns_to_offsets() {
+        let text = "ab\r\ncé\n\nd";
+        let index = LineIndex::new(text);
+
+        for offset in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let position = index.line_col(offset).unwrap();
+            // the `\r` of `\r\n` and the `\n` both end the first line
+            let expected = if offset == 3 { 2 } else { offset };
+            assert_eq!(index.offset(position), Some(expected), "{offset}");
+        }
+        assert_eq!(index.offset(at(1, 4)), None);
+        assert_eq!(index.offset(at(5, 1)), None);
+        assert_eq!(index.offset(at(0, 1)), None);
+        assert_eq!(index.offset(at(1, 0)), None);
+    }
+}
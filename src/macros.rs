@@ -0,0 +1,130 @@
+// The `json!` macro for writing values inline
+
+/// Build a [`Value`](crate::Value) from JSON-like syntax, interpolating any
+/// Rust expression convertible with `Value::from`
+///
+/// ```
+/// use parser_json_rs::json;
+///
+/// let name = "ana";
+/// let value = json!({
+///     "name": name,
+///     "tags": [1, 2.5, null, true],
+///     "nested": {"id": 40 + 2},
+/// });
+///
+/// assert_eq!(value["nested"]["id"], json!(42));
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::Value::Null
+    };
+    ([]) => {
+        $crate::Value::Array($crate::__private::vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::Value::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::Value::Object($crate::Map::default())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::Map::default();
+        $crate::json_internal!(@object object $($tt)+);
+        $crate::Value::Object(object)
+    }};
+    ($other:expr) => {
+        $crate::Value::from($other)
+    };
+}
+
+/// Munches the elements of arrays and members of objects for [`json!`],
+/// one at a time up to the next top level comma
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    (@array [$($elements:expr,)*]) => {
+        $crate::__private::vec![$($elements,)*]
+    };
+    (@array [$($elements:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elements,)* $crate::Value::Null,] $($($rest)*)?)
+    };
+    (@array [$($elements:expr,)*] [ $($element:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elements,)* $crate::json!([ $($element)* ]),] $($($rest)*)?)
+    };
+    (@array [$($elements:expr,)*] { $($member:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elements,)* $crate::json!({ $($member)* }),] $($($rest)*)?)
+    };
+    (@array [$($elements:expr,)*] $element:expr $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elements,)* $crate::json!($element),] $($($rest)*)?)
+    };
+
+    (@object $object:ident) => {};
+    (@object $object:ident $key:tt : null $(, $($rest:tt)*)?) => {
+        $object.insert($crate::__private::String::from($key), $crate::Value::Null);
+        $( $crate::json_internal!(@object $object $($rest)*); )?
+    };
+    (@object $object:ident $key:tt : [ $($element:tt)* ] $(, $($rest:tt)*)?) => {
+        $object.insert($crate::__private::String::from($key), $crate::json!([ $($element)* ]));
+        $( $crate::json_internal!(@object $object $($rest)*); )?
+    };
+    (@object $object:ident $key:tt : { $($member:tt)* } $(, $($rest:tt)*)?) => {
+        $object.insert($crate::__private::String::from($key), $crate::json!({ $($member)* }));
+        $( $crate::json_internal!(@object $object $($rest)*); )?
+    };
+    (@object $object:ident $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $object.insert($crate::__private::String::from($key), $crate::json!($value));
+        $( $crate::json_internal!(@object $object $($rest)*); )?
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Map;
+    use crate::prelude::*;
+
+    use crate::{Value, parse};
+
+    #[test]
+    fn json_literals() {
+        assert_eq!(json!(null), Value::Null);
+        assert_eq!(json!(true), Value::Boolean(true));
+        assert_eq!(json!(-1.5), Value::Number(-1.5));
+        assert_eq!(json!("a"), Value::String("a".into()));
+        assert_eq!(json!([]), Value::Array(Vec::new()));
+        assert_eq!(json!({}), Value::Object(Map::default()));
+    }
+
+    #[test]
+    fn json_nested() {
+        let value = json!({
+            "a": [1, null, {"b": false}, []],
+            "c": {"d": {}, "e": "f"},
+            "g": null,
+        });
+
+        assert_eq!(
+            value,
+            parse(r#"{"a": [1, null, {"b": false}, []], "c": {"d": {}, "e": "f"}, "g": null}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn json_interpolates_expressions() {
+        let name = String::from("ana");
+        let key = "computed";
+        let count = 3u8;
+        let value = json!({
+            "name": name,
+            (key): count * 2,
+            "list": [count, -(count as i32), json!([1])],
+        });
+
+        assert_eq!(
+            value,
+            parse(r#"{"name": "ana", "computed": 6, "list": [3, -3, [1]]}"#).unwrap()
+        );
+    }
+}
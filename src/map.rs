@@ -0,0 +1,50 @@
+// The map holding the members of objects: the standard `HashMap` unless only
+// `alloc` is available, hashing with SipHash unless `fast-hash` is enabled,
+// or a `BTreeMap` with `sorted-keys`
+
+#[cfg(feature = "std")]
+pub use std::collections::HashMap;
+
+#[cfg(not(feature = "std"))]
+pub use hashbrown::HashMap;
+
+/// How the keys of objects are hashed: FxHash with the `fast-hash` feature,
+/// which is several times faster on short keys but, unlike the default, not
+/// resistant to inputs crafted to collide
+///
+/// Unused with the `sorted-keys` feature.
+#[cfg(feature = "fast-hash")]
+pub type ObjectHasher = rustc_hash::FxBuildHasher;
+
+#[cfg(all(not(feature = "fast-hash"), feature = "std"))]
+pub type ObjectHasher = std::hash::RandomState;
+
+#[cfg(all(not(feature = "fast-hash"), not(feature = "std")))]
+pub type ObjectHasher = hashbrown::DefaultHashBuilder;
+
+/// The map holding the members of a [`Value::Object`](crate::Value::Object)
+///
+/// `std::collections::HashMap`, or `hashbrown::HashMap` when built for
+/// `no_std` with only the `alloc` feature, hashing with [`ObjectHasher`].
+/// With the `sorted-keys` feature it is a `BTreeMap` instead, which is
+/// slower to build but iterates, and so serializes, in key order.
+///
+/// Create maps with `Map::default()` rather than `HashMap::new()`, which
+/// only exists for the default hasher.
+#[cfg(not(feature = "sorted-keys"))]
+pub type Map<K, V> = HashMap<K, V, ObjectHasher>;
+
+#[cfg(feature = "sorted-keys")]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+#[cfg(all(not(feature = "sorted-keys"), feature = "std"))]
+pub use std::collections::hash_map::{Entry, Iter, IterMut};
+
+#[cfg(all(not(feature = "sorted-keys"), not(feature = "std")))]
+pub use hashbrown::hash_map::{Iter, IterMut};
+
+#[cfg(all(not(feature = "sorted-keys"), not(feature = "std")))]
+pub type Entry<'a, K, V> = hashbrown::hash_map::Entry<'a, K, V, ObjectHasher>;
+
+#[cfg(feature = "sorted-keys")]
+pub use alloc::collections::btree_map::{Entry, Iter, IterMut};
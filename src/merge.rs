@@ -0,0 +1,129 @@
+// Deep merging of one value into another, e.g. configuration overrides into
+// defaults
+
+use super::Value;
+
+/// How [`Value::merge`] combines two values
+///
+/// The default merges objects member by member, lets arrays and nulls
+/// replace what they are merged into, and is a shorthand for building one
+/// with struct update syntax:
+///
+/// ```
+/// use parser_json_rs::{ArrayMerge, MergeStrategy};
+///
+/// let strategy = MergeStrategy { arrays: ArrayMerge::Concat, ..MergeStrategy::default() };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeStrategy {
+    pub objects: ObjectMerge,
+    pub arrays: ArrayMerge,
+    pub nulls: NullMerge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectMerge {
+    /// Merge members present in both objects, keeping the others from both
+    #[default]
+    Recurse,
+    /// Replace the object as a whole
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    #[default]
+    Replace,
+    /// Append the merged array's elements
+    Concat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullMerge {
+    /// A null replaces the value like any other
+    #[default]
+    Replace,
+    /// A null leaves the value as it is
+    Ignore,
+    /// A null object member removes the member, as in a JSON Merge Patch
+    Remove,
+}
+
+impl Value {
+    /// Merge `other` into this value, `other` taking precedence wherever
+    /// `strategy` doesn't combine the two
+    pub fn merge(&mut self, other: Value, strategy: MergeStrategy) {
+        match (self, other) {
+            (_, Value::Null) if strategy.nulls == NullMerge::Ignore => {}
+            (Value::Object(map), Value::Object(members))
+                if strategy.objects == ObjectMerge::Recurse =>
+            {
+                for (key, value) in members {
+                    match (map.get_mut(&key), value) {
+                        (_, Value::Null) if strategy.nulls == NullMerge::Remove => {
+                            map.remove(&key);
+                        }
+                        (Some(existing), value) => existing.merge(value, strategy),
+                        (None, value) => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (Value::Array(values), Value::Array(elements))
+                if strategy.arrays == ArrayMerge::Concat =>
+            {
+                values.extend(elements);
+            }
+            (this, other) => *this = other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrayMerge, MergeStrategy, NullMerge, ObjectMerge};
+
+    #[test]
+    fn default_merges_objects_deeply() {
+        let mut config = json!({"server": {"port": 80, "hosts": ["a"]}, "debug": false});
+        let overrides = json!({"server": {"hosts": ["b"], "tls": null}, "debug": true});
+        config.merge(overrides, MergeStrategy::default());
+
+        assert_eq!(
+            config,
+            json!({"server": {"port": 80, "hosts": ["b"], "tls": null}, "debug": true})
+        );
+    }
+
+    #[test]
+    fn strategies_change_arrays_objects_and_nulls() {
+        let base = json!({"a": {"b": 1, "c": [1]}, "d": 2, "e": 3});
+        let other = json!({"a": {"c": [2]}, "d": null});
+
+        let mut concat = base.clone();
+        let strategy = MergeStrategy {
+            arrays: ArrayMerge::Concat,
+            nulls: NullMerge::Ignore,
+            ..MergeStrategy::default()
+        };
+        concat.merge(other.clone(), strategy);
+        assert_eq!(concat, json!({"a": {"b": 1, "c": [1, 2]}, "d": 2, "e": 3}));
+
+        let mut removed = base.clone();
+        let strategy = MergeStrategy {
+            nulls: NullMerge::Remove,
+            ..MergeStrategy::default()
+        };
+        removed.merge(other.clone(), strategy);
+        assert_eq!(removed, json!({"a": {"b": 1, "c": [2]}, "e": 3}));
+
+        let mut shallow = base;
+        let strategy = MergeStrategy {
+            objects: ObjectMerge::Replace,
+            ..MergeStrategy::default()
+        };
+        shallow.merge(other.clone(), strategy);
+        assert_eq!(shallow, other);
+    }
+}
@@ -0,0 +1,111 @@
+//! [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7386): a partial
+//! document whose members replace those of the target and whose nulls
+//! delete them
+
+use super::Map;
+use super::Value;
+use super::prelude::*;
+
+/// Apply the merge patch `patch` to `target`
+///
+/// An object patch is merged member by member, a null member removing the
+/// target's member of that name. Any other patch replaces the target.
+pub fn apply(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !matches!(target, Value::Object(_)) {
+        *target = Value::Object(Map::default());
+    }
+    let Value::Object(map) = target else {
+        unreachable!("made an object above");
+    };
+
+    for (key, value) in members {
+        if *value == Value::Null {
+            map.remove(key);
+        } else {
+            apply(map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Merge patch turning `from` into `to`
+///
+/// Merge patches can't set a member to null, so a null member of `to` that
+/// `from` doesn't share is removed rather than set, as RFC 7386 notes.
+pub fn diff(from: &Value, to: &Value) -> Value {
+    let (Value::Object(from), Value::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+
+    let mut patch: Map<String, Value> = from
+        .keys()
+        .filter(|key| !to.contains_key(*key))
+        .map(|key| (key.clone(), Value::Null))
+        .collect();
+    for (key, value) in to {
+        match from.get(key) {
+            Some(previous) if previous == value => {}
+            Some(previous) => {
+                patch.insert(key.clone(), diff(previous, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, diff};
+    use crate::parse;
+
+    #[test]
+    fn apply_merges_objects() {
+        let mut target =
+            parse(r#"{"title": "Hello", "author": {"given": "J", "family": "D"}, "tags": ["a"]}"#)
+                .unwrap();
+        let patch =
+            parse(r#"{"title": "Bye", "author": {"family": null}, "tags": ["b"], "new": 1}"#)
+                .unwrap();
+        apply(&mut target, &patch);
+
+        let expected =
+            parse(r#"{"title": "Bye", "author": {"given": "J"}, "tags": ["b"], "new": 1}"#)
+                .unwrap();
+        assert_eq!(target, expected);
+    }
+
+    #[test]
+    fn apply_replaces_non_objects() {
+        let mut target = parse(r#"["a"]"#).unwrap();
+        apply(
+            &mut target,
+            &parse(r#"{"a": {"b": null}, "c": 1}"#).unwrap(),
+        );
+
+        assert_eq!(target, parse(r#"{"a": {}, "c": 1}"#).unwrap());
+
+        apply(&mut target, &parse("[1]").unwrap());
+        assert_eq!(target, parse("[1]").unwrap());
+    }
+
+    #[test]
+    fn diff_then_apply_round_trips() {
+        let from = parse(r#"{"a": 1, "b": {"c": [1], "d": true}, "e": "x"}"#).unwrap();
+        let to = parse(r#"{"a": 1, "b": {"c": [2], "f": false}, "g": {"h": 1}}"#).unwrap();
+        let patch = diff(&from, &to);
+
+        assert_eq!(
+            patch,
+            parse(r#"{"b": {"c": [2], "d": null, "f": false}, "e": null, "g": {"h": 1}}"#).unwrap()
+        );
+        let mut patched = from.clone();
+        apply(&mut patched, &patch);
+        assert_eq!(patched, to);
+    }
+}
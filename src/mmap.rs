@@ -0,0 +1,132 @@
+// Parsing files through a memory mapping rather than reading them in
+
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use super::{JsonError, ParserOptions, ValueRef};
+
+/// A file mapped into memory, see [`parse_file_mmap`]
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// The contents of the file
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Parse the file, validating UTF-8 while lexing, with strings and keys
+    /// without escapes pointing into the mapping
+    pub fn parse(&self) -> Result<ValueRef<'_>, JsonError> {
+        self.parse_with_options(ParserOptions::default())
+    }
+
+    /// [`MappedFile::parse`] with everything `options` configures, the
+    /// document size limit applying to the whole file
+    pub fn parse_with_options(&self, options: ParserOptions) -> Result<ValueRef<'_>, JsonError> {
+        options.parse_tokens(options.slice_tokens(&self.map)?)
+    }
+}
+
+/// Map the file at `path` into memory to parse it in place
+///
+/// Only the pages the parser touches are read from disk, and only strings
+/// with escapes are copied, so a file of several gigabytes parses without
+/// ever being held in memory as a whole. The returned [`ValueRef`] borrows
+/// from the mapping:
+///
+/// ```no_run
+/// use parser_json_rs::{JsonError, parse_file_mmap};
+///
+/// # fn main() -> Result<(), JsonError> {
+/// // SAFETY: nothing else writes to dump.json while it is mapped
+/// let file = unsafe { parse_file_mmap("dump.json")? };
+/// let value = file.parse()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Safety
+///
+/// The file must not be changed, by this process or any other, for as long
+/// as the returned [`MappedFile`] lives: truncating it makes reads of the
+/// lost pages fault, and other changes alter bytes the parser has already
+/// checked and values borrowing them, which is undefined behaviour.
+pub unsafe fn parse_file_mmap(path: impl AsRef<Path>) -> Result<MappedFile, JsonError> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is read-only, and keeping the file unchanged while
+    // it is mapped is the caller's obligation
+    let map = unsafe { Mmap::map(&file)? };
+
+    Ok(MappedFile { map })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::fs;
+
+    use std::path::{Path, PathBuf};
+
+    use super::{MappedFile, parse_file_mmap};
+    use crate::{JsonError, Limit, Limits, Parser, TokenParseError, ValueRef, parse_borrowed};
+
+    /// A path in the temporary directory no other test or test run uses
+    fn temp_path(test: &str) -> PathBuf {
+        let name = format!("parser-json-rs-{}-{test}.json", std::process::id());
+        std::env::temp_dir().join(name)
+    }
+
+    fn map_file(path: &Path) -> Result<MappedFile, JsonError> {
+        // SAFETY: each test owns its file and leaves it alone while mapped
+        unsafe { parse_file_mmap(path) }
+    }
+
+    #[test]
+    fn parse_a_mapped_file() {
+        let input = r#"{"name": "mapped", "escaped": "a\nb", "n": [1, 2.5]}"#;
+        let path = temp_path("parse_a_mapped_file");
+        fs::write(&path, input).unwrap();
+
+        let file = map_file(&path).unwrap();
+        let value = file.parse().unwrap();
+        assert_eq!(value, parse_borrowed(input).unwrap());
+        let ValueRef::Object(map) = &value else {
+            panic!("expected an object");
+        };
+        assert!(matches!(
+            map["name"],
+            ValueRef::String(Cow::Borrowed("mapped"))
+        ));
+        let limits = Limits {
+            max_document_size: 16,
+            ..Limits::default()
+        };
+        assert_eq!(
+            file.parse_with_options(Parser::builder().limits(limits)),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                Limit::DocumentSize
+            )))
+        );
+
+        drop(value);
+        drop(file);
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            map_file(&path).unwrap().parse(),
+            Err(JsonError::Parse(TokenParseError::EmptyInput))
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_files_are_io_errors() {
+        let path = temp_path("missing_files_are_io_errors");
+
+        assert!(matches!(map_file(&path), Err(JsonError::Io(_))));
+    }
+}
@@ -0,0 +1,116 @@
+//! [Newline delimited JSON](https://github.com/ndjson/ndjson-spec), one
+//! document per line
+
+use std::io::{self, Write};
+
+use super::Value;
+use super::ser::write_value;
+
+/// Writes each value as compact JSON on a line of its own
+///
+/// Strings are escaped so a value never spans more than one line. Each line
+/// is handed to the inner writer in a single `write_all`, wrap it in a
+/// [`BufWriter`](std::io::BufWriter) to batch lines further.
+pub struct Writer<W: Write> {
+    inner: W,
+    line: String,
+    /// flush after this many lines, `None` leaves flushing to the caller
+    flush_every: Option<usize>,
+    unflushed: usize,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Writer {
+            inner,
+            line: String::new(),
+            flush_every: None,
+            unflushed: 0,
+        }
+    }
+
+    /// Flush the inner writer after every `n` lines, so that lines reach
+    /// their destination while the stream is still being written
+    pub fn flush_every_n(mut self, n: usize) -> Self {
+        self.flush_every = Some(n.max(1));
+        self
+    }
+
+    /// Write `value` followed by a newline
+    pub fn write(&mut self, value: &Value) -> io::Result<()> {
+        self.line.clear();
+        write_value(&mut self.line, value).expect("writing to a String never fails");
+        self.line.push('\n');
+        self.inner.write_all(self.line.as_bytes())?;
+
+        self.unflushed += 1;
+        if self.flush_every.is_some_and(|n| self.unflushed >= n) {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.unflushed = 0;
+        self.inner.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The inner writer, lines written so far are not flushed
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use super::Writer;
+    use crate::{Value, parse};
+
+    /// Records the length of what had been written at every flush
+    #[derive(Default)]
+    struct Recorder {
+        written: Vec<u8>,
+        flushed_at: Vec<usize>,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed_at.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_one_value_per_line() {
+        let mut writer = Writer::new(Vec::new());
+        writer
+            .write(&parse(r#"{"msg": "two\nlines"}"#).unwrap())
+            .unwrap();
+        writer.write(&Value::Array(vec![Value::Null])).unwrap();
+
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(out, "{\"msg\":\"two\\nlines\"}\n[null]\n");
+    }
+
+    #[test]
+    fn flush_every_n_lines() {
+        let mut writer = Writer::new(Recorder::default()).flush_every_n(2);
+        for _ in 0..5 {
+            writer.write(&Value::Boolean(true)).unwrap();
+        }
+
+        // "true\n" is 5 bytes long
+        assert_eq!(writer.get_ref().flushed_at, vec![10, 20]);
+    }
+}
@@ -1,31 +1,364 @@
-use std::collections::HashMap;
-
-use super::Value;
-use super::tokenize::Token;
-
-type ParseResult = Result<Value, TokenParseError>;
-
-fn parse_tokens(tokens: &[Token], index: &mut usize) -> ParseResult {
-    let token = &tokens[*index];
-    if matches!(
-        token,
-        Token::Null | Token::False | Token::True | Token::Number(_) | Token::String(_)
-    ) {
-        *index += 1
-    }
-    match token {
-        Token::Null => Ok(Value::Null),
-        Token::False => Ok(Value::Boolean(false)),
-        Token::True => Ok(Value::Boolean(true)),
-        Token::Number(number) => Ok(Value::Number(*number)),
-        Token::String(string) => parse_string(string),
-        Token::LeftCurlyBracket => parse_object(tokens, index),
-        Token::LeftSquareBracket => parse_array(tokens, index),
-        _ => todo!(),
+use alloc::borrow::Cow;
+use core::hash::Hash;
+use core::iter::Peekable;
+use core::marker::PhantomData;
+use core::mem;
+use core::str::Chars;
+
+use super::Map;
+use super::limits::{Limit, Limits};
+use super::prelude::*;
+use super::tokenize::{Lexer, SliceSource, Token, TokenStream};
+use super::{DEFAULT_MAX_DEPTH, Diagnostic, JsonError, Syntax, Value};
+
+/// A tree the parser can build, letting owned [`Value`]s and borrowed
+/// [`ValueRef`](crate::ValueRef)s share one parser
+pub(crate) trait Node<'a>: Sized {
+    type Key: Ord + Hash;
+
+    fn null() -> Self;
+    fn boolean(b: bool) -> Self;
+    fn number(n: f64) -> Self;
+    /// `s` has its escapes resolved already
+    fn string(s: Cow<'a, str>) -> Self;
+    fn key(s: Cow<'a, str>) -> Self::Key;
+    fn array(arr: Vec<Self>) -> Self;
+    fn object(map: Map<Self::Key, Self>) -> Self;
+}
+
+impl<'a> Node<'a> for Value {
+    type Key = String;
+
+    fn null() -> Self {
+        Value::Null
+    }
+
+    fn boolean(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+
+    fn number(n: f64) -> Self {
+        Value::Number(n)
+    }
+
+    fn string(s: Cow<'a, str>) -> Self {
+        Value::String(s.into())
+    }
+
+    fn key(s: Cow<'a, str>) -> String {
+        s.into_owned()
+    }
+
+    fn array(arr: Vec<Self>) -> Self {
+        Value::Array(arr)
+    }
+
+    fn object(map: Map<String, Self>) -> Self {
+        Value::Object(map)
+    }
+}
+
+/// Builds the nodes of a tree that needs more than the tokens to do so, like
+/// the arena its nodes are allocated in
+pub(crate) trait Builder<'a> {
+    type Node;
+    type Key: Ord + Hash;
+
+    fn null(&self) -> Self::Node;
+    fn boolean(&self, b: bool) -> Self::Node;
+    fn number(&self, n: f64) -> Self::Node;
+    /// `s` has its escapes resolved already
+    fn string(&self, s: Cow<'a, str>) -> Self::Node;
+    fn key(&self, s: Cow<'a, str>) -> Self::Key;
+    fn array(&self, arr: Vec<Self::Node>) -> Self::Node;
+    fn object(&self, map: Map<Self::Key, Self::Node>) -> Self::Node;
+}
+
+/// Nodes that build themselves
+impl<'a, N: Node<'a>> Builder<'a> for PhantomData<N> {
+    type Node = N;
+    type Key = N::Key;
+
+    fn null(&self) -> N {
+        N::null()
+    }
+
+    fn boolean(&self, b: bool) -> N {
+        N::boolean(b)
+    }
+
+    fn number(&self, n: f64) -> N {
+        N::number(n)
+    }
+
+    fn string(&self, s: Cow<'a, str>) -> N {
+        N::string(s)
+    }
+
+    fn key(&self, s: Cow<'a, str>) -> N::Key {
+        N::key(s)
+    }
+
+    fn array(&self, arr: Vec<N>) -> N {
+        N::array(arr)
+    }
+
+    fn object(&self, map: Map<N::Key, N>) -> N {
+        N::object(map)
+    }
+}
+
+/// Parse one value, pulling tokens from `tokens` only as they are needed
+/// and failing as soon as it goes past `limits`
+pub(crate) fn parse<'a, N, I, E>(tokens: I, limits: &Limits) -> Result<N, JsonError>
+where
+    N: Node<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    parse_with(&PhantomData::<N>, tokens, limits)
+}
+
+/// Parse one value into nodes made by `builder`
+pub(crate) fn parse_with<'a, B, I, E>(
+    builder: &B,
+    tokens: I,
+    limits: &Limits,
+) -> Result<B::Node, JsonError>
+where
+    B: Builder<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    parse_with_scratch(builder, tokens, limits, &mut Scratch::default())
+}
+
+fn parse_with_scratch<'a, B, I, E>(
+    builder: &B,
+    tokens: I,
+    limits: &Limits,
+    scratch: &mut Scratch<B::Node, B::Key>,
+) -> Result<B::Node, JsonError>
+where
+    B: Builder<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    let mut tokens = tokens;
+    let value = parse_next_with(builder, &mut tokens, limits, scratch)?
+        .ok_or(TokenParseError::EmptyInput)?;
+
+    match tokens.next().map(|token| token.map_err(JsonError::from)) {
+        None => Ok(value),
+        // failing to read past the value is not about what the input holds
+        #[cfg(feature = "std")]
+        Some(Err(err @ JsonError::Io(_))) => Err(err),
+        Some(_) => Err(TokenParseError::TrailingTokens.into()),
+    }
+}
+
+/// Parse the value starting at the next token, `None` if there are no tokens
+/// left. Tokens following the value are left unread.
+pub(crate) fn parse_next<'a, N, I, E>(
+    tokens: &mut I,
+    limits: &Limits,
+) -> Result<Option<N>, JsonError>
+where
+    N: Node<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    parse_next_with(&PhantomData::<N>, tokens, limits, &mut Scratch::default())
+}
+
+fn parse_next_with<'a, B, I, E>(
+    builder: &B,
+    tokens: &mut I,
+    limits: &Limits,
+    scratch: &mut Scratch<B::Node, B::Key>,
+) -> Result<Option<B::Node>, JsonError>
+where
+    B: Builder<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    // the value is complete before anything past it is peeked at
+    let mut tokens = tokens.peekable();
+    if tokens.peek().is_none() {
+        return Ok(None);
+    }
+    parse_tokens(builder, &mut tokens, limits, scratch).map(Some)
+}
+
+fn next_token<'a, I, E>(tokens: &mut I) -> Result<Token<'a>, JsonError>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    Ok(tokens.next().ok_or(TokenParseError::UnexpectedEnd)??)
+}
+
+/// Consume the next token if it is `expected`
+fn next_if<'a, I, E>(tokens: &mut Peekable<I>, expected: &Token) -> bool
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+{
+    tokens
+        .next_if(|token| matches!(token, Ok(token) if token == expected))
+        .is_some()
+}
+
+/// A container whose members are still being parsed
+enum Frame<N, K> {
+    Array(Vec<N>),
+    /// the object so far and the key of the member being parsed
+    Object(Map<K, N>, Option<K>),
+}
+
+/// Memory the parser works in, which can be kept from one value to the next
+struct Scratch<N, K> {
+    /// the open containers, innermost last
+    stack: Vec<Frame<N, K>>,
+    /// empty arrays and objects to fill before allocating new ones
+    arrays: Vec<Vec<N>>,
+    objects: Vec<Map<K, N>>,
+}
+
+impl<N, K> Default for Scratch<N, K> {
+    fn default() -> Self {
+        Scratch {
+            stack: Vec::new(),
+            arrays: Vec::new(),
+            objects: Vec::new(),
+        }
     }
 }
 
-fn parse_string(s: &str) -> ParseResult {
+/// Parse one value within `limits`. Open containers are kept on an explicit
+/// stack rather than the call stack, so the depth is only bounded by
+/// `limits.max_depth` and available memory.
+fn parse_tokens<'a, B, I, E>(
+    builder: &B,
+    tokens: &mut Peekable<I>,
+    limits: &Limits,
+    scratch: &mut Scratch<B::Node, B::Key>,
+) -> Result<B::Node, JsonError>
+where
+    B: Builder<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    let Scratch {
+        stack,
+        arrays,
+        objects,
+    } = scratch;
+    // a frame left open by a failed parse doesn't belong to this value
+    stack.clear();
+
+    loop {
+        let mut value = match next_token(tokens)? {
+            Token::Null => builder.null(),
+            Token::False => builder.boolean(false),
+            Token::True => builder.boolean(true),
+            Token::Number(number) => builder.number(number),
+            Token::String(string) => {
+                let string = unescape(string)?;
+                limits.check_string(&string)?;
+                builder.string(string)
+            }
+            Token::LeftCurlyBracket | Token::LeftSquareBracket
+                if stack.len() == limits.max_depth =>
+            {
+                return Err(TokenParseError::DepthLimitExceeded.into());
+            }
+            Token::LeftSquareBracket => {
+                if next_if(tokens, &Token::RightSquareBracket) {
+                    builder.array(Vec::new())
+                } else {
+                    stack.push(Frame::Array(arrays.pop().unwrap_or_default()));
+                    continue;
+                }
+            }
+            Token::LeftCurlyBracket => {
+                if next_if(tokens, &Token::RightCurlyBracket) {
+                    builder.object(Map::default())
+                } else {
+                    let key = parse_key(builder, tokens, limits)?;
+                    stack.push(Frame::Object(objects.pop().unwrap_or_default(), Some(key)));
+                    continue;
+                }
+            }
+            _ => return Err(TokenParseError::ExpectedValue.into()),
+        };
+
+        // the value is complete, add it to its container and close every
+        // container that ends right after it
+        loop {
+            match stack.last_mut() {
+                None => return Ok(value),
+                Some(Frame::Array(arr)) => {
+                    if arr.len() == limits.max_array_length {
+                        return Err(TokenParseError::LimitExceeded(Limit::ArrayLength).into());
+                    }
+                    arr.push(value);
+                    match next_token(tokens)? {
+                        Token::Comma => break,
+                        Token::RightSquareBracket => {
+                            value = builder.array(mem::take(arr));
+                            stack.pop();
+                        }
+                        _ => return Err(TokenParseError::ExpectedComma.into()),
+                    }
+                }
+                Some(Frame::Object(map, key)) => {
+                    let member_key = key.take().expect("a key is read before every value");
+                    map.insert(member_key, value);
+                    if map.len() > limits.max_object_length {
+                        return Err(TokenParseError::LimitExceeded(Limit::ObjectLength).into());
+                    }
+                    match next_token(tokens)? {
+                        Token::Comma => {
+                            *key = Some(parse_key(builder, tokens, limits)?);
+                            break;
+                        }
+                        Token::RightCurlyBracket => {
+                            value = builder.object(mem::take(map));
+                            stack.pop();
+                        }
+                        _ => return Err(TokenParseError::ExpectedComma.into()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parse the `"key":` of an object member
+fn parse_key<'a, B, I, E>(builder: &B, tokens: &mut I, limits: &Limits) -> Result<B::Key, JsonError>
+where
+    B: Builder<'a>,
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    let Token::String(s) = next_token(tokens)? else {
+        return Err(TokenParseError::ExpectedProperty.into());
+    };
+    let s = unescape(s)?;
+    limits.check_string(&s)?;
+    if next_token(tokens)? != Token::Colon {
+        return Err(TokenParseError::ExpectedColon.into());
+    }
+    Ok(builder.key(s))
+}
+
+/// Resolve the escape sequences in the raw contents of a string token, only
+/// allocating when there are any
+pub(crate) fn unescape(s: Cow<'_, str>) -> Result<Cow<'_, str>, TokenParseError> {
+    if !s.contains('\\') {
+        return Ok(s);
+    }
+
     let mut output = String::with_capacity(s.len());
     let mut is_escaping = false;
     let mut chars = s.chars();
@@ -36,27 +369,15 @@ fn parse_string(s: &str) -> ParseResult {
                 '"' => output.push('"'),
                 '\\' => output.push('\\'),
                 'b' => output.push('\u{8}'),
-                'f' => output.push('\u{12}'),
-                '\n' => output.push('\n'),
-                '\r' => output.push('\r'),
-                '\t' => output.push('\t'),
-                'u' => {
-                    let mut sum = 0;
-                    for i in 0..4 {
-                        let next_char = chars.next().ok_or(TokenParseError::UnfinishedEscape)?;
-                        let digit = next_char
-                            .to_digit(16)
-                            .ok_or(TokenParseError::InvalidHexValue)?;
-                        sum += (16u32).pow(3 - i) * digit;
-                    }
-                    let unescape_char =
-                        char::from_u32(sum).ok_or(TokenParseError::InvalidCodePointValue)?;
-                    output.push(unescape_char);
-                }
+                'f' => output.push('\u{c}'),
+                'n' => output.push('\n'),
+                'r' => output.push('\r'),
+                't' => output.push('\t'),
+                'u' => output.push(unicode_escape(&mut chars)?),
                 // any other character *may* be escaped, ex. `\q` just push that letter `q`
                 _ => output.push(next_char),
             }
-            is_escaping = true;
+            is_escaping = false;
         } else if next_char == '\\' {
             is_escaping = true;
         } else {
@@ -64,89 +385,387 @@ fn parse_string(s: &str) -> ParseResult {
         }
     }
 
-    Ok(Value::String(output))
+    Ok(Cow::Owned(output))
 }
 
-fn parse_array(tokens: &[Token], index: &mut usize) -> ParseResult {
-    let mut arr: Vec<Value> = Vec::new();
-    loop {
-        // consume previous left bracket or comma token
-        *index += 1;
-        if tokens[*index] == Token::RightSquareBracket {
-            break;
-        }
-        let value = parse_tokens(tokens, index)?;
-        arr.push(value);
-
-        let token = &tokens[*index];
-        match token {
-            Token::Comma => {}
-            Token::RightSquareBracket => break,
-            _ => return Err(TokenParseError::ExpectedComma),
-        }
+/// Read the character of a `\u` escape whose `\u` is already consumed,
+/// along with the escaped low surrogate that must follow a high one
+pub(crate) fn unicode_escape(chars: &mut Chars<'_>) -> Result<char, TokenParseError> {
+    let code = hex_digits(chars)?;
+    if !(0xd800..0xdc00).contains(&code) {
+        return char::from_u32(code).ok_or(TokenParseError::InvalidCodePointValue);
+    }
+
+    if chars.next() != Some('\\') || chars.next() != Some('u') {
+        return Err(TokenParseError::InvalidCodePointValue);
+    }
+    let low = hex_digits(chars)?;
+    if !(0xdc00..0xe000).contains(&low) {
+        return Err(TokenParseError::InvalidCodePointValue);
     }
-    // consume right bracket token
-    *index += 1;
-    Ok(Value::Array(arr))
+    char::from_u32(0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00))
+        .ok_or(TokenParseError::InvalidCodePointValue)
 }
 
-fn parse_object(tokens: &[Token], index: &mut usize) -> ParseResult {
-    let mut map = HashMap::new();
-    loop {
-        // consume previous left brace or comma
-        *index += 1;
-        if tokens[*index] == Token::RightCurlyBracket {
-            break;
-        }
-        if let Token::String(s) = &tokens[*index] {
-            *index += 1;
-            if tokens[*index] == Token::Colon {
-                *index += 1;
-                let key = s.clone();
-                let value = parse_tokens(tokens, index)?;
-                println!("{:?}", value);
-                map.insert(key, value);
-            } else {
-                return Err(TokenParseError::ExpectedColon);
-            }
-            match &tokens[*index] {
-                Token::Comma => {}
-                Token::RightCurlyBracket => break,
-                _ => return Err(TokenParseError::ExpectedComma),
+/// The four hex digits of a `\u` escape
+fn hex_digits(chars: &mut Chars<'_>) -> Result<u32, TokenParseError> {
+    let mut code = 0;
+    for _ in 0..4 {
+        let c = chars.next().ok_or(TokenParseError::UnfinishedEscape)?;
+        code = code * 16 + c.to_digit(16).ok_or(TokenParseError::InvalidHexValue)?;
+    }
+    Ok(code)
+}
+
+/// A parser keeping its working memory from one document to the next
+///
+/// [`parse`](crate::parse) allocates every array and object of a document
+/// afresh. Services parsing many small documents one after the other can
+/// instead hand the previous document back to [`Parser::parse_into`], which
+/// takes it apart and fills its arrays and objects, and the stack of open
+/// containers, with the next one:
+///
+/// ```
+/// use parser_json_rs::{Parser, Value};
+///
+/// let mut parser = Parser::new();
+/// let mut value = Value::Null;
+/// for message in [r#"{"id": 1, "tags": ["a"]}"#, r#"{"id": 2, "tags": []}"#] {
+///     parser.parse_into(message, &mut value).unwrap();
+///     assert!(value["id"].is_number());
+/// }
+/// ```
+pub struct Parser {
+    options: ParserOptions,
+    scratch: Scratch<Value, String>,
+    /// values of the previous document still to be taken apart
+    pending: Vec<Value>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::with_max_depth(DEFAULT_MAX_DEPTH)
+    }
+
+    /// A parser allowing arrays and objects to be nested at most
+    /// `max_depth` levels deep
+    pub fn with_max_depth(max_depth: usize) -> Self {
+        Parser::with_limits(Limits::depth(max_depth))
+    }
+
+    /// A parser rejecting documents that go past `limits`
+    pub fn with_limits(limits: Limits) -> Self {
+        Parser::builder().limits(limits).build()
+    }
+
+    /// Options for a parser other than the default one, see [`ParserOptions`]
+    pub fn builder() -> ParserOptions {
+        ParserOptions::default()
+    }
+
+    /// Parse a JSON document from text
+    pub fn parse(&mut self, input: &str) -> Result<Value, JsonError> {
+        let mut value = Value::Null;
+        self.parse_into(input, &mut value)?;
+        Ok(value)
+    }
+
+    /// Parse a JSON document from text into `value`, reusing the arrays and
+    /// objects of what `value` held before. `value` is null if parsing fails.
+    pub fn parse_into(&mut self, input: &str, value: &mut Value) -> Result<(), JsonError> {
+        self.recycle(mem::replace(value, Value::Null));
+        let ParserOptions { syntax, limits } = &self.options;
+        limits.check_document(input.len())?;
+        *value = parse_with_scratch(
+            &PhantomData::<Value>,
+            Lexer::with_syntax(input, *syntax),
+            limits,
+            &mut self.scratch,
+        )?;
+        Ok(())
+    }
+
+    /// Parse a JSON document from text, an error coming with the span of the
+    /// input it was found at, as [`parse_with_diagnostic`](crate::parse_with_diagnostic)
+    /// does for strict JSON
+    ///
+    /// ```
+    /// use parser_json_rs::{Parser, Syntax};
+    ///
+    /// let input = "{a: 1 b: 2}";
+    /// let err = Parser::builder()
+    ///     .syntax(Syntax::json5())
+    ///     .build()
+    ///     .parse_with_diagnostic(input)
+    ///     .unwrap_err();
+    /// assert_eq!(&input[err.span.clone()], "b");
+    /// ```
+    pub fn parse_with_diagnostic(&mut self, input: &str) -> Result<Value, Diagnostic> {
+        let ParserOptions { syntax, limits } = &self.options;
+        // a document too large is refused before any of it is read
+        limits
+            .check_document(input.len())
+            .map_err(|err| Diagnostic {
+                error: err.into(),
+                span: 0..0,
+            })?;
+        let mut tokens = Lexer::with_syntax(input, *syntax);
+        parse_with_scratch(
+            &PhantomData::<Value>,
+            &mut tokens,
+            limits,
+            &mut self.scratch,
+        )
+        .map_err(|error| Diagnostic {
+            error,
+            span: tokens.span(),
+        })
+    }
+
+    /// Empty every array and object of `value` and keep them for reuse
+    fn recycle(&mut self, value: Value) {
+        self.pending.push(value);
+        while let Some(value) = self.pending.pop() {
+            match value {
+                Value::Array(mut arr) => {
+                    self.pending.append(&mut arr);
+                    self.scratch.arrays.push(arr);
+                }
+                Value::Object(mut map) => {
+                    // unlike `drain`, `retain` is there for every kind of map,
+                    // and keeps a hash map's capacity
+                    map.retain(|_, value| {
+                        self.pending.push(mem::replace(value, Value::Null));
+                        false
+                    });
+                    self.scratch.objects.push(map);
+                }
+                _ => {}
             }
-        } else {
-            return Err(TokenParseError::ExpectedProperty);
         }
     }
-    // consume right brace
-    *index += 1;
-    Ok(Value::Object(map))
 }
 
-#[derive(Debug, PartialEq)]
-enum TokenParseError {
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
+}
+
+/// Everything a [`Parser`] can be configured with, passed on to the lexer
+/// and the parser alike
+///
+/// Start from [`Parser::builder`], which is strict JSON within the default
+/// [`Limits`], and change what is needed:
+///
+/// ```
+/// use parser_json_rs::{Limits, Parser, Syntax, json};
+///
+/// let mut parser = Parser::builder()
+///     .syntax(Syntax::jsonc())
+///     .limits(Limits { max_string_length: 64, ..Limits::default() })
+///     .max_depth(8)
+///     .build();
+/// assert_eq!(parser.parse("[1] // one").unwrap(), json!([1]));
+/// assert!(parser.parse(&"[".repeat(9)).is_err());
+/// ```
+///
+/// The free functions building a [`Value`] or a [`ValueRef`](crate::ValueRef)
+/// all parse with the default options. The trees kept for other purposes,
+/// [`parse_spanned`](crate::parse_spanned), [`parse_tape`](crate::parse_tape),
+/// [`Document`](crate::edit::Document), [`validate`](crate::validate) and
+/// [`parse_lossy`](crate::parse_lossy), are built by parsers of their own
+/// nesting at most [`DEFAULT_MAX_DEPTH`] levels deep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub(crate) syntax: Syntax,
+    pub(crate) limits: Limits,
+}
+
+impl ParserOptions {
+    /// Accept the extensions to JSON `syntax` enables
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Reject documents that go past `limits`
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Allow arrays and objects to be nested at most `max_depth` levels
+    /// deep, leaving the other limits as they are
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.limits.max_depth = max_depth;
+        self
+    }
+
+    /// Parse a JSON document from text with these options, see
+    /// [`parse_with_options`](crate::parse_with_options)
+    pub(crate) fn parse(&self, input: &str) -> Result<Value, JsonError> {
+        self.parse_tokens(self.lexer(input)?)
+    }
+
+    /// The tokens of `input` in this syntax, once it is known to be within
+    /// the document size limit
+    pub(crate) fn lexer<'a>(&self, input: &'a str) -> Result<Lexer<'a>, JsonError> {
+        self.limits.check_document(input.len())?;
+        Ok(Lexer::with_syntax(input, self.syntax))
+    }
+
+    /// [`ParserOptions::lexer`] for input that may not be UTF-8
+    pub(crate) fn slice_tokens<'a>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<TokenStream<'a, SliceSource<'a>>, JsonError> {
+        self.limits.check_document(input.len())?;
+        Ok(TokenStream::with_syntax(
+            SliceSource::new(input),
+            self.syntax,
+        ))
+    }
+
+    /// Parse one value from `tokens` within these limits, whatever it is
+    /// built into
+    pub(crate) fn parse_tokens<'a, N, I, E>(&self, tokens: I) -> Result<N, JsonError>
+    where
+        N: Node<'a>,
+        I: Iterator<Item = Result<Token<'a>, E>>,
+        JsonError: From<E>,
+    {
+        parse(tokens, &self.limits)
+    }
+
+    /// Parse the value starting at the next of `tokens` within these limits,
+    /// leaving the tokens after it unread
+    pub(crate) fn parse_next<'a, N, I, E>(&self, tokens: &mut I) -> Result<Option<N>, JsonError>
+    where
+        N: Node<'a>,
+        I: Iterator<Item = Result<Token<'a>, E>>,
+        JsonError: From<E>,
+    {
+        parse_next(tokens, &self.limits)
+    }
+
+    pub fn build(self) -> Parser {
+        Parser {
+            options: self,
+            scratch: Scratch::default(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenParseError {
+    EmptyInput,
+    UnexpectedEnd,
+    ExpectedValue,
     UnfinishedEscape,
     InvalidHexValue,
     InvalidCodePointValue,
     ExpectedComma,
     ExpectedProperty,
     ExpectedColon,
+    /// arrays and objects are nested deeper than the configured maximum
+    DepthLimitExceeded,
+    /// anything but whitespace follows a complete value
+    TrailingTokens,
+    /// the document goes past one of the configured [`Limits`]
+    LimitExceeded(Limit),
+}
+
+impl TokenParseError {
+    /// See [`JsonError::code`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenParseError::EmptyInput => "E101_EMPTY_INPUT",
+            TokenParseError::UnexpectedEnd => "E102_UNEXPECTED_END",
+            TokenParseError::ExpectedValue => "E103_EXPECTED_VALUE",
+            TokenParseError::UnfinishedEscape => "E104_UNFINISHED_ESCAPE",
+            TokenParseError::InvalidHexValue => "E105_INVALID_HEX_ESCAPE",
+            TokenParseError::InvalidCodePointValue => "E106_INVALID_CODE_POINT",
+            TokenParseError::ExpectedComma => "E107_EXPECTED_COMMA",
+            TokenParseError::ExpectedProperty => "E108_EXPECTED_KEY",
+            TokenParseError::ExpectedColon => "E109_EXPECTED_COLON",
+            TokenParseError::DepthLimitExceeded => "E110_DEPTH_LIMIT_EXCEEDED",
+            TokenParseError::TrailingTokens => "E111_TRAILING_CHARACTERS",
+            TokenParseError::LimitExceeded(Limit::DocumentSize) => "E112_DOCUMENT_TOO_LARGE",
+            TokenParseError::LimitExceeded(Limit::StringLength) => "E113_STRING_TOO_LONG",
+            TokenParseError::LimitExceeded(Limit::ArrayLength) => "E114_ARRAY_TOO_LONG",
+            TokenParseError::LimitExceeded(Limit::ObjectLength) => "E115_OBJECT_TOO_LONG",
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use crate::Map;
+    use crate::prelude::*;
 
-    use crate::{Value, tokenize::Token};
+    use crate::{DEFAULT_MAX_DEPTH, JsonError, Value, tokenize::Token};
 
-    use super::parse_tokens;
+    use super::TokenParseError;
 
-    fn check(input: &[Token], expected: Value) {
-        let actual = parse_tokens(&input, &mut 0).unwrap();
+    fn parse(input: &[Token<'static>], max_depth: usize) -> Result<Value, JsonError> {
+        super::parse(
+            input.iter().cloned().map(Ok::<_, JsonError>),
+            &super::Limits::depth(max_depth),
+        )
+    }
+
+    fn check(input: &[Token<'static>], expected: Value) {
+        let actual = parse(input, DEFAULT_MAX_DEPTH).unwrap();
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn parse_within_max_depth() {
+        // [[null]]
+        let input = [
+            Token::LeftSquareBracket,
+            Token::LeftSquareBracket,
+            Token::Null,
+            Token::RightSquareBracket,
+            Token::RightSquareBracket,
+        ];
+        let expected = Value::Array(vec![Value::Array(vec![Value::Null])]);
+
+        assert_eq!(parse(&input, 2), Ok(expected));
+        assert_eq!(
+            parse(&input, 1),
+            Err(TokenParseError::DepthLimitExceeded.into())
+        );
+    }
+
+    #[test]
+    fn parse_rejects_trailing_comma() {
+        // [null,]
+        let input = [
+            Token::LeftSquareBracket,
+            Token::Null,
+            Token::Comma,
+            Token::RightSquareBracket,
+        ];
+
+        assert_eq!(parse(&input, 1), Err(TokenParseError::ExpectedValue.into()));
+    }
+
+    #[test]
+    fn parse_unfinished_object() {
+        // {"a":
+        let input = [
+            Token::LeftCurlyBracket,
+            Token::String("a".into()),
+            Token::Colon,
+        ];
+
+        assert_eq!(parse(&input, 1), Err(TokenParseError::UnexpectedEnd.into()));
+    }
+
     #[test]
     fn parses_null() {
         let input = [Token::Null];
@@ -211,6 +830,28 @@ mod tests {
         check(&input, expected);
     }
 
+    #[test]
+    fn parse_string_unescape_control_characters() {
+        let input = [Token::String(r#"a\nb\tc\"d"#.into())];
+        let expected = Value::String("a\nb\tc\"d".into());
+
+        check(&input, expected);
+    }
+
+    #[test]
+    fn parse_string_unescape_surrogate_pairs() {
+        let input = [Token::String(r#"\ud83d\ude00 \u00e9"#.into())];
+        let expected = Value::String("😀 é".into());
+
+        check(&input, expected);
+        for unpaired in [r#"\ud83d"#, r#"\ud83dx"#, r#"\ud83d\u0041"#, r#"\ude00"#] {
+            assert_eq!(
+                super::unescape(unpaired.into()),
+                Err(TokenParseError::InvalidCodePointValue)
+            );
+        }
+    }
+
     #[test]
     fn parses_array_one_element() {
         // [true]
@@ -269,7 +910,7 @@ mod tests {
     fn parse_empty_object() {
         // {}
         let input = [Token::LeftCurlyBracket, Token::RightCurlyBracket];
-        let expected = Value::Object(HashMap::new());
+        let expected = Value::Object(Map::default());
 
         check(&input, expected);
     }
@@ -277,8 +918,8 @@ mod tests {
     #[test]
     fn parse_object_one_item() {
         // {"a": "A"}
-        let mut map = HashMap::new();
-        map.insert(String::from("a"), Value::String(String::from("A")));
+        let mut map = Map::default();
+        map.insert(String::from("a"), Value::String("A".into()));
         let input = [
             Token::LeftCurlyBracket,
             Token::String("a".into()),
@@ -294,8 +935,8 @@ mod tests {
     #[test]
     fn parse_object_two_items() {
         // {"a": "A", "b": null}
-        let mut map = HashMap::new();
-        map.insert(String::from("a"), Value::String(String::from("A")));
+        let mut map = Map::default();
+        map.insert(String::from("a"), Value::String("A".into()));
         map.insert(String::from("b"), Value::Null);
         let input = [
             Token::LeftCurlyBracket,
@@ -316,7 +957,7 @@ mod tests {
     #[test]
     fn parse_object_nested_with_array() {
         // {"a": [null, 6]}
-        let mut map = HashMap::new();
+        let mut map = Map::default();
         map.insert(
             String::from("a"),
             Value::Array(vec![Value::Null, Value::Number(6f64)]),
@@ -340,8 +981,8 @@ mod tests {
     #[test]
     fn parse_object_nested_with_object() {
         // {"a": {"b": 6}}
-        let mut map = HashMap::new();
-        let mut inner = HashMap::new();
+        let mut map = Map::default();
+        let mut inner = Map::default();
         inner.insert(String::from("b"), Value::Number(6f64));
         map.insert(String::from("a"), Value::Object(inner));
         let input = [
@@ -359,4 +1000,111 @@ mod tests {
 
         check(&input, expected);
     }
+    #[test]
+    fn parse_matches_json_macro() {
+        // {"a": [null, 6, {"b": "B"}], "c": {}}
+        let input = [
+            Token::LeftCurlyBracket,
+            Token::String("a".into()),
+            Token::Colon,
+            Token::LeftSquareBracket,
+            Token::Null,
+            Token::Comma,
+            Token::Number(6f64),
+            Token::Comma,
+            Token::LeftCurlyBracket,
+            Token::String("b".into()),
+            Token::Colon,
+            Token::String("B".into()),
+            Token::RightCurlyBracket,
+            Token::RightSquareBracket,
+            Token::Comma,
+            Token::String("c".into()),
+            Token::Colon,
+            Token::LeftCurlyBracket,
+            Token::RightCurlyBracket,
+            Token::RightCurlyBracket,
+        ];
+
+        check(&input, json!({"a": [null, 6, {"b": "B"}], "c": {}}));
+    }
+
+    #[test]
+    fn parse_rejects_trailing_tokens() {
+        let input = [Token::True, Token::Null];
+
+        assert_eq!(
+            parse(&input, DEFAULT_MAX_DEPTH),
+            Err(JsonError::Parse(TokenParseError::TrailingTokens))
+        );
+    }
+
+    #[test]
+    fn parse_into_matches_parse() {
+        let mut parser = super::Parser::new();
+        let mut value = json!({"old": [1, 2, {"x": null}]});
+
+        for input in [r#"{"a": [1, {"b": "c"}], "d": []}"#, "[true, [false]]", "7"] {
+            parser.parse_into(input, &mut value).unwrap();
+            assert_eq!(Ok(&value), crate::parse(input).as_ref());
+        }
+    }
+
+    #[test]
+    fn parse_into_reuses_arrays() {
+        let mut parser = super::Parser::new();
+        let mut value = Value::Null;
+        parser
+            .parse_into("[1, 2, 3, 4, 5, 6, 7, 8]", &mut value)
+            .unwrap();
+        parser.parse_into("[1]", &mut value).unwrap();
+
+        let Value::Array(arr) = &value else {
+            panic!("expected an array");
+        };
+        assert!(arr.capacity() >= 8);
+    }
+
+    #[test]
+    fn parse_into_recovers_from_errors() {
+        let mut parser = super::Parser::with_max_depth(2);
+        let mut value = json!([1]);
+
+        assert_eq!(
+            parser.parse_into("[[[1]]]", &mut value),
+            Err(JsonError::Parse(TokenParseError::DepthLimitExceeded))
+        );
+        assert_eq!(value, Value::Null);
+        parser.parse_into(r#"{"a": [2]}"#, &mut value).unwrap();
+        assert_eq!(value, json!({"a": [2]}));
+    }
+
+    #[test]
+    fn builder_options_reach_lexer_and_parser() {
+        let limits = crate::Limits {
+            max_array_length: 2,
+            ..crate::Limits::default()
+        };
+        let mut parser = super::Parser::builder()
+            .limits(limits)
+            .syntax(crate::Syntax::json5())
+            .build();
+
+        assert_eq!(parser.parse("[+1, 0x2,]"), Ok(json!([1, 2])));
+        assert_eq!(
+            parser.parse("[1, 2, 3]"),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                crate::Limit::ArrayLength
+            )))
+        );
+        assert!(super::Parser::new().parse("[+1]").is_err());
+        assert_eq!(
+            crate::parse_with_options("[1, 2, 3]", parser.options),
+            parser.parse("[1, 2, 3]")
+        );
+        assert_eq!(
+            crate::parse_with_options("[+1, 0x2,]", parser.options),
+            Ok(json!([1, 2]))
+        );
+    }
 }
@@ -0,0 +1,641 @@
+//! [JSON Patch](https://www.rfc-editor.org/rfc/rfc6902) documents describing
+//! how to turn one value into another
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use super::Map;
+use super::Value;
+use super::pointer::{escape_token, parse_index, unescape_token};
+use super::prelude::*;
+
+/// One operation of a [`Patch`], with paths written as JSON Pointers
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Operations to apply in order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Patch(pub Vec<Operation>);
+
+/// Why a patch could not be read or applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// the patch document is not an array
+    NotAnArray,
+    /// index of an operation that isn't an object with a known `op` and the
+    /// members that operation needs
+    InvalidOperation(usize),
+    /// a path or `from` naming no value, or no place a value can be added
+    PathNotFound(String),
+    /// path of a `test` operation that found a different value
+    TestFailed(String),
+    /// path of a `move` into a member or element of what it moves
+    MoveIntoItself(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::NotAnArray => f.write_str("a JSON Patch must be an array of operations"),
+            PatchError::InvalidOperation(index) => write!(f, "operation {index} is malformed"),
+            PatchError::PathNotFound(path) => write!(f, "nothing at `{path}`"),
+            PatchError::TestFailed(path) => write!(f, "test of `{path}` failed"),
+            PatchError::MoveIntoItself(path) => {
+                write!(f, "can't move a value into itself at `{path}`")
+            }
+        }
+    }
+}
+
+impl core::error::Error for PatchError {}
+
+impl Patch {
+    /// The patch as a JSON array of operation objects
+    pub fn to_value(&self) -> Value {
+        Value::Array(self.0.iter().map(operation_value).collect())
+    }
+
+    /// Read a patch from its JSON form, an array of operation objects
+    ///
+    /// ```
+    /// use parser_json_rs::json;
+    /// use parser_json_rs::patch::{self, Patch};
+    ///
+    /// let patch = Patch::from_value(&json!([{"op": "add", "path": "/b", "value": 2}]))?;
+    /// let mut value = json!({"a": 1});
+    /// patch::apply(&mut value, &patch)?;
+    /// assert_eq!(value, json!({"a": 1, "b": 2}));
+    /// # Ok::<(), patch::PatchError>(())
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Patch, PatchError> {
+        let operations = value.as_array().ok_or(PatchError::NotAnArray)?;
+        operations
+            .iter()
+            .enumerate()
+            .map(|(index, operation)| {
+                parse_operation(operation).ok_or(PatchError::InvalidOperation(index))
+            })
+            .collect::<Result<_, _>>()
+            .map(Patch)
+    }
+}
+
+fn parse_operation(operation: &Value) -> Option<Operation> {
+    let member = |name: &str| operation.as_object()?.get(name);
+    let pointer = |name: &str| member(name)?.as_str().map(String::from);
+    let path = pointer("path")?;
+    Some(match member("op")?.as_str()? {
+        "add" => Operation::Add {
+            path,
+            value: member("value")?.clone(),
+        },
+        "remove" => Operation::Remove { path },
+        "replace" => Operation::Replace {
+            path,
+            value: member("value")?.clone(),
+        },
+        "move" => Operation::Move {
+            from: pointer("from")?,
+            path,
+        },
+        "copy" => Operation::Copy {
+            from: pointer("from")?,
+            path,
+        },
+        "test" => Operation::Test {
+            path,
+            value: member("value")?.clone(),
+        },
+        _ => return None,
+    })
+}
+
+fn operation_value(operation: &Operation) -> Value {
+    let string = |s: &str| Value::String(s.into());
+    let (op, members) = match operation {
+        Operation::Add { path, value } => (
+            "add",
+            vec![("path", string(path)), ("value", value.clone())],
+        ),
+        Operation::Remove { path } => ("remove", vec![("path", string(path))]),
+        Operation::Replace { path, value } => (
+            "replace",
+            vec![("path", string(path)), ("value", value.clone())],
+        ),
+        Operation::Move { from, path } => {
+            ("move", vec![("from", string(from)), ("path", string(path))])
+        }
+        Operation::Copy { from, path } => {
+            ("copy", vec![("from", string(from)), ("path", string(path))])
+        }
+        Operation::Test { path, value } => (
+            "test",
+            vec![("path", string(path)), ("value", value.clone())],
+        ),
+    };
+
+    let mut object: Map<String, Value> = members
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .collect();
+    object.insert(String::from("op"), string(op));
+    Value::Object(object)
+}
+
+/// Apply `patch` to `target`, operation by operation
+///
+/// Either every operation succeeds or `target` is left as it was, as RFC
+/// 6902 requires. Unlike [`Value::insert_at_pointer`], adding to a place
+/// whose parent doesn't exist is an error.
+pub fn apply(target: &mut Value, patch: &Patch) -> Result<(), PatchError> {
+    let mut patched = target.clone();
+    for operation in &patch.0 {
+        apply_operation(&mut patched, operation)?;
+    }
+    *target = patched;
+    Ok(())
+}
+
+fn apply_operation(target: &mut Value, operation: &Operation) -> Result<(), PatchError> {
+    match operation {
+        Operation::Add { path, value } => add(target, path, value.clone()),
+        Operation::Remove { path } => remove(target, path).map(drop),
+        Operation::Replace { path, value } => {
+            let slot = target
+                .pointer_mut(path)
+                .ok_or_else(|| PatchError::PathNotFound(path.clone()))?;
+            *slot = value.clone();
+            Ok(())
+        }
+        Operation::Move { from, path } => {
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                return Err(PatchError::MoveIntoItself(path.clone()));
+            }
+            let value = remove(target, from)?;
+            add(target, path, value)
+        }
+        Operation::Copy { from, path } => {
+            let value = target
+                .pointer(from)
+                .ok_or_else(|| PatchError::PathNotFound(from.clone()))?
+                .clone();
+            add(target, path, value)
+        }
+        Operation::Test { path, value } => match target.pointer(path) {
+            Some(found) if found == value => Ok(()),
+            Some(_) => Err(PatchError::TestFailed(path.clone())),
+            None => Err(PatchError::PathNotFound(path.clone())),
+        },
+    }
+}
+
+/// The container holding what `path` points to and the last token of
+/// `path`, `None` for the empty path, which points to the whole value
+fn parent_of<'v, 'p>(
+    target: &'v mut Value,
+    path: &'p str,
+) -> Result<Option<(&'v mut Value, Cow<'p, str>)>, PatchError> {
+    let Some((parent, token)) = path.rsplit_once('/') else {
+        return if path.is_empty() {
+            Ok(None)
+        } else {
+            Err(PatchError::PathNotFound(path.to_owned()))
+        };
+    };
+    let parent = target
+        .pointer_mut(parent)
+        .ok_or_else(|| PatchError::PathNotFound(path.to_owned()))?;
+    Ok(Some((parent, unescape_token(token))))
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    let Some((parent, token)) = parent_of(target, path)? else {
+        *target = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(map) => {
+            map.insert(token.into_owned(), value);
+            Ok(())
+        }
+        Value::Array(values) => {
+            let index = match &*token {
+                "-" => values.len(),
+                token => parse_index(token)
+                    .filter(|&index| index <= values.len())
+                    .ok_or_else(|| PatchError::PathNotFound(path.to_owned()))?,
+            };
+            values.insert(index, value);
+            Ok(())
+        }
+        _ => Err(PatchError::PathNotFound(path.to_owned())),
+    }
+}
+
+fn remove(target: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let not_found = || PatchError::PathNotFound(path.to_owned());
+    let (parent, token) = parent_of(target, path)?.ok_or_else(not_found)?;
+    match parent {
+        Value::Object(map) => map.remove(&*token).ok_or_else(not_found),
+        Value::Array(values) => {
+            let index = parse_index(&token)
+                .filter(|&index| index < values.len())
+                .ok_or_else(not_found)?;
+            Ok(values.remove(index))
+        }
+        _ => Err(not_found()),
+    }
+}
+
+/// Patch turning `from` into `to`
+///
+/// Unchanged parts are left out, object members are added, removed or
+/// diffed individually, and arrays are compared by longest common
+/// subsequence so that inserting or removing an element produces a single
+/// operation rather than a cascade of replacements.
+pub fn diff(from: &Value, to: &Value) -> Patch {
+    let mut operations = Vec::new();
+    diff_at(from, to, String::new(), &mut operations);
+    Patch(operations)
+}
+
+fn diff_at(from: &Value, to: &Value, path: String, operations: &mut Vec<Operation>) {
+    match (from, to) {
+        _ if from == to => {}
+        (Value::Object(from), Value::Object(to)) => diff_objects(from, to, &path, operations),
+        (Value::Array(from), Value::Array(to)) => diff_arrays(from, to, &path, operations),
+        _ => operations.push(Operation::Replace {
+            path,
+            value: to.clone(),
+        }),
+    }
+}
+
+fn diff_objects(
+    from: &Map<String, Value>,
+    to: &Map<String, Value>,
+    path: &str,
+    operations: &mut Vec<Operation>,
+) {
+    // sorted so the same documents always give the same patch
+    let mut keys: Vec<&String> = from
+        .keys()
+        .chain(to.keys().filter(|key| !from.contains_key(*key)))
+        .collect();
+    keys.sort();
+
+    for key in keys {
+        let member = format!("{path}/{}", escape_token(key));
+        match (from.get(key), to.get(key)) {
+            (Some(a), Some(b)) => diff_at(a, b, member, operations),
+            (Some(_), None) => operations.push(Operation::Remove { path: member }),
+            (None, Some(b)) => operations.push(Operation::Add {
+                path: member,
+                value: b.clone(),
+            }),
+            (None, None) => unreachable!("keys come from either object"),
+        }
+    }
+}
+
+/// Step of an edit script between two arrays
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Delete,
+    /// insert the element of the target array at this index
+    Insert(usize),
+}
+
+fn diff_arrays(from: &[Value], to: &[Value], path: &str, operations: &mut Vec<Operation>) {
+    let edits = edit_script(from, to);
+
+    // `index` follows the position in the array as the operations so far
+    // have left it, `i` the position in `from`
+    let mut index = 0;
+    let mut i = 0;
+    let mut edits = edits.into_iter().peekable();
+    while let Some(edit) = edits.next() {
+        match edit {
+            Edit::Keep => {
+                index += 1;
+                i += 1;
+            }
+            // a deletion followed by an insertion is a change in place,
+            // diffed further in case only part of the element changed
+            Edit::Delete if matches!(edits.peek(), Some(Edit::Insert(_))) => {
+                let Some(Edit::Insert(j)) = edits.next() else {
+                    unreachable!("peeked above");
+                };
+                diff_at(&from[i], &to[j], format!("{path}/{index}"), operations);
+                index += 1;
+                i += 1;
+            }
+            Edit::Delete => {
+                operations.push(Operation::Remove {
+                    path: format!("{path}/{index}"),
+                });
+                i += 1;
+            }
+            Edit::Insert(j) => {
+                operations.push(Operation::Add {
+                    path: format!("{path}/{index}"),
+                    value: to[j].clone(),
+                });
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Most entries of the table [`lcs_edits`] fills, a few megabytes, beyond
+/// which arrays are diffed position by position instead
+const MAX_LCS_TABLE: usize = 1 << 20;
+
+/// Edits turning `from` into `to` along their longest common subsequence,
+/// with the deletions of every changed run ahead of its insertions
+///
+/// The common subsequence takes time and memory proportional to the product
+/// of the lengths of the changed runs. When that is more than
+/// [`MAX_LCS_TABLE`], the runs are paired up element by element, giving a
+/// longer patch that is still correct.
+fn edit_script(from: &[Value], to: &[Value]) -> Vec<Edit> {
+    let prefix = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+    let (from_rest, to_rest) = (&from[prefix..], &to[prefix..]);
+    let suffix = from_rest
+        .iter()
+        .rev()
+        .zip(to_rest.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &from_rest[..from_rest.len() - suffix];
+    let b = &to_rest[..to_rest.len() - suffix];
+
+    let mut edits = vec![Edit::Keep; prefix];
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LCS_TABLE {
+        let mut deletes = vec![Edit::Delete; a.len()];
+        let mut inserts = (prefix..prefix + b.len()).map(Edit::Insert).collect();
+        edits.append(&mut interleave(&mut deletes, &mut inserts));
+    } else {
+        lcs_edits(a, b, prefix, &mut edits);
+    }
+    edits.extend(core::iter::repeat_n(Edit::Keep, suffix));
+    edits
+}
+
+/// Push the edits turning `a` into `b` along their longest common
+/// subsequence, `b` starting at `offset` in the target array
+fn lcs_edits(a: &[Value], b: &[Value], offset: usize, edits: &mut Vec<Edit>) {
+    // lcs[i][j] is the length of the common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut deletes = Vec::new();
+    let mut inserts = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.append(&mut interleave(&mut deletes, &mut inserts));
+            edits.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            deletes.push(Edit::Delete);
+            i += 1;
+        } else {
+            inserts.push(Edit::Insert(offset + j));
+            j += 1;
+        }
+    }
+    edits.append(&mut interleave(&mut deletes, &mut inserts));
+}
+
+/// Pair up the deletions and insertions of a changed run, so each pair
+/// becomes a change in place
+fn interleave(deletes: &mut Vec<Edit>, inserts: &mut Vec<Edit>) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(deletes.len() + inserts.len());
+    let mut inserts_left = inserts.drain(..);
+    for delete in deletes.drain(..) {
+        edits.push(delete);
+        edits.extend(inserts_left.next());
+    }
+    edits.extend(inserts_left);
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operation, Patch, PatchError, apply, diff};
+    use crate::prelude::*;
+    use crate::{Value, parse};
+
+    fn patch(from: &str, to: &str) -> Patch {
+        diff(&parse(from).unwrap(), &parse(to).unwrap())
+    }
+
+    #[test]
+    fn diff_equal_values() {
+        assert_eq!(patch(r#"{"a": [1]}"#, r#"{"a": [1]}"#), Patch::default());
+    }
+
+    #[test]
+    fn diff_objects() {
+        let expected = Patch(vec![
+            Operation::Remove {
+                path: String::from("/a"),
+            },
+            Operation::Replace {
+                path: String::from("/b/c"),
+                value: Value::Number(2.0),
+            },
+            Operation::Add {
+                path: String::from("/d~1e"),
+                value: Value::Null,
+            },
+        ]);
+
+        assert_eq!(
+            patch(
+                r#"{"a": 1, "b": {"c": 1}}"#,
+                r#"{"b": {"c": 2}, "d/e": null}"#
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn diff_arrays_by_common_subsequence() {
+        assert_eq!(
+            patch("[1, 2, 3, 4]", "[1, 2, 9, 3, 4]"),
+            Patch(vec![Operation::Add {
+                path: String::from("/2"),
+                value: Value::Number(9.0),
+            }])
+        );
+        assert_eq!(
+            patch("[1, 2, 3, 4]", "[1, 3, 4]"),
+            Patch(vec![Operation::Remove {
+                path: String::from("/1"),
+            }])
+        );
+    }
+
+    #[test]
+    fn diff_long_arrays_position_by_position() {
+        let from: Vec<Value> = (0..3000).map(|n| Value::Number(f64::from(n))).collect();
+        let mut to: Vec<Value> = from.iter().rev().cloned().collect();
+        to.push(Value::Null);
+        let (from, to) = (Value::Array(from), Value::Array(to));
+
+        let patch = diff(&from, &to);
+        assert_eq!(patch.0.len(), 3001);
+        assert!(
+            patch.0[..3000]
+                .iter()
+                .all(|op| matches!(op, Operation::Replace { .. }))
+        );
+        let mut patched = from.clone();
+        apply(&mut patched, &patch).unwrap();
+        assert_eq!(patched, to);
+    }
+
+    #[test]
+    fn diff_changed_array_elements_in_place() {
+        let expected = Patch(vec![
+            Operation::Replace {
+                path: String::from("/0/name"),
+                value: Value::String("b".into()),
+            },
+            Operation::Remove {
+                path: String::from("/2"),
+            },
+            Operation::Remove {
+                path: String::from("/2"),
+            },
+        ]);
+
+        assert_eq!(
+            patch(
+                r#"[{"name": "a", "id": 1}, true, 3, 4]"#,
+                r#"[{"name": "b", "id": 1}, true]"#
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn diff_replaces_different_types() {
+        let expected = Patch(vec![Operation::Replace {
+            path: String::new(),
+            value: Value::Array(Vec::new()),
+        }]);
+
+        assert_eq!(patch("{}", "[]"), expected);
+    }
+
+    #[test]
+    fn patch_to_value() {
+        let patch = Patch(vec![Operation::Move {
+            from: String::from("/a"),
+            path: String::from("/b"),
+        }]);
+
+        assert_eq!(
+            patch.to_value(),
+            parse(r#"[{"op": "move", "from": "/a", "path": "/b"}]"#).unwrap()
+        );
+    }
+
+    fn applied(target: &str, patch: &str) -> Result<Value, PatchError> {
+        let mut value = parse(target).unwrap();
+        apply(&mut value, &Patch::from_value(&parse(patch).unwrap())?)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn apply_each_operation() {
+        assert_eq!(
+            applied(
+                r#"{"a": {"b": [1, 3]}, "c": "x"}"#,
+                r#"[
+                    {"op": "add", "path": "/a/b/1", "value": 2},
+                    {"op": "add", "path": "/a/b/-", "value": 4},
+                    {"op": "remove", "path": "/c"},
+                    {"op": "replace", "path": "/a/b/0", "value": 0},
+                    {"op": "copy", "from": "/a/b", "path": "/d"},
+                    {"op": "move", "from": "/a", "path": "/e~1f"},
+                    {"op": "test", "path": "/d/3", "value": 4}
+                ]"#
+            ),
+            Ok(json!({"d": [0, 2, 3, 4], "e/f": {"b": [0, 2, 3, 4]}}))
+        );
+        assert_eq!(
+            applied("[1]", r#"[{"op": "add", "path": "", "value": {}}]"#),
+            Ok(json!({}))
+        );
+    }
+
+    #[test]
+    fn apply_fails_as_a_whole() {
+        let mut value = json!({"a": [1]});
+        let patch = Patch::from_value(&json!([
+            {"op": "remove", "path": "/a/0"},
+            {"op": "test", "path": "/a", "value": [1]}
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            apply(&mut value, &patch),
+            Err(PatchError::TestFailed(String::from("/a")))
+        );
+        assert_eq!(value, json!({"a": [1]}));
+
+        let fails = |patch: &str| applied(r#"{"a": [1], "b": {}}"#, patch).unwrap_err();
+        assert_eq!(
+            fails(r#"[{"op": "add", "path": "/a/2", "value": 0}]"#),
+            PatchError::PathNotFound(String::from("/a/2"))
+        );
+        assert_eq!(
+            fails(r#"[{"op": "add", "path": "/x/y", "value": 0}]"#),
+            PatchError::PathNotFound(String::from("/x/y"))
+        );
+        assert_eq!(
+            fails(r#"[{"op": "remove", "path": "/b/c"}]"#),
+            PatchError::PathNotFound(String::from("/b/c"))
+        );
+        assert_eq!(
+            fails(r#"[{"op": "move", "from": "/b", "path": "/b/c"}]"#),
+            PatchError::MoveIntoItself(String::from("/b/c"))
+        );
+        assert_eq!(
+            fails(r#"[{"op": "test", "path": "/a"}]"#),
+            PatchError::InvalidOperation(0)
+        );
+        assert_eq!(fails(r#"{"op": "remove"}"#), PatchError::NotAnArray);
+    }
+
+    #[test]
+    fn apply_undoes_diff() {
+        let from = json!({"a": [1, 2, {"b": 3}], "c": null, "d": "x"});
+        let to = json!({"a": [0, 2, {"b": 4}, 5], "d/e": true, "d": "y"});
+        let mut value = from.clone();
+
+        apply(&mut value, &diff(&from, &to)).unwrap();
+        assert_eq!(value, to);
+    }
+}
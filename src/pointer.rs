@@ -0,0 +1,257 @@
+// JSON Pointer (RFC 6901) navigation through a `Value`
+
+use alloc::borrow::Cow;
+use core::mem;
+
+use super::Map;
+use super::Value;
+use super::prelude::*;
+
+/// Why a value could not be set through a pointer
+#[derive(Debug, PartialEq)]
+pub enum PointerError {
+    /// the pointer is neither empty nor starts with `/`
+    Malformed,
+    /// the path runs through a value that is not an array or object
+    NotAContainer,
+    /// a token addressing an array element is not an index or `-`, or is
+    /// an index past the end of the array
+    InvalidIndex,
+}
+
+/// Reference tokens of `pointer`, `None` unless it is empty or starts with `/`
+fn tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(pointer.split('/').skip(1).map(unescape_token))
+}
+
+/// `~1` stands for `/` and `~0` for `~`, in that order so that `~01` is `~1`
+pub(crate) fn unescape_token(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// `~` is written `~0` and `/` is written `~1`, the reverse of
+/// [`unescape_token`]
+pub(crate) fn escape_token(token: &str) -> Cow<'_, str> {
+    if token.contains(['~', '/']) {
+        Cow::Owned(token.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Array index written without sign or leading zeros, as the RFC requires
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Index of the array element `token` refers to, where `-` or the length is
+/// the element past the end, added to the array as null
+fn array_slot(values: &mut Vec<Value>, token: &str) -> Result<usize, PointerError> {
+    let index = match token {
+        "-" => values.len(),
+        token => parse_index(token).ok_or(PointerError::InvalidIndex)?,
+    };
+    if index > values.len() {
+        return Err(PointerError::InvalidIndex);
+    }
+    if index == values.len() {
+        values.push(Value::Null);
+    }
+    Ok(index)
+}
+
+/// The member `token` refers to in `value`, created as null if missing. A
+/// null `value` is turned into an empty object first.
+fn slot<'v>(value: &'v mut Value, token: &str) -> Result<&'v mut Value, PointerError> {
+    if *value == Value::Null {
+        *value = Value::Object(Map::default());
+    }
+    match value {
+        Value::Object(map) => Ok(map.entry(token.to_owned()).or_insert(Value::Null)),
+        Value::Array(values) => {
+            let index = array_slot(values, token)?;
+            Ok(&mut values[index])
+        }
+        _ => Err(PointerError::NotAContainer),
+    }
+}
+
+impl Value {
+    /// The value `pointer` refers to, as in `"/users/0/name"`, `None` if
+    /// there is none or the pointer is malformed. The empty pointer refers to
+    /// the whole value.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            Value::Object(map) => map.get(token.as_ref()),
+            Value::Array(values) => values.get(parse_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Mutable access to the value `pointer` refers to, see [`Value::pointer`]
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            Value::Object(map) => map.get_mut(token.as_ref()),
+            Value::Array(values) => values.get_mut(parse_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Set the value at `pointer`, returning the value it replaces
+    ///
+    /// Objects missing along the way are created and nulls along the way are
+    /// replaced by objects. Array elements are addressed by index, or by `-`
+    /// or the length of the array for a new last element; an index further
+    /// past the end is an [`InvalidIndex`](PointerError::InvalidIndex).
+    pub fn insert_at_pointer(
+        &mut self,
+        pointer: &str,
+        value: Value,
+    ) -> Result<Option<Value>, PointerError> {
+        let tokens: Vec<Cow<str>> = tokens(pointer).ok_or(PointerError::Malformed)?.collect();
+        let Some((last, parents)) = tokens.split_last() else {
+            return Ok(Some(mem::replace(self, value)));
+        };
+
+        let mut target = self;
+        for token in parents {
+            target = slot(target, token)?;
+        }
+        if *target == Value::Null {
+            *target = Value::Object(Map::default());
+        }
+        match target {
+            Value::Object(map) => Ok(map.insert(last.to_string(), value)),
+            Value::Array(values) => {
+                let len = values.len();
+                let index = array_slot(values, last)?;
+                let previous = mem::replace(&mut values[index], value);
+                Ok((index < len).then_some(previous))
+            }
+            _ => Err(PointerError::NotAContainer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PointerError, escape_token, unescape_token};
+    use crate::{Value, parse};
+
+    #[test]
+    fn pointer_lookup() {
+        let value = parse(r#"{"users": [{"name": "ana"}, {"name": "bo"}], "": 1}"#).unwrap();
+
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(
+            value.pointer("/users/1/name"),
+            Some(&Value::String("bo".into()))
+        );
+        assert_eq!(value.pointer("/"), Some(&Value::Number(1.0)));
+        assert_eq!(value.pointer("/users/2"), None);
+        assert_eq!(value.pointer("/users/01"), None);
+        assert_eq!(value.pointer("/users/0/name/x"), None);
+        assert_eq!(value.pointer("users"), None);
+    }
+
+    #[test]
+    fn pointer_unescapes_tokens() {
+        let value = parse(r#"{"a/b": 1, "m~n": 2, "~1": 3}"#).unwrap();
+
+        assert_eq!(value.pointer("/a~1b"), Some(&Value::Number(1.0)));
+        assert_eq!(value.pointer("/m~0n"), Some(&Value::Number(2.0)));
+        assert_eq!(value.pointer("/~01"), Some(&Value::Number(3.0)));
+    }
+
+    #[test]
+    fn escape_token_reverses_unescape() {
+        for token in ["plain", "a/b", "m~n", "~1", "/~0/"] {
+            assert_eq!(unescape_token(&escape_token(token)), token);
+        }
+        assert_eq!(escape_token("~/"), "~0~1");
+    }
+
+    #[test]
+    fn pointer_mut_edits_in_place() {
+        let mut value = parse(r#"{"a": [1, 2]}"#).unwrap();
+        *value.pointer_mut("/a/1").unwrap() = Value::Boolean(true);
+
+        assert_eq!(value, parse(r#"{"a": [1, true]}"#).unwrap());
+        assert_eq!(value.pointer_mut("/a/2"), None);
+    }
+
+    #[test]
+    fn insert_creates_missing_objects() {
+        let mut value = parse(r#"{"server": {"port": 80}}"#).unwrap();
+        let cert = Value::String("cert.pem".into());
+
+        assert_eq!(value.insert_at_pointer("/server/tls/cert", cert), Ok(None));
+        assert_eq!(
+            value,
+            parse(r#"{"server": {"port": 80, "tls": {"cert": "cert.pem"}}}"#).unwrap()
+        );
+        assert_eq!(
+            value.insert_at_pointer("/server/port", Value::Number(443.0)),
+            Ok(Some(Value::Number(80.0)))
+        );
+    }
+
+    #[test]
+    fn insert_into_arrays() {
+        let mut value = parse(r#"{"list": [1]}"#).unwrap();
+
+        assert_eq!(
+            value.insert_at_pointer("/list/-", Value::Number(2.0)),
+            Ok(None)
+        );
+        assert_eq!(value.insert_at_pointer("/list/2/x", Value::Null), Ok(None));
+        assert_eq!(value, parse(r#"{"list": [1, 2, {"x": null}]}"#).unwrap());
+        assert_eq!(
+            value.insert_at_pointer("/list/x", Value::Null),
+            Err(PointerError::InvalidIndex)
+        );
+        for pointer in [
+            "/list/4",
+            "/list/4000000000",
+            "/list/18446744073709551615/x",
+        ] {
+            assert_eq!(
+                value.insert_at_pointer(pointer, Value::Null),
+                Err(PointerError::InvalidIndex),
+                "{pointer}"
+            );
+        }
+        assert_eq!(value, parse(r#"{"list": [1, 2, {"x": null}]}"#).unwrap());
+    }
+
+    #[test]
+    fn insert_rejects_bad_paths() {
+        let mut value = parse(r#"{"a": 1}"#).unwrap();
+
+        assert_eq!(
+            value.insert_at_pointer("/a/b", Value::Null),
+            Err(PointerError::NotAContainer)
+        );
+        assert_eq!(
+            value.insert_at_pointer("a", Value::Null),
+            Err(PointerError::Malformed)
+        );
+        assert_eq!(
+            value.insert_at_pointer("", Value::Null),
+            Ok(Some(parse(r#"{"a": 1}"#).unwrap()))
+        );
+    }
+}
@@ -0,0 +1,438 @@
+// Writing values out indented for people to read
+
+use core::fmt::{self, Write};
+
+use super::Value;
+use super::prelude::*;
+use super::ser::{Node, NonFinite, Tree, write_number, write_string};
+
+/// ANSI escape sequences for each part of a value, as `jq` colors them
+const KEY: &str = "\x1b[1;34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+const LITERAL: &str = "\x1b[35m";
+const PUNCTUATION: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// How [`Value::to_string_pretty_with`] lays a value out
+///
+/// By default every array element and object member goes on a line of its
+/// own, indented by two spaces per level, and members keep the order of the
+/// map they are in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// color the output for a terminal, see [`Value::to_string_colored`]
+    pub colored: bool,
+    /// keep arrays and objects on one line when they end by this column,
+    /// see [`Value::to_string_pretty_width`]
+    pub width: Option<usize>,
+    /// lay arrays holding nothing but numbers, strings, booleans and nulls
+    /// out this many elements to a line, `usize::MAX` keeping them on one,
+    /// so that matrices of numbers stay readable
+    pub scalars_per_line: Option<usize>,
+    /// spaces to indent each level by, two if not given
+    pub indent: Option<usize>,
+    /// write object members ordered by key, as
+    /// [`WriteOptions::sort_keys`](crate::WriteOptions::sort_keys) does
+    pub sort_keys: bool,
+}
+
+/// Writes a value laid out as [`PrettyOptions`] say
+pub(crate) struct Pretty<'w, W> {
+    out: &'w mut W,
+    options: PrettyOptions,
+    depth: usize,
+    /// where on its line the output is
+    column: usize,
+    /// whether the container being written is kept on one line
+    flat: bool,
+}
+
+impl<'w, W: Write> Pretty<'w, W> {
+    pub(crate) fn new(out: &'w mut W, options: PrettyOptions) -> Self {
+        Pretty {
+            out,
+            options,
+            depth: 0,
+            column: 0,
+            flat: false,
+        }
+    }
+
+    pub(crate) fn write<T: Tree>(&mut self, value: &T) -> fmt::Result {
+        self.write_then(value, 0)
+    }
+
+    /// Write `value`, which `trailing` more characters follow on its line
+    fn write_then<T: Tree>(&mut self, value: &T, trailing: usize) -> fmt::Result {
+        match value.node() {
+            Node::Null => self.paint(LITERAL, |out| out.write_str("null")),
+            Node::Boolean(b) => self.paint(LITERAL, |out| {
+                out.write_str(if b { "true" } else { "false" })
+            }),
+            Node::Number(n) => self.paint(NUMBER, |out| write_number(out, n, NonFinite::Null)),
+            Node::String(s) => self.paint(STRING, |out| write_string(out, s)),
+            Node::Array([]) => self.punctuation("[]"),
+            Node::Object(members) if members.len() == 0 => self.punctuation("{}"),
+            Node::Array(values)
+                if !self.flat
+                    && self.options.scalars_per_line.is_some()
+                    && values.iter().all(is_scalar)
+                    && !self.fits(value, trailing) =>
+            {
+                let per_line = self.options.scalars_per_line.unwrap_or(usize::MAX).max(1);
+                self.scalar_rows(values, per_line)
+            }
+            Node::Array(values) => {
+                let flat = self.flat;
+                self.flat = flat || self.fits(value, trailing);
+                self.punctuation("[")?;
+                self.depth += 1;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        self.punctuation(",")?;
+                    }
+                    self.line_break(i > 0)?;
+                    self.write_then(value, usize::from(i + 1 < values.len()))?;
+                }
+                self.depth -= 1;
+                self.line_break(false)?;
+                self.punctuation("]")?;
+                self.flat = flat;
+                Ok(())
+            }
+            Node::Object(members) if self.options.sort_keys => {
+                let mut members: Vec<_> = members.collect();
+                members.sort_by_key(|(key, _)| *key);
+                self.object(value, members.into_iter(), trailing)
+            }
+            Node::Object(members) => self.object(value, members, trailing),
+        }
+    }
+
+    /// Write the object `value` with its `members` in the order given
+    fn object<'v, T: Tree + 'v>(
+        &mut self,
+        value: &T,
+        members: impl ExactSizeIterator<Item = (&'v str, &'v T)>,
+        trailing: usize,
+    ) -> fmt::Result {
+        let flat = self.flat;
+        self.flat = flat || self.fits(value, trailing);
+        self.punctuation("{")?;
+        self.depth += 1;
+        let len = members.len();
+        for (i, (key, value)) in members.enumerate() {
+            if i > 0 {
+                self.punctuation(",")?;
+            }
+            self.line_break(i > 0)?;
+            self.paint(KEY, |out| write_string(out, key))?;
+            self.punctuation(":")?;
+            self.out.write_str(" ")?;
+            if !self.flat {
+                self.column += text_width(|out| write_string(out, key)) + 2;
+            }
+            self.write_then(value, usize::from(i + 1 < len))?;
+        }
+        self.depth -= 1;
+        self.line_break(false)?;
+        self.punctuation("}")?;
+        self.flat = flat;
+        Ok(())
+    }
+
+    /// Write an array of scalars `per_line` elements to a line
+    fn scalar_rows<T: Tree>(&mut self, values: &[T], per_line: usize) -> fmt::Result {
+        self.flat = values.len() <= per_line;
+        self.punctuation("[")?;
+        self.depth += 1;
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.punctuation(",")?;
+            }
+            if i % per_line == 0 {
+                self.line_break(i > 0)?;
+            } else {
+                self.out.write_str(" ")?;
+            }
+            self.write(value)?;
+        }
+        self.depth -= 1;
+        self.line_break(false)?;
+        self.punctuation("]")?;
+        self.flat = false;
+        Ok(())
+    }
+
+    /// Whether `value` fits on the rest of the line, `trailing` characters
+    /// still to follow it
+    fn fits<T: Tree>(&self, value: &T, trailing: usize) -> bool {
+        let Some(width) = self.options.width else {
+            return false;
+        };
+        let Some(room) = width.checked_sub(self.column + trailing) else {
+            return false;
+        };
+        let mut counter = Counter { len: 0, max: room };
+        let mut flat = Pretty {
+            out: &mut counter,
+            options: PrettyOptions::default(),
+            depth: 0,
+            column: 0,
+            flat: true,
+        };
+        flat.write(value).is_ok()
+    }
+
+    /// Go on to the next element or member, on a new line unless the
+    /// container is kept on one, where a `space` separates them
+    fn line_break(&mut self, space: bool) -> fmt::Result {
+        if self.flat {
+            return if space {
+                self.out.write_str(" ")
+            } else {
+                Ok(())
+            };
+        }
+        self.out.write_str("\n")?;
+        self.column = self.depth * self.options.indent.unwrap_or(2);
+        for _ in 0..self.column {
+            self.out.write_str(" ")?;
+        }
+        Ok(())
+    }
+
+    fn punctuation(&mut self, s: &str) -> fmt::Result {
+        self.paint(PUNCTUATION, |out| out.write_str(s))
+    }
+
+    /// Write with `write`, in `color` if the output is colored
+    fn paint(&mut self, color: &str, write: impl FnOnce(&mut W) -> fmt::Result) -> fmt::Result {
+        if !self.options.colored {
+            return write(self.out);
+        }
+        self.out.write_str(color)?;
+        write(self.out)?;
+        self.out.write_str(RESET)
+    }
+}
+
+/// Counts the characters written to it, failing once there are more than
+/// `max` so that measuring a large value stops early
+struct Counter {
+    len: usize,
+    max: usize,
+}
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.len += s.chars().count();
+        if self.len > self.max {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+fn is_scalar<T: Tree>(value: &T) -> bool {
+    !matches!(value.node(), Node::Array(_) | Node::Object(_))
+}
+
+/// Number of characters `write` writes
+fn text_width(write: impl FnOnce(&mut Counter) -> fmt::Result) -> usize {
+    let mut counter = Counter {
+        len: 0,
+        max: usize::MAX,
+    };
+    write(&mut counter).expect("counting never fails");
+    counter.len
+}
+
+/// `value` as JSON text indented for people to read, laid out as `options`
+/// say
+pub(crate) fn to_string_pretty_with<T: Tree>(value: &T, options: PrettyOptions) -> String {
+    let mut out = String::new();
+    Pretty::new(&mut out, options)
+        .write(value)
+        .expect("writing to a String never fails");
+    out
+}
+
+impl Value {
+    /// The value as JSON text indented for people to read, with every array
+    /// element and object member on a line of its own
+    ///
+    /// ```
+    /// use parser_json_rs::json;
+    ///
+    /// let value = json!({"ports": [80, 443]});
+    /// assert_eq!(value.to_string_pretty(), "{\n  \"ports\": [\n    80,\n    443\n  ]\n}");
+    /// ```
+    pub fn to_string_pretty(&self) -> String {
+        self.to_string_pretty_with(PrettyOptions::default())
+    }
+
+    /// The value as JSON text indented for people to read, laid out as
+    /// `options` say
+    ///
+    /// ```
+    /// use parser_json_rs::{PrettyOptions, json};
+    ///
+    /// let value = json!({"points": [1, 2, 3, 4, 5]});
+    /// let options = PrettyOptions { scalars_per_line: Some(2), ..PrettyOptions::default() };
+    /// assert_eq!(
+    ///     value.to_string_pretty_with(options),
+    ///     "{\n  \"points\": [\n    1, 2,\n    3, 4,\n    5\n  ]\n}"
+    /// );
+    /// ```
+    pub fn to_string_pretty_with(&self, options: PrettyOptions) -> String {
+        to_string_pretty_with(self, options)
+    }
+
+    /// The value as JSON text indented for people to read, keeping arrays
+    /// and objects on one line when they fit within `width` columns and
+    /// breaking them up otherwise, as `prettier` lays JSON out
+    ///
+    /// ```
+    /// use parser_json_rs::json;
+    ///
+    /// let value = json!({"matrix": [[1, 0, 0], [0, 1, 0], [0, 0, 1]]});
+    /// assert_eq!(
+    ///     value.to_string_pretty_width(24),
+    ///     "{\n  \"matrix\": [\n    [1, 0, 0],\n    [0, 1, 0],\n    [0, 0, 1]\n  ]\n}"
+    /// );
+    /// assert_eq!(
+    ///     value.to_string_pretty_width(80),
+    ///     r#"{"matrix": [[1, 0, 0], [0, 1, 0], [0, 0, 1]]}"#
+    /// );
+    /// ```
+    pub fn to_string_pretty_width(&self, width: usize) -> String {
+        self.to_string_pretty_with(PrettyOptions {
+            width: Some(width),
+            ..PrettyOptions::default()
+        })
+    }
+
+    /// [`to_string_pretty`](Value::to_string_pretty) with ANSI escape
+    /// sequences coloring keys, strings, numbers, literals and punctuation
+    /// apart, for printing to a terminal
+    pub fn to_string_colored(&self) -> String {
+        self.to_string_pretty_with(PrettyOptions {
+            colored: true,
+            ..PrettyOptions::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrettyOptions;
+    use crate::parse;
+
+    #[test]
+    fn pretty_puts_members_on_lines_of_their_own() {
+        let value = json!({"a": [1, {"b": null}, [], {}, "c"]});
+        let expected = r#"{
+  "a": [
+    1,
+    {
+      "b": null
+    },
+    [],
+    {},
+    "c"
+  ]
+}"#;
+
+        assert_eq!(value.to_string_pretty(), expected);
+        let value = json!({"a": 1, "b": {"c": [true]}});
+        assert_eq!(parse(&value.to_string_pretty()), Ok(value));
+    }
+
+    #[test]
+    fn pretty_width_breaks_what_does_not_fit() {
+        let value = json!({"a": [1, 2, {"b": "cd"}], "e": null});
+        let one_line = value.to_string_pretty_width(80);
+        assert_eq!(one_line.len(), 37);
+        assert_eq!(value.to_string_pretty_width(37), one_line);
+        assert_ne!(value.to_string_pretty_width(36), one_line);
+
+        let value = json!([[10, 20, 30], 4]);
+        // `  [10, 20, 30],` is 15 columns wide
+        assert_eq!(
+            value.to_string_pretty_width(15),
+            "[\n  [10, 20, 30],\n  4\n]"
+        );
+        let value = json!({"key": [[1, 2], 3]});
+        assert_eq!(
+            value.to_string_pretty_width(19),
+            "{\n  \"key\": [\n    [1, 2],\n    3\n  ]\n}"
+        );
+        let value = json!([[1, 2], [3, 4]]);
+        // the comma after the first element counts too
+        assert_eq!(value.to_string_pretty_width(9), "[\n  [1, 2],\n  [3, 4]\n]");
+        assert_eq!(
+            value.to_string_pretty_width(8),
+            "[\n  [\n    1,\n    2\n  ],\n  [3, 4]\n]"
+        );
+        assert_eq!(parse(&value.to_string_pretty_width(0)), Ok(value));
+    }
+
+    #[test]
+    fn scalar_arrays_per_line() {
+        let value = json!([[1, 2, 3], [4, 5, 6], ["a", null, [7]]]);
+        let per_line = |n| {
+            value.to_string_pretty_with(PrettyOptions {
+                scalars_per_line: Some(n),
+                ..PrettyOptions::default()
+            })
+        };
+
+        assert_eq!(
+            per_line(usize::MAX),
+            "[\n  [1, 2, 3],\n  [4, 5, 6],\n  [\n    \"a\",\n    null,\n    [7]\n  ]\n]"
+        );
+        assert_eq!(
+            per_line(2),
+            "[\n  [\n    1, 2,\n    3\n  ],\n  [\n    4, 5,\n    6\n  ],\n  [\n    \"a\",\n    null,\n    [7]\n  ]\n]"
+        );
+        assert_eq!(per_line(0), per_line(1));
+        assert_eq!(parse(&per_line(2)), Ok(value));
+    }
+
+    #[test]
+    fn indent_and_sort_keys() {
+        let value = json!({"b": [1, {"d": 2, "c": 3}], "a": null});
+        let options = PrettyOptions {
+            indent: Some(4),
+            sort_keys: true,
+            ..PrettyOptions::default()
+        };
+
+        assert_eq!(
+            value.to_string_pretty_with(options),
+            "{\n    \"a\": null,\n    \"b\": [\n        1,\n        {\n            \"c\": 3,\n            \"d\": 2\n        }\n    ]\n}"
+        );
+        assert_eq!(
+            value.to_string_pretty_with(PrettyOptions {
+                indent: Some(0),
+                width: Some(30),
+                ..options
+            }),
+            "{\n\"a\": null,\n\"b\": [1, {\"c\": 3, \"d\": 2}]\n}"
+        );
+    }
+
+    #[test]
+    fn colored_output_wraps_each_part() {
+        let value = json!({"k": [true, 1.5, "s"]});
+
+        assert_eq!(
+            value.to_string_colored(),
+            "\x1b[1m{\x1b[0m\n  \x1b[1;34m\"k\"\x1b[0m\x1b[1m:\x1b[0m \x1b[1m[\x1b[0m\n    \
+             \x1b[35mtrue\x1b[0m\x1b[1m,\x1b[0m\n    \x1b[33m1.5\x1b[0m\x1b[1m,\x1b[0m\n    \
+             \x1b[32m\"s\"\x1b[0m\n  \x1b[1m]\x1b[0m\n\x1b[1m}\x1b[0m"
+        );
+    }
+}
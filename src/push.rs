@@ -0,0 +1,663 @@
+// Parsing input handed over in chunks of arbitrary size, such as reads from a socket
+
+use core::iter::{self, Empty};
+use core::mem;
+
+use super::limits::Limit;
+use super::prelude::*;
+use super::stream::{Events, JsonEvent};
+use super::string::from_string;
+use super::tokenize::{
+    Source, Token, TokenStream, TokenizeError, end_of_string, find_quote_or_backslash,
+};
+use super::{JsonError, Map, ParserOptions, Syntax, TokenParseError, Value};
+
+/// Why lexing a chunk stopped early
+enum Interrupt {
+    /// a token runs up to the end of the data fed so far
+    NeedMoreData,
+    Tokenize(TokenizeError),
+}
+
+impl From<TokenizeError> for Interrupt {
+    fn from(err: TokenizeError) -> Self {
+        Interrupt::Tokenize(err)
+    }
+}
+
+/// Bytes fed so far that haven't been lexed yet
+struct ChunkSource<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    /// offset of `bytes` in the whole input
+    base: usize,
+    /// whether the end of `bytes` is the end of the input
+    last: bool,
+}
+
+impl Source<'_> for ChunkSource<'_> {
+    type Error = Interrupt;
+
+    fn peek(&mut self) -> Result<Option<u8>, Interrupt> {
+        match self.bytes.get(self.pos) {
+            None if !self.last => Err(Interrupt::NeedMoreData),
+            byte => Ok(byte.copied()),
+        }
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+    }
+
+    // counted from the start of the whole input, so only a byte order mark
+    // at the very start is one and errors point at the right byte
+    fn offset(&self) -> usize {
+        self.base + self.pos
+    }
+}
+
+/// Outcome of [`PushParser::finish`]
+#[derive(Debug, PartialEq)]
+pub enum Progress {
+    /// the data fed so far holds a whole document
+    Complete(Value),
+
+    /// the document is cut short, feed more data and finish again
+    NeedMoreData,
+}
+
+/// Where the scan for the end of the token being received has got to
+#[derive(Clone, Copy, PartialEq)]
+enum Scan {
+    /// in whitespace, before a token or after one the lexer looks past
+    Between,
+    /// after a `/` that may start a comment
+    Slash,
+    LineComment,
+    BlockComment {
+        star: bool,
+    },
+    String {
+        quote: u8,
+        escaping: bool,
+    },
+    /// in a number, a literal or an identifier
+    Word,
+    /// the lexer can take a token from the bytes pending
+    Complete,
+}
+
+/// Finds where the token being received ends by looking at each byte fed
+/// once, so that the lexer only runs once a token is there for it to take.
+///
+/// It may wait for a byte more than the lexer needs, never for one less.
+struct Scanner {
+    syntax: Syntax,
+    state: Scan,
+    /// whether the lexer looks past the token for the byte after it, as it
+    /// does after identifiers and JSON5 commas
+    lookahead: bool,
+}
+
+impl Scanner {
+    fn new(syntax: Syntax) -> Self {
+        Scanner {
+            syntax,
+            state: Scan::Between,
+            lookahead: false,
+        }
+    }
+
+    /// Scan `bytes` from the start of a token, returning whether the lexer
+    /// can take it
+    fn restart(&mut self, bytes: &[u8]) -> bool {
+        self.state = Scan::Between;
+        self.lookahead = false;
+        self.scan(bytes)
+    }
+
+    /// Scan the bytes following those scanned so far
+    fn scan(&mut self, bytes: &[u8]) -> bool {
+        if self.state == Scan::Complete {
+            return true;
+        }
+        let mut i = 0;
+        while i < bytes.len() {
+            // inside a string only a quote or backslash can change anything
+            if let Scan::String {
+                quote,
+                escaping: false,
+            } = self.state
+            {
+                match find_quote_or_backslash(&bytes[i..], quote) {
+                    Some(skip) => i += skip,
+                    None => return false,
+                }
+            }
+            if self.step(bytes[i]) {
+                self.state = Scan::Complete;
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    fn step(&mut self, byte: u8) -> bool {
+        let syntax = self.syntax;
+        match &mut self.state {
+            Scan::Between => match byte {
+                _ if byte.is_ascii_whitespace() => {}
+                b'/' if syntax.comments => self.state = Scan::Slash,
+                // the byte the lexer looks at past the token
+                _ if self.lookahead => return true,
+                b'"' => self.state = Scan::string(byte),
+                b'\'' if syntax.single_quotes => self.state = Scan::string(byte),
+                b',' if syntax.json5 => self.lookahead = true,
+                _ if is_word_byte(byte) => self.state = Scan::Word,
+                _ => return true,
+            },
+            Scan::Slash => match byte {
+                b'/' => self.state = Scan::LineComment,
+                b'*' => self.state = Scan::BlockComment { star: false },
+                _ => return true,
+            },
+            Scan::LineComment if byte == b'\n' => self.state = Scan::Between,
+            Scan::LineComment => {}
+            Scan::BlockComment { star: true } if byte == b'/' => self.state = Scan::Between,
+            Scan::BlockComment { star } => *star = byte == b'*',
+            Scan::String { quote, escaping } => {
+                if end_of_string(byte, *quote, escaping) {
+                    return true;
+                }
+            }
+            Scan::Word if is_word_byte(byte) => {}
+            Scan::Word if syntax.unquoted_keys || syntax.non_finite => {
+                self.state = Scan::Between;
+                self.lookahead = true;
+                return self.step(byte);
+            }
+            Scan::Word => return true,
+            Scan::Complete => return true,
+        }
+        false
+    }
+
+    /// Whether the bytes scanned hold no token, only whitespace and comments
+    fn in_trivia(&self) -> bool {
+        !self.lookahead && matches!(self.state, Scan::Between | Scan::LineComment)
+    }
+
+    /// Whether the bytes scanned end inside a string or a comment, which no
+    /// end of input can finish
+    fn in_unfinished(&self) -> bool {
+        matches!(
+            self.state,
+            Scan::Slash | Scan::BlockComment { .. } | Scan::String { .. }
+        )
+    }
+}
+
+impl Scan {
+    fn string(quote: u8) -> Self {
+        Scan::String {
+            quote,
+            escaping: false,
+        }
+    }
+}
+
+/// Whether `byte` may carry on a number, literal or identifier, letting
+/// through more than the lexer takes
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(byte, b'+' | b'-' | b'.' | b'_' | b'$')
+        || !byte.is_ascii()
+}
+
+/// A container still open, with what has been read into it so far
+enum Partial {
+    Array(Vec<Value>),
+    /// the members so far and the key of the one being read
+    Object(Map<String, Value>, Option<String>),
+}
+
+/// Parser fed its input piece by piece, for input arriving in chunks whose
+/// boundaries may fall anywhere, even inside a token or a UTF-8 character
+///
+/// Each byte fed is looked at once to find where the token it is part of
+/// ends, and each token is lexed and added to the document once it has all
+/// been fed, so the work done is proportional to the input however it is
+/// split up.
+pub struct PushParser {
+    options: ParserOptions,
+    /// the structure checked so far
+    events: Events<Empty<Result<Token<'static>, JsonError>>>,
+    /// the containers open so far, innermost last
+    stack: Vec<Partial>,
+    /// the document, once it is complete
+    root: Option<Value>,
+    /// the error the document failed with, if it did
+    error: Option<TokenParseError>,
+    /// bytes of the token still being received
+    pending: Vec<u8>,
+    /// offset of `pending` in the whole input
+    consumed: usize,
+    /// where the token in `pending` ends, if it does
+    scanner: Scanner,
+    /// whether the last token lexed ends a value
+    after_value: bool,
+    /// bytes handed to the lexer so far
+    #[cfg(test)]
+    lexed: core::cell::Cell<usize>,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        PushParser::with_options(ParserOptions::default())
+    }
+}
+
+impl PushParser {
+    pub fn new() -> Self {
+        PushParser::default()
+    }
+
+    /// A parser accepting the syntax and enforcing the limits of `options`
+    ///
+    /// The document size limit is checked against the bytes fed so far.
+    pub fn with_options(options: ParserOptions) -> Self {
+        PushParser {
+            options,
+            events: Events::new(iter::empty()),
+            stack: Vec::new(),
+            root: None,
+            error: None,
+            pending: Vec::new(),
+            consumed: 0,
+            scanner: Scanner::new(options.syntax),
+            after_value: false,
+            #[cfg(test)]
+            lexed: core::cell::Cell::new(0),
+        }
+    }
+
+    /// Add the next chunk of input, taking in every token it completes
+    ///
+    /// Errors are reported as soon as they are seen, with byte offsets
+    /// counted from the start of the whole input.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), JsonError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone().into());
+        }
+        let size = self.consumed + self.pending.len() + chunk.len();
+        if size > self.options.limits.max_document_size {
+            let err = TokenParseError::LimitExceeded(Limit::DocumentSize);
+            self.error = Some(err.clone());
+            return Err(err.into());
+        }
+        self.pending.extend_from_slice(chunk);
+        if !self.scanner.scan(chunk) {
+            return Ok(());
+        }
+
+        let pending = mem::take(&mut self.pending);
+        let mut tokens = self.tokens(&pending, false);
+        let mut lexed = self.consumed;
+        let result = loop {
+            match tokens.next() {
+                Some(Ok(token)) => {
+                    lexed = tokens.span().end;
+                    if let Err(err) = self.take(token) {
+                        break Err(err);
+                    }
+                }
+                None | Some(Err(Interrupt::NeedMoreData)) => break Ok(()),
+                Some(Err(Interrupt::Tokenize(err))) => break Err(err.into()),
+            }
+        };
+        self.after_value = tokens.after_value();
+
+        let mut pending = pending;
+        pending.drain(..lexed - self.consumed);
+        self.scanner.restart(&pending);
+        self.pending = pending;
+        self.consumed = lexed;
+        result
+    }
+
+    /// Report whether the data fed so far is a whole document, handing it
+    /// over if it is, in which case feeding can carry on
+    ///
+    /// A document that is complete as it stands is returned even if more data
+    /// could have extended it, a top-level number like `12` for example.
+    pub fn finish(&mut self) -> Result<Progress, JsonError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone().into());
+        }
+        if self.scanner.in_unfinished() {
+            return Ok(Progress::NeedMoreData);
+        }
+        let tail = match self.scanner.in_trivia() {
+            true => None,
+            false => self.tokens(&self.pending, true).next(),
+        };
+        let tail = match tail {
+            None => None,
+            Some(Ok(token)) => Some(token),
+            // the token was cut short, more data may complete it
+            Some(Err(_)) => return Ok(Progress::NeedMoreData),
+        };
+        let Some(tail) = tail else {
+            return Ok(match &self.root {
+                Some(root) => Progress::Complete(root.clone()),
+                None => Progress::NeedMoreData,
+            });
+        };
+
+        // more data may still extend the last token, so it is only tried out
+        // on a copy of the structure, which holds no more than the nesting
+        let mut events = self.events.clone();
+        match events.push(tail)? {
+            Some(event) if events.is_done() => Ok(Progress::Complete(scalar(event))),
+            _ => Ok(Progress::NeedMoreData),
+        }
+    }
+
+    /// Parse the data fed so far as the whole input, tokens and documents
+    /// left unfinished are errors
+    #[cfg(feature = "tokio")]
+    pub(crate) fn close(mut self) -> Result<Value, JsonError> {
+        if let Some(err) = self.error {
+            return Err(err.into());
+        }
+        let pending = mem::take(&mut self.pending);
+        for token in self.tokens(&pending, true) {
+            match token {
+                Ok(token) => self.take(token)?,
+                Err(Interrupt::Tokenize(err)) => return Err(err.into()),
+                Err(Interrupt::NeedMoreData) => unreachable!("the last chunk never runs out"),
+            }
+        }
+        self.root.ok_or_else(|| self.events.unfinished().into())
+    }
+
+    /// Tokens lexed from `bytes`, the data fed from `consumed` on, which is
+    /// the whole rest of the input if `last`
+    fn tokens<'b>(&self, bytes: &'b [u8], last: bool) -> TokenStream<'static, ChunkSource<'b>> {
+        #[cfg(test)]
+        self.lexed.set(self.lexed.get() + bytes.len());
+        let source = ChunkSource {
+            bytes,
+            pos: 0,
+            base: self.consumed,
+            last,
+        };
+        TokenStream::with_syntax(source, self.options.syntax).resume(self.after_value)
+    }
+
+    /// Add the next token to the document, keeping the first error
+    fn take(&mut self, token: Token<'static>) -> Result<(), JsonError> {
+        let result = self.add(token);
+        if let Err(err) = &result {
+            self.error = Some(err.clone());
+        }
+        Ok(result?)
+    }
+
+    fn add(&mut self, token: Token<'static>) -> Result<(), TokenParseError> {
+        let Some(event) = self.events.push(token)? else {
+            return Ok(());
+        };
+        let limits = &self.options.limits;
+        let value = match event {
+            JsonEvent::StartArray | JsonEvent::StartObject
+                if self.stack.len() == limits.max_depth =>
+            {
+                return Err(TokenParseError::DepthLimitExceeded);
+            }
+            JsonEvent::StartArray => {
+                self.stack.push(Partial::Array(Vec::new()));
+                return Ok(());
+            }
+            JsonEvent::StartObject => {
+                self.stack.push(Partial::Object(Map::default(), None));
+                return Ok(());
+            }
+            JsonEvent::Key(name) => {
+                limits.check_string(&name)?;
+                if let Some(Partial::Object(_, key)) = self.stack.last_mut() {
+                    *key = Some(name);
+                }
+                return Ok(());
+            }
+            JsonEvent::EndArray | JsonEvent::EndObject => match self.stack.pop() {
+                Some(Partial::Array(values)) => Value::Array(values),
+                Some(Partial::Object(map, _)) => Value::Object(map),
+                None => unreachable!("events are balanced"),
+            },
+            JsonEvent::String(s) => {
+                limits.check_string(&s)?;
+                Value::String(from_string(s))
+            }
+            event => scalar(event),
+        };
+
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Partial::Array(values)) => {
+                if values.len() == limits.max_array_length {
+                    return Err(TokenParseError::LimitExceeded(Limit::ArrayLength));
+                }
+                values.push(value);
+            }
+            Some(Partial::Object(map, key)) => {
+                let key = key.take().expect("values in objects follow a key");
+                map.insert(key, value);
+                if map.len() > limits.max_object_length {
+                    return Err(TokenParseError::LimitExceeded(Limit::ObjectLength));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The value of an event for a scalar
+fn scalar(event: JsonEvent) -> Value {
+    match event {
+        JsonEvent::Null => Value::Null,
+        JsonEvent::Boolean(b) => Value::Boolean(b),
+        JsonEvent::Number(n) => Value::Number(n),
+        JsonEvent::String(s) => Value::String(from_string(s)),
+        _ => unreachable!("only scalars complete a value on their own"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Progress, PushParser};
+    use crate::prelude::*;
+    use crate::{
+        DEFAULT_MAX_DEPTH, JsonError, Limit, Limits, Parser, Syntax, TokenParseError,
+        TokenizeError, Value, parse, parse_with_options,
+    };
+
+    #[test]
+    fn feed_one_byte_at_a_time() {
+        let input = r#"{"name": "olá", "ok": [true, -12.5, null]}"#;
+        let mut parser = PushParser::new();
+
+        for byte in input.as_bytes() {
+            assert_eq!(parser.finish(), Ok(Progress::NeedMoreData));
+            parser.feed(&[*byte]).unwrap();
+        }
+
+        assert_eq!(
+            parser.finish(),
+            Ok(Progress::Complete(parse(input).unwrap()))
+        );
+    }
+
+    #[test]
+    fn number_split_across_chunks() {
+        let mut parser = PushParser::new();
+        parser.feed(b"[12").unwrap();
+        parser.feed(b"34.5]").unwrap();
+
+        let expected = Value::Array(vec![Value::Number(1234.5)]);
+        assert_eq!(parser.finish(), Ok(Progress::Complete(expected)));
+    }
+
+    #[test]
+    fn finish_takes_top_level_number_as_is() {
+        let mut parser = PushParser::new();
+        parser.feed(b" 12").unwrap();
+
+        assert_eq!(parser.finish(), Ok(Progress::Complete(Value::Number(12.0))));
+    }
+
+    #[test]
+    fn feed_reports_errors_with_absolute_offsets() {
+        let mut parser = PushParser::new();
+        parser.feed(b"[\"ok\", \"a").unwrap();
+
+        assert_eq!(
+            parser.feed(b"\xff\"]"),
+            Err(JsonError::Tokenize(TokenizeError::InvalidUtf8(9)))
+        );
+    }
+
+    #[test]
+    fn byte_order_mark_only_at_the_start() {
+        let mut parser = PushParser::new();
+        parser.feed(b"\xef\xbb").unwrap();
+        parser.feed(b"\xbf[1,").unwrap();
+
+        assert_eq!(
+            parser.feed("\u{feff}2]".as_bytes()).err(),
+            parse("[1,\u{feff}2]").err()
+        );
+    }
+
+    #[test]
+    fn feed_reports_errors_in_the_structure() {
+        let mut parser = PushParser::new();
+        let expected = Err(JsonError::Parse(TokenParseError::ExpectedColon));
+
+        assert_eq!(parser.feed(b"{\"a\" 1, "), expected);
+        assert_eq!(parser.finish().map(|_| ()), expected);
+        assert_eq!(parser.feed(b"}"), expected);
+    }
+
+    #[test]
+    fn whole_documents_parse_as_parse_does() {
+        let deep = format!(
+            "{}{}",
+            "[".repeat(DEFAULT_MAX_DEPTH + 1),
+            "]".repeat(DEFAULT_MAX_DEPTH + 1)
+        );
+        let inputs = [
+            r#"{"a": [1, {"b": null}], "a": "\u00e9", "c": {}}"#,
+            "[1}",
+            "{1: 2}",
+            "[1,]",
+            "[1] 2",
+            r#"["\u12"]"#,
+            &deep,
+        ];
+
+        for input in inputs {
+            let mut parser = PushParser::new();
+            let pushed = parser.feed(input.as_bytes()).and_then(|()| parser.finish());
+            assert_eq!(pushed, parse(input).map(Progress::Complete), "{input}");
+        }
+    }
+
+    #[test]
+    fn finish_reports_malformed_documents() {
+        let mut parser = PushParser::new();
+        parser.feed(b"[1 2").unwrap();
+
+        assert_eq!(
+            parser.finish(),
+            Err(JsonError::Parse(TokenParseError::ExpectedComma))
+        );
+    }
+
+    #[test]
+    fn each_byte_is_lexed_once() {
+        let input = format!("[\"{}\", 12345, // note\n {{}}]", "a\\\"".repeat(30_000));
+        let mut parser = PushParser::with_options(Parser::builder().syntax(Syntax::jsonc()));
+
+        for byte in input.as_bytes() {
+            parser.feed(&[*byte]).unwrap();
+            assert_eq!(parser.finish().map(|_| ()), Ok(()));
+        }
+
+        assert!(matches!(parser.finish(), Ok(Progress::Complete(_))));
+        // a token is lexed once, along with the bytes after it in its chunk
+        assert!(
+            parser.lexed.get() <= 2 * input.len(),
+            "{}",
+            parser.lexed.get()
+        );
+    }
+
+    #[test]
+    fn options_apply_across_chunks() {
+        let options = Parser::builder().syntax(Syntax::json5());
+        let input = "{key: 'a', /* c */ n: +Infinity, list: [1, 0x2,],}";
+
+        for split in 0..input.len() {
+            let mut parser = PushParser::with_options(options);
+            parser.feed(&input.as_bytes()[..split]).unwrap();
+            parser.feed(&input.as_bytes()[split..]).unwrap();
+            assert_eq!(
+                parser.finish(),
+                parse_with_options(input, options).map(Progress::Complete),
+                "{split}"
+            );
+        }
+    }
+
+    #[test]
+    fn options_limits_are_enforced() {
+        let limits = Limits {
+            max_depth: 2,
+            max_document_size: 24,
+            max_string_length: 3,
+            max_array_length: 2,
+            max_object_length: 1,
+        };
+        let options = Parser::builder().limits(limits);
+        let inputs = [
+            "[[[]]]",
+            "[1, 2, 3]",
+            r#"{"a": 1, "b": 2}"#,
+            r#"["abcd"]"#,
+            r#"{"abcd": 1}"#,
+            &format!("[{}]", " ".repeat(23)),
+            r#"{"a": [1, "b"]}"#,
+        ];
+
+        for input in inputs {
+            let mut parser = PushParser::with_options(options);
+            let pushed = parser.feed(input.as_bytes()).and_then(|()| parser.finish());
+            assert_eq!(
+                pushed,
+                parse_with_options(input, options).map(Progress::Complete),
+                "{input}"
+            );
+        }
+
+        let mut parser = PushParser::with_options(options);
+        parser.feed(&[b' '; 24]).unwrap();
+        assert_eq!(
+            parser.feed(b"1"),
+            Err(JsonError::Parse(TokenParseError::LimitExceeded(
+                Limit::DocumentSize
+            )))
+        );
+    }
+}
@@ -0,0 +1,183 @@
+// Deferred parsing: keeping a subtree exactly as written in the source
+
+use core::fmt;
+use core::ops::Range;
+
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+use super::stream::Events;
+use super::tokenize::{SliceSource, Token, TokenStream};
+use super::{JsonError, Value};
+
+type Tokens<'a> = TokenStream<'a, SliceSource<'a>>;
+
+/// A syntactically valid JSON value kept as the exact source text it was
+/// read from, without building a DOM for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawValue<'a> {
+    json: &'a str,
+}
+
+impl<'a> RawValue<'a> {
+    /// Check that `json` holds exactly one JSON value, surrounding whitespace
+    /// is left out of the captured text
+    pub fn new(json: &'a str) -> Result<Self, JsonError> {
+        let mut tokens = TokenStream::new(SliceSource::new(json.as_bytes()));
+        let span = skip_value(&mut tokens)?;
+        if tokens.next().transpose()?.is_some() {
+            return Err(TokenParseError::TrailingTokens.into());
+        }
+
+        Ok(RawValue { json: &json[span] })
+    }
+
+    /// The source text of the value
+    pub fn get(&self) -> &'a str {
+        self.json
+    }
+
+    /// Build the DOM for the value after all
+    pub fn parse(&self) -> Result<Value, JsonError> {
+        super::parse(self.json)
+    }
+
+    /// Members of an object with their values left raw, `None` if the
+    /// value is not an object
+    pub fn entries(&self) -> Option<Vec<(String, RawValue<'a>)>> {
+        self.members(Token::RightCurlyBracket).ok()
+    }
+
+    /// Elements of an array left raw, `None` if the value is not an array
+    pub fn elements(&self) -> Option<Vec<RawValue<'a>>> {
+        let members = self.members(Token::RightSquareBracket).ok()?;
+        Some(members.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Walk the members of a container closed by `end`, pairing each value
+    /// with its key, or an empty key for array elements
+    fn members(&self, end: Token) -> Result<Vec<(String, RawValue<'a>)>, JsonError> {
+        let is_object = end == Token::RightCurlyBracket;
+        let open = if is_object {
+            Token::LeftCurlyBracket
+        } else {
+            Token::LeftSquareBracket
+        };
+        let mut tokens = TokenStream::new(SliceSource::new(self.json.as_bytes()));
+        if next_token(&mut tokens)? != open {
+            return Err(TokenParseError::ExpectedValue.into());
+        }
+
+        let mut members = Vec::new();
+        // the text is known to be valid, so only whitespace can sit between
+        // the brackets of an empty container
+        if self.json[1..].trim_start().len() == 1 {
+            return Ok(members);
+        }
+        loop {
+            let mut key = String::new();
+            if is_object {
+                let Token::String(raw) = next_token(&mut tokens)? else {
+                    return Err(TokenParseError::ExpectedProperty.into());
+                };
+                key = unescape(raw)?.into_owned();
+                if next_token(&mut tokens)? != Token::Colon {
+                    return Err(TokenParseError::ExpectedColon.into());
+                }
+            }
+            let span = skip_value(&mut tokens)?;
+            members.push((
+                key,
+                RawValue {
+                    json: &self.json[span],
+                },
+            ));
+
+            match next_token(&mut tokens)? {
+                Token::Comma => {}
+                token if token == end => break,
+                _ => return Err(TokenParseError::ExpectedComma.into()),
+            }
+        }
+
+        Ok(members)
+    }
+}
+
+/// Writes the source text unchanged
+impl fmt::Display for RawValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.json)
+    }
+}
+
+fn next_token<'a>(tokens: &mut Tokens<'a>) -> Result<Token<'a>, JsonError> {
+    Ok(tokens.next().ok_or(TokenParseError::UnexpectedEnd)??)
+}
+
+/// Check the structure of the next value and step over it, returning the
+/// byte range it covers
+fn skip_value(tokens: &mut Tokens<'_>) -> Result<Range<usize>, JsonError> {
+    let mut events = Events::new(&mut *tokens);
+    let mut span: Option<Range<usize>> = None;
+
+    while let Some(event) = events.next() {
+        event?;
+        let token = events.tokens().span();
+        span = Some(match span {
+            Some(span) => span.start..token.end,
+            None => token,
+        });
+    }
+
+    Ok(span.expect("events end with an error or after a whole value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawValue;
+    use crate::prelude::*;
+    use crate::{JsonError, TokenParseError, Value};
+
+    #[test]
+    fn raw_value_keeps_source_text() {
+        let raw = RawValue::new("  {\"b\" : [1,  2], \"a\":null}\n").unwrap();
+
+        assert_eq!(raw.get(), "{\"b\" : [1,  2], \"a\":null}");
+        assert_eq!(raw.to_string(), raw.get());
+    }
+
+    #[test]
+    fn raw_value_rejects_invalid_or_trailing_input() {
+        assert_eq!(
+            RawValue::new("[1, 2"),
+            Err(JsonError::Parse(TokenParseError::UnexpectedEnd))
+        );
+        assert_eq!(
+            RawValue::new("[1] [2]"),
+            Err(JsonError::Parse(TokenParseError::TrailingTokens))
+        );
+    }
+
+    #[test]
+    fn raw_entries_for_routing() {
+        let message = r#"{"type": "order", "payload": {"id": 7, "items": [ "a" ]}}"#;
+        let raw = RawValue::new(message).unwrap();
+        let entries = raw.entries().unwrap();
+
+        assert_eq!(entries[0].0, "type");
+        assert_eq!(entries[0].1.parse(), Ok(Value::String("order".into())));
+        assert_eq!(entries[1].0, "payload");
+        assert_eq!(entries[1].1.get(), r#"{"id": 7, "items": [ "a" ]}"#);
+        assert_eq!(raw.elements(), None);
+    }
+
+    #[test]
+    fn raw_elements() {
+        let raw = RawValue::new(r#"[ {"a": 1} , "x",[]]"#).unwrap();
+        let elements: Vec<&str> = raw.elements().unwrap().iter().map(|e| e.get()).collect();
+
+        assert_eq!(elements, vec![r#"{"a": 1}"#, r#""x""#, "[]"]);
+        assert_eq!(RawValue::new("[ ]").unwrap().elements(), Some(vec![]));
+        assert_eq!(RawValue::new("{}").unwrap().entries(), Some(vec![]));
+    }
+}
@@ -0,0 +1,193 @@
+// Lexing straight from an `io::Read`, holding only a small window of the input
+
+use std::io::{self, Read};
+
+use super::limits::Limit;
+use super::tokenize::{Source, Token, TokenStream};
+use super::{JsonError, ParserOptions, Syntax, TokenParseError};
+
+pub(crate) const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Bytes pulled from a reader one buffer at a time
+struct ReadSource<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    consumed: usize,
+    /// bytes that may be read in all before the input is too large
+    max_len: usize,
+}
+
+impl<R: Read> ReadSource<R> {
+    fn new(reader: R) -> Self {
+        ReadSource::with_max_len(reader, usize::MAX)
+    }
+
+    fn with_max_len(reader: R, max_len: usize) -> Self {
+        ReadSource {
+            reader,
+            buf: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            consumed: 0,
+            max_len,
+        }
+    }
+
+    fn refill(&mut self) -> io::Result<()> {
+        loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                    return Ok(());
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<R: Read> Source<'_> for ReadSource<R> {
+    type Error = JsonError;
+
+    fn peek(&mut self) -> Result<Option<u8>, JsonError> {
+        if self.pos == self.len {
+            self.refill()?;
+            // everything before the buffer has been consumed
+            if self.consumed + self.len > self.max_len {
+                return Err(TokenParseError::LimitExceeded(Limit::DocumentSize).into());
+            }
+        }
+        Ok(self.buf[..self.len].get(self.pos).copied())
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+        self.consumed += 1;
+    }
+
+    fn offset(&self) -> usize {
+        self.consumed
+    }
+}
+
+/// Streaming tokenizer over an `io::Read`, the counterpart of
+/// [`Lexer`](crate::Lexer) for input that isn't held in memory
+pub struct ReaderLexer<R> {
+    tokens: TokenStream<'static, ReadSource<R>>,
+}
+
+impl<R: Read> ReaderLexer<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderLexer {
+            tokens: TokenStream::new(ReadSource::new(reader)),
+        }
+    }
+
+    /// Tokenizer accepting the extensions to JSON in `syntax`
+    pub fn with_syntax(reader: R, syntax: Syntax) -> Self {
+        ReaderLexer {
+            tokens: TokenStream::with_syntax(ReadSource::new(reader), syntax),
+        }
+    }
+
+    /// Tokenizer in the syntax of `options`, failing once more bytes are
+    /// read than their document size limit allows
+    pub(crate) fn with_options(reader: R, options: &ParserOptions) -> Self {
+        let source = ReadSource::with_max_len(reader, options.limits.max_document_size);
+        ReaderLexer {
+            tokens: TokenStream::with_syntax(source, options.syntax),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ReaderLexer<R> {
+    type Item = Result<Token<'static>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokens.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use super::{BUFFER_SIZE, ReaderLexer};
+    use crate::{JsonError, Token, TokenizeError};
+
+    /// Hands out its input a few bytes per `read` call
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn lex_across_read_boundaries() {
+        let input = r#"{"name": "olá", "ok": [true, -12.5]}"#;
+        let tokens: Result<Vec<Token>, JsonError> =
+            ReaderLexer::new(Trickle(input.as_bytes())).collect();
+        let expected = vec![
+            Token::LeftCurlyBracket,
+            Token::String("name".into()),
+            Token::Colon,
+            Token::String("olá".into()),
+            Token::Comma,
+            Token::String("ok".into()),
+            Token::Colon,
+            Token::LeftSquareBracket,
+            Token::True,
+            Token::Comma,
+            Token::Number(-12.5),
+            Token::RightSquareBracket,
+            Token::RightCurlyBracket,
+        ];
+
+        assert_eq!(tokens, Ok(expected));
+    }
+
+    #[test]
+    fn lex_string_longer_than_buffer() {
+        let long = "x".repeat(BUFFER_SIZE * 3);
+        let input = format!("\"{long}\"");
+        let tokens: Result<Vec<Token>, JsonError> = ReaderLexer::new(input.as_bytes()).collect();
+
+        assert_eq!(tokens, Ok(vec![Token::String(long.into())]));
+    }
+
+    #[test]
+    fn lex_reports_invalid_utf8_offset() {
+        let input: &[u8] = b"[\"ab\xff\"]";
+        let tokens: Result<Vec<Token>, JsonError> = ReaderLexer::new(input).collect();
+
+        assert_eq!(tokens, Err(TokenizeError::InvalidUtf8(4).into()));
+    }
+
+    #[test]
+    fn lex_reports_io_errors() {
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let mut tokens = ReaderLexer::new(Broken);
+
+        assert_eq!(
+            tokens.next(),
+            Some(Err(JsonError::Io(io::ErrorKind::BrokenPipe.into())))
+        );
+        assert_eq!(tokens.next(), None);
+    }
+}
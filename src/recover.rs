@@ -0,0 +1,549 @@
+// Carrying on past syntax errors to find as many as possible in one pass
+
+use alloc::borrow::Cow;
+use core::mem;
+use core::ops::Range;
+
+use super::diagnostic::Diagnostic;
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+use super::tokenize::{SliceSource, Token, TokenStream, TokenizeError};
+use super::{DEFAULT_MAX_DEPTH, JsonError, Map, Value};
+
+/// A lexed token, or the error lexing it, which stands in for a value or key
+/// once reported
+type Lexed<'a> = (Result<Token<'a>, TokenizeError>, Range<usize>);
+
+/// Tokens of the whole input, lexing on after an error from where the bad
+/// text ends
+struct Tokens<'a> {
+    text: &'a str,
+    input: &'a [u8],
+    tokens: TokenStream<'a, SliceSource<'a>>,
+    /// offset in the input `tokens` started lexing at
+    base: usize,
+    /// whether an unclosed string swallowed the rest of the input
+    truncated: bool,
+    pushed_back: Option<Lexed<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Tokens {
+            text: input,
+            input: input.as_bytes(),
+            tokens: TokenStream::new(SliceSource::new(input.as_bytes())),
+            base: 0,
+            truncated: false,
+            pushed_back: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// The next token, an error being left to whoever meets it to report
+    fn next(&mut self) -> Option<Lexed<'a>> {
+        if let Some(lexed) = self.pushed_back.take() {
+            return Some(lexed);
+        }
+        let token = self.tokens.next()?;
+        let span = self.span();
+
+        if let Err(err) = &token {
+            if matches!(err, TokenizeError::UnclosedQuotes) {
+                self.truncated = true;
+                self.base = self.input.len();
+            } else {
+                self.base = span.end.max(span.start + 1);
+            }
+            self.tokens = TokenStream::new(SliceSource::new(&self.input[self.base..]));
+        }
+        Some((token, span))
+    }
+
+    /// The span of the last token, empty at the end of the input
+    fn span(&self) -> Range<usize> {
+        let span = self.tokens.span();
+        self.base + span.start..self.base + span.end
+    }
+
+    fn push_back(&mut self, lexed: Lexed<'a>) {
+        self.pushed_back = Some(lexed);
+    }
+
+    /// Consume the next token if it is `expected`
+    fn next_if(&mut self, expected: &Token) -> bool {
+        match self.next() {
+            Some((Ok(token), _)) if token == *expected => true,
+            Some(lexed) => {
+                self.push_back(lexed);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn report(&mut self, error: JsonError, span: Range<usize>) {
+        self.diagnostics.push(Diagnostic { error, span });
+    }
+
+    fn report_end(&mut self) {
+        // an unclosed string is all that is wrong with the end
+        if !self.truncated {
+            let span = self.span();
+            self.report(TokenParseError::UnexpectedEnd.into(), span);
+        }
+    }
+
+    /// The string with its escapes resolved, as written if they are broken
+    fn string(&mut self, raw: Cow<'a, str>, span: Range<usize>) -> Cow<'a, str> {
+        match unescape(raw.clone()) {
+            Ok(s) => s,
+            Err(err) => {
+                self.report(err.into(), span);
+                raw
+            }
+        }
+    }
+
+    fn text(&self, span: Range<usize>) -> &'a str {
+        self.text.get(span).unwrap_or_default()
+    }
+}
+
+/// A container whose members are still being parsed
+enum Frame {
+    Array(Vec<Value>),
+    /// the object so far and the key of the member being parsed
+    Object(Map<String, Value>, String),
+}
+
+impl Frame {
+    fn is_array(&self) -> bool {
+        matches!(self, Frame::Array(_))
+    }
+}
+
+/// The value being built, its open containers innermost last
+#[derive(Default)]
+struct Tree {
+    stack: Vec<Frame>,
+    root: Option<Value>,
+}
+
+impl Tree {
+    /// Add a complete value to the innermost container, or make it the root
+    fn add(&mut self, value: Value) {
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Frame::Array(values)) => values.push(value),
+            Some(Frame::Object(map, key)) => {
+                map.insert(mem::take(key), value);
+            }
+        }
+    }
+
+    /// Close the innermost container
+    fn close(&mut self) {
+        let value = match self.stack.pop().expect("a container is open") {
+            Frame::Array(values) => Value::Array(values),
+            Frame::Object(map, _) => Value::Object(map),
+        };
+        self.add(value);
+    }
+
+    fn set_key(&mut self, name: String) {
+        if let Some(Frame::Object(_, key)) = self.stack.last_mut() {
+            *key = name;
+        }
+    }
+}
+
+/// What the next token should be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Value,
+    /// a comma or the end of the innermost container, or of the input
+    AfterValue,
+    /// the key of an object member, `first` right after the `{`
+    Key {
+        first: bool,
+    },
+}
+
+/// Result of parsing text that may not be valid JSON, see [`parse_lossy`]
+#[derive(Debug, PartialEq)]
+pub struct LossyParse {
+    /// the document as far as it could be made out
+    pub value: Value,
+    /// every error found in input order, none for valid input
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Parse text that may not be valid JSON into the value it most likely
+/// means, with every error found on the way
+///
+/// Where [`parse_with_diagnostic`](crate::parse_with_diagnostic) stops at
+/// the first error, this carries on as if the likeliest fix had been made:
+/// a missing comma or colon is assumed, a stray closing bracket is skipped,
+/// containers left open are closed at the end and a missing or broken value
+/// is `null`. Keys that aren't strings are taken as written. The first
+/// diagnostic is always the one
+/// [`parse_with_diagnostic`](crate::parse_with_diagnostic) fails with, the
+/// same error at the same span, later ones may be knock-on effects of
+/// earlier ones:
+///
+/// ```
+/// use parser_json_rs::{json, parse_lossy};
+///
+/// let lossy = parse_lossy(r#"{"a": [1 2], "b" tru, "c": {"d": 3"#);
+/// assert_eq!(lossy.value, json!({"a": [1, 2], "b": null, "c": {"d": 3}}));
+/// let codes: Vec<_> = lossy.diagnostics.iter().map(|d| d.error.code()).collect();
+/// assert_eq!(
+///     codes,
+///     ["E107_EXPECTED_COMMA", "E008_MISSPELLED_LITERAL", "E109_EXPECTED_COLON", "E102_UNEXPECTED_END"]
+/// );
+/// ```
+pub fn parse_lossy(input: &str) -> LossyParse {
+    let mut tokens = Tokens::new(input);
+    let mut tree = Tree::default();
+    let mut state = State::Value;
+
+    match tokens.next() {
+        Some(lexed) => tokens.push_back(lexed),
+        None => {
+            let span = tokens.span();
+            tokens.report(TokenParseError::EmptyInput.into(), span);
+            return LossyParse {
+                value: Value::Null,
+                diagnostics: tokens.diagnostics,
+            };
+        }
+    }
+
+    loop {
+        let Some((token, span)) = tokens.next() else {
+            if !(tree.stack.is_empty() && state == State::AfterValue) {
+                tokens.report_end();
+            }
+            break;
+        };
+
+        state = match state {
+            State::Value => match token {
+                Err(err) => {
+                    tokens.report(err.into(), span);
+                    tree.add(Value::Null);
+                    State::AfterValue
+                }
+                Ok(Token::Null) => {
+                    tree.add(Value::Null);
+                    State::AfterValue
+                }
+                Ok(Token::True) => {
+                    tree.add(Value::Boolean(true));
+                    State::AfterValue
+                }
+                Ok(Token::False) => {
+                    tree.add(Value::Boolean(false));
+                    State::AfterValue
+                }
+                Ok(Token::Number(n)) => {
+                    tree.add(Value::Number(n));
+                    State::AfterValue
+                }
+                Ok(Token::String(raw)) => {
+                    let s = tokens.string(raw, span);
+                    tree.add(Value::String(s.into()));
+                    State::AfterValue
+                }
+                Ok(Token::LeftSquareBracket | Token::LeftCurlyBracket)
+                    if tree.stack.len() == DEFAULT_MAX_DEPTH =>
+                {
+                    // going on would only build a tree too deep to handle
+                    tokens.report(TokenParseError::DepthLimitExceeded.into(), span);
+                    break;
+                }
+                Ok(Token::LeftCurlyBracket) => {
+                    tree.stack
+                        .push(Frame::Object(Map::default(), String::new()));
+                    State::Key { first: true }
+                }
+                Ok(Token::LeftSquareBracket) => {
+                    if tokens.next_if(&Token::RightSquareBracket) {
+                        tree.add(Value::Array(Vec::new()));
+                        State::AfterValue
+                    } else {
+                        tree.stack.push(Frame::Array(Vec::new()));
+                        State::Value
+                    }
+                }
+                Ok(Token::Colon) => {
+                    tokens.report(TokenParseError::ExpectedValue.into(), span);
+                    State::Value
+                }
+                // the value is missing, what follows it is handled as usual
+                // unless there is nothing it could belong to
+                Ok(token) => {
+                    tokens.report(TokenParseError::ExpectedValue.into(), span.clone());
+                    let trailing_comma = token == Token::RightSquareBracket
+                        && tree.stack.last().is_some_and(Frame::is_array);
+                    if !trailing_comma {
+                        tree.add(Value::Null);
+                    }
+                    if !tree.stack.is_empty() {
+                        tokens.push_back((Ok(token), span));
+                    }
+                    State::AfterValue
+                }
+            },
+
+            State::AfterValue => {
+                // past the root value anything is trailing, even text that
+                // doesn't lex
+                let Some(in_array) = tree.stack.last().map(Frame::is_array) else {
+                    tokens.report(TokenParseError::TrailingTokens.into(), span);
+                    break;
+                };
+                match token {
+                    Ok(Token::Comma) if in_array => State::Value,
+                    Ok(Token::Comma) => State::Key { first: false },
+                    Ok(close @ (Token::RightSquareBracket | Token::RightCurlyBracket)) => {
+                        let closes_array = close == Token::RightSquareBracket;
+                        if in_array != closes_array {
+                            tokens.report(TokenParseError::ExpectedComma.into(), span);
+                        }
+                        // close up to the container the bracket belongs to,
+                        // or skip it if there is none
+                        if let Some(depth) = tree
+                            .stack
+                            .iter()
+                            .rposition(|frame| frame.is_array() == closes_array)
+                        {
+                            while tree.stack.len() > depth {
+                                tree.close();
+                            }
+                        }
+                        State::AfterValue
+                    }
+                    Ok(Token::Colon) => {
+                        tokens.report(TokenParseError::ExpectedComma.into(), span);
+                        State::Value
+                    }
+                    // the comma before the next value is missing
+                    token => {
+                        if token.is_ok() {
+                            tokens.report(TokenParseError::ExpectedComma.into(), span.clone());
+                        }
+                        tokens.push_back((token, span));
+                        if in_array {
+                            State::Value
+                        } else {
+                            State::Key { first: false }
+                        }
+                    }
+                }
+            }
+
+            State::Key { first } => match token {
+                Ok(Token::RightCurlyBracket) => {
+                    if !first {
+                        tokens.report(TokenParseError::ExpectedProperty.into(), span);
+                    }
+                    tree.close();
+                    State::AfterValue
+                }
+                Ok(Token::Comma | Token::RightSquareBracket) => {
+                    tokens.report(TokenParseError::ExpectedProperty.into(), span.clone());
+                    tokens.push_back((token, span));
+                    State::AfterValue
+                }
+                Ok(Token::Colon) => {
+                    tokens.report(TokenParseError::ExpectedProperty.into(), span);
+                    tree.set_key(String::new());
+                    State::Value
+                }
+                key => {
+                    let name = match key {
+                        Ok(Token::String(raw)) => tokens.string(raw, span).into_owned(),
+                        Ok(_) => {
+                            tokens.report(TokenParseError::ExpectedProperty.into(), span.clone());
+                            tokens.text(span).to_owned()
+                        }
+                        Err(err) => {
+                            tokens.report(err.into(), span.clone());
+                            tokens.text(span).to_owned()
+                        }
+                    };
+                    tree.set_key(name);
+                    if tokens.next_if(&Token::Colon) {
+                        State::Value
+                    } else {
+                        match tokens.next() {
+                            // text that doesn't lex is taken for the value,
+                            // and is what `parse` stops at
+                            Some((Err(err), span)) => {
+                                tokens.report(err.into(), span.clone());
+                                tokens.report(TokenParseError::ExpectedColon.into(), span);
+                                tree.add(Value::Null);
+                                State::AfterValue
+                            }
+                            Some(lexed) => {
+                                let span = lexed.1.clone();
+                                tokens.report(TokenParseError::ExpectedColon.into(), span);
+                                tokens.push_back(lexed);
+                                State::Value
+                            }
+                            None => State::Value,
+                        }
+                    }
+                }
+            },
+        };
+    }
+
+    while !tree.stack.is_empty() {
+        tree.close();
+    }
+    LossyParse {
+        value: tree.root.unwrap_or(Value::Null),
+        diagnostics: tokens.diagnostics,
+    }
+}
+
+/// Every syntax error in `input` that can be told apart, in input order,
+/// none for valid input
+///
+/// These are the diagnostics of [`parse_lossy`], for checking input rather
+/// than recovering it:
+///
+/// ```
+/// use parser_json_rs::diagnose;
+///
+/// let codes: Vec<_> = diagnose(r#"{"a": [1 2], "b" 3, "c": tru}"#)
+///     .iter()
+///     .map(|diagnostic| diagnostic.error.code())
+///     .collect();
+/// assert_eq!(codes, ["E107_EXPECTED_COMMA", "E109_EXPECTED_COLON", "E008_MISSPELLED_LITERAL"]);
+/// ```
+pub fn diagnose(input: &str) -> Vec<Diagnostic> {
+    parse_lossy(input).diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diagnose, parse_lossy};
+    use crate::prelude::*;
+    use crate::{DEFAULT_MAX_DEPTH, parse_with_diagnostic};
+
+    fn codes(input: &str) -> Vec<&'static str> {
+        diagnose(input)
+            .iter()
+            .map(|diagnostic| diagnostic.error.code())
+            .collect()
+    }
+
+    #[test]
+    fn valid_input_has_no_diagnostics() {
+        let input = r#" {"a": [1, 2.5, {"b": null}], "c": "d\"e", "f": [], "g": {}} "#;
+
+        assert!(diagnose(input).is_empty());
+    }
+
+    #[test]
+    fn report_every_error() {
+        let input = "[1 2, , {\"a\" 3, \"b\": tru, 4: 5,}, x, \"\\u12\"]";
+        let diagnostics = diagnose(input);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.error.code(), &input[diagnostic.span.clone()]))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("E107_EXPECTED_COMMA", "2"),
+                ("E103_EXPECTED_VALUE", ","),
+                ("E109_EXPECTED_COLON", "3"),
+                ("E008_MISSPELLED_LITERAL", "tru"),
+                ("E108_EXPECTED_KEY", "4"),
+                ("E108_EXPECTED_KEY", "}"),
+                ("E006_UNEXPECTED_CHARACTER", "x"),
+                ("E104_UNFINISHED_ESCAPE", "\"\\u12\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn brackets_and_ends() {
+        assert_eq!(codes("[1}"), ["E107_EXPECTED_COMMA", "E102_UNEXPECTED_END"]);
+        assert_eq!(codes("{\"a\": [1}"), ["E107_EXPECTED_COMMA"]);
+        assert_eq!(codes("[1]]"), ["E111_TRAILING_CHARACTERS"]);
+        assert_eq!(codes("[\"abc"), ["E001_UNCLOSED_STRING"]);
+        assert_eq!(codes(""), ["E101_EMPTY_INPUT"]);
+        assert_eq!(codes("]"), ["E103_EXPECTED_VALUE"]);
+        assert_eq!(codes("[[["), ["E102_UNEXPECTED_END"]);
+        assert_eq!(
+            codes(&"[".repeat(DEFAULT_MAX_DEPTH + 10)),
+            ["E110_DEPTH_LIMIT_EXCEEDED"]
+        );
+    }
+
+    #[test]
+    fn lossy_values() {
+        let value = |input: &str| parse_lossy(input).value;
+
+        assert_eq!(value("[1, , 2,]"), json!([1, null, 2]));
+        assert_eq!(value("[1, [2, 3"), json!([1, [2, 3]]));
+        assert_eq!(
+            value(r#"{"a": 1,, b: 2, 3: [x], : 4}"#),
+            json!({"a": 1, "b": 2, "3": [null], "": 4})
+        );
+        assert_eq!(value(r#"{"a": [1}"#), json!({"a": [1]}));
+        assert_eq!(value(r#"["\u12"]"#), json!(["\\u12"]));
+        assert_eq!(value("[1]]"), json!([1]));
+        assert_eq!(value(""), json!(null));
+        let input = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e"}"#;
+        assert_eq!(value(input), crate::parse(input).unwrap());
+    }
+
+    #[test]
+    fn first_diagnostic_is_the_parse_error() {
+        // every input of up to four of these pieces, run together
+        let pieces = [
+            "",
+            " ",
+            "[",
+            "]",
+            "{",
+            "}",
+            ",",
+            ":",
+            "1",
+            "-",
+            "\"a\"",
+            "\"\\u12\"",
+            "\"",
+            "tru",
+            "null",
+            "x",
+        ];
+        let mut inputs = vec![String::new()];
+        for _ in 0..4 {
+            inputs = inputs
+                .iter()
+                .flat_map(|input| pieces.iter().map(move |piece| format!("{input}{piece}")))
+                .collect();
+            inputs.sort();
+            inputs.dedup();
+
+            for input in &inputs {
+                assert_eq!(
+                    diagnose(input).into_iter().next(),
+                    parse_with_diagnostic(input).err(),
+                    "{input}"
+                );
+            }
+        }
+    }
+}
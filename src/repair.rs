@@ -0,0 +1,298 @@
+// Fixing the mistakes people and other tools commonly make writing JSON
+
+use super::prelude::*;
+
+/// A mistake [`repair`] fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixKind {
+    /// a comma before a closing bracket, removed
+    TrailingComma,
+    /// a string in single quotes, requoted
+    SingleQuotes,
+    /// an object key without quotes, quoted
+    UnquotedKey,
+    /// a `//` or `/* */` comment, removed
+    Comment,
+    /// a string the input ends within, closed
+    UnclosedString,
+    /// an array or object the input ends within, closed
+    UnclosedBracket,
+}
+
+/// A fix and the byte offset in the input of what it fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fix {
+    pub kind: FixKind,
+    pub offset: usize,
+}
+
+/// Result of [`repair`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repaired {
+    pub text: String,
+    /// the fixes made, in input order
+    pub fixes: Vec<Fix>,
+}
+
+/// Rewrite almost-JSON as JSON, fixing only what has a single obvious fix
+///
+/// Comments are removed, as are commas right before a closing bracket,
+/// single-quoted strings and bare identifiers used as keys get double
+/// quotes, and strings, arrays and objects the input ends within are
+/// closed. Everything else is left as it is, valid JSON comes back
+/// unchanged, and the result may still not parse:
+///
+/// ```
+/// use parser_json_rs::{FixKind, parse, repair};
+///
+/// let repaired = repair("{name: 'web', ports: [80, 443,], // todo\n");
+/// assert_eq!(repaired.text, "{\"name\": \"web\", \"ports\": [80, 443] \n}");
+/// assert!(parse(&repaired.text).is_ok());
+/// let kinds: Vec<_> = repaired.fixes.iter().map(|fix| fix.kind).collect();
+/// assert_eq!(kinds, [
+///     FixKind::UnquotedKey,
+///     FixKind::SingleQuotes,
+///     FixKind::UnquotedKey,
+///     FixKind::TrailingComma,
+///     FixKind::TrailingComma,
+///     FixKind::Comment,
+///     FixKind::UnclosedBracket,
+/// ]);
+/// ```
+pub fn repair(input: &str) -> Repaired {
+    let mut repairer = Repairer {
+        input,
+        pos: 0,
+        text: String::with_capacity(input.len()),
+        fixes: Vec::new(),
+        open: Vec::new(),
+    };
+    repairer.run();
+
+    Repaired {
+        text: repairer.text,
+        fixes: repairer.fixes,
+    }
+}
+
+struct Repairer<'a> {
+    input: &'a str,
+    pos: usize,
+    text: String,
+    fixes: Vec<Fix>,
+    /// the closing brackets of the open arrays and objects, innermost last
+    open: Vec<char>,
+}
+
+impl Repairer<'_> {
+    fn run(&mut self) {
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            match c {
+                '/' if self.rest().starts_with("//") || self.rest().starts_with("/*") => {
+                    self.skip_comment();
+                    self.fix(FixKind::Comment, start);
+                }
+                '"' => self.string('"'),
+                '\'' => {
+                    self.fix(FixKind::SingleQuotes, start);
+                    self.string('\'');
+                }
+                '[' | '{' => {
+                    self.open.push(if c == '[' { ']' } else { '}' });
+                    self.copy(c);
+                }
+                ']' | '}' => {
+                    if self.open.last() == Some(&c) {
+                        self.open.pop();
+                    }
+                    self.copy(c);
+                }
+                // at the end of the input the comma is before a closer added below
+                ',' if matches!(self.after_comma(), Some(']' | '}'))
+                    || self.after_comma().is_none() && !self.open.is_empty() =>
+                {
+                    self.pos += 1;
+                    self.fix(FixKind::TrailingComma, start);
+                }
+                c if c.is_alphabetic() || c == '_' || c == '$' => self.identifier(),
+                c => self.copy(c),
+            }
+        }
+
+        while let Some(close) = self.open.pop() {
+            self.text.push(close);
+            self.fix(FixKind::UnclosedBracket, self.input.len());
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn copy(&mut self, c: char) {
+        self.text.push(c);
+        self.pos += c.len_utf8();
+    }
+
+    fn fix(&mut self, kind: FixKind, offset: usize) {
+        self.fixes.push(Fix { kind, offset });
+    }
+
+    /// Skip the comment starting at the current position
+    fn skip_comment(&mut self) {
+        let rest = self.rest();
+        let len = if rest.starts_with("//") {
+            // the line break stays, it may be all that separates two tokens
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            rest[2..].find("*/").map_or(rest.len(), |end| end + 4)
+        };
+        self.pos += len;
+    }
+
+    /// The first character after the comma at the current position that
+    /// isn't whitespace or part of a comment
+    fn after_comma(&self) -> Option<char> {
+        let mut rest = &self.rest()[1..];
+        loop {
+            rest = rest.trim_start();
+            if let Some(line) = rest.strip_prefix("//") {
+                rest = line.find('\n').map_or("", |end| &line[end..]);
+            } else if let Some(block) = rest.strip_prefix("/*") {
+                rest = block.find("*/").map_or("", |end| &block[end + 2..]);
+            } else {
+                return rest.chars().next();
+            }
+        }
+    }
+
+    /// Copy the string starting at the current position in double quotes
+    fn string(&mut self, quote: char) {
+        let start = self.pos;
+        self.pos += 1;
+        self.text.push('"');
+        let mut escaped = false;
+
+        while let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                    // `\'` only needs escaping in single quotes
+                    if c != '\'' {
+                        self.text.push('\\');
+                    }
+                    self.text.push(c);
+                }
+                '\\' => escaped = true,
+                _ if c == quote => {
+                    self.text.push('"');
+                    return;
+                }
+                '"' => self.text.push_str("\\\""),
+                c => self.text.push(c),
+            }
+        }
+
+        if escaped {
+            // a lone backslash, kept as one
+            self.text.push_str("\\\\");
+        }
+        self.text.push('"');
+        self.fix(FixKind::UnclosedString, start);
+    }
+
+    /// Copy the word starting at the current position, quoted if it is the
+    /// key of an object member
+    fn identifier(&mut self) {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(self.rest().len());
+        let word = &self.input[start..start + len];
+        self.pos += len;
+
+        let is_key = self.open.last() == Some(&'}') && self.rest().trim_start().starts_with(':');
+        if is_key {
+            self.text.push('"');
+            self.text.push_str(word);
+            self.text.push('"');
+            self.fix(FixKind::UnquotedKey, start);
+        } else {
+            self.text.push_str(word);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fix, FixKind, repair};
+    use crate::parse;
+    use crate::prelude::*;
+
+    fn repaired(input: &str) -> String {
+        let text = repair(input).text;
+        assert!(parse(&text).is_ok(), "{text}");
+        text
+    }
+
+    #[test]
+    fn valid_json_is_unchanged() {
+        let input = r#" {"a": [1, 2.5, {"b": null}], "c": "d\"e, ]", "f": [], "g": {}} "#;
+        let result = repair(input);
+
+        assert_eq!(result.text, input);
+        assert!(result.fixes.is_empty());
+    }
+
+    #[test]
+    fn fix_common_mistakes() {
+        assert_eq!(repaired("[1, 2, ]"), "[1, 2 ]");
+        assert_eq!(repaired("{'a': 'it\\'s \"x\"'}"), r#"{"a": "it's \"x\""}"#);
+        assert_eq!(
+            repaired("{$id: 1, _x2: true}"),
+            r#"{"$id": 1, "_x2": true}"#
+        );
+        assert_eq!(repaired("[1 /* one */, 2] // two"), "[1 , 2] ");
+        assert_eq!(repaired("[1, // a\n 2,\n // b\n]"), "[1, \n 2\n \n]");
+        assert_eq!(
+            repaired(r#"{"a": [1, {"b": "c"#),
+            r#"{"a": [1, {"b": "c"}]}"#
+        );
+        assert_eq!(repaired("'a\\"), r#""a\\""#);
+    }
+
+    #[test]
+    fn ambiguous_mistakes_are_left_alone() {
+        let result = repair("[yes, {a b}] ]");
+
+        assert_eq!(result.text, "[yes, {a b}] ]");
+        assert!(result.fixes.is_empty());
+    }
+
+    #[test]
+    fn fixes_point_into_the_input() {
+        let result = repair("{a: 1,}");
+
+        assert_eq!(
+            result.fixes,
+            [
+                Fix {
+                    kind: FixKind::UnquotedKey,
+                    offset: 1
+                },
+                Fix {
+                    kind: FixKind::TrailingComma,
+                    offset: 5
+                },
+            ]
+        );
+        assert_eq!(parse(&result.text), Ok(json!({"a": 1})));
+    }
+}
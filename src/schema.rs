@@ -0,0 +1,1112 @@
+//! Validation against [JSON Schema](https://json-schema.org/) documents
+//!
+//! The core keywords of draft 2020-12 are supported, along with their
+//! draft-07 spellings: `type`, `enum`, `const`, the numeric, length and size
+//! bounds, `properties`, `required`, `additionalProperties`, `items`,
+//! `prefixItems`, `additionalItems`, `uniqueItems`, `allOf`, `anyOf`,
+//! `oneOf`, `not` and `$ref` to anywhere in the same document. Other
+//! keywords are ignored, as the specification asks of unknown keywords.
+
+use core::fmt;
+
+use super::Map;
+use super::Value;
+use super::pointer::escape_token;
+use super::prelude::*;
+
+/// Why a schema could not be compiled
+#[derive(Debug, PartialEq)]
+pub enum SchemaError {
+    /// schema path of a keyword whose value has the wrong form
+    InvalidKeyword(String),
+    /// a `$ref` that doesn't point into the schema document itself
+    UnsupportedReference(String),
+    /// a `$ref` pointing at nothing
+    UnresolvedReference(String),
+    /// a `$ref` leading back to itself without going into a part of the
+    /// instance, so checking it would never end
+    ReferenceCycle(String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::InvalidKeyword(path) => write!(f, "invalid keyword at {path}"),
+            SchemaError::UnsupportedReference(reference) => {
+                write!(f, "unsupported reference {reference}")
+            }
+            SchemaError::UnresolvedReference(reference) => {
+                write!(f, "unresolved reference {reference}")
+            }
+            SchemaError::ReferenceCycle(reference) => {
+                write!(f, "reference {reference} refers to itself")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SchemaError {}
+
+/// An instance failing one keyword of the schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// JSON Pointer to the offending part of the instance
+    pub instance_path: String,
+    /// JSON Pointer to the keyword in the schema, as in `/properties/age/minimum`
+    pub schema_path: String,
+    pub message: String,
+}
+
+/// A compiled schema, to validate any number of instances against
+#[derive(Debug)]
+pub struct Schema {
+    root: Node,
+    /// compiled targets of every `$ref`, by reference
+    refs: Map<String, Node>,
+}
+
+impl Schema {
+    pub fn compile(schema: &Value) -> Result<Self, SchemaError> {
+        let mut compiler = Compiler {
+            document: schema,
+            refs: Map::default(),
+        };
+        let root = compiler.node(schema, String::new())?;
+        let refs = compiler
+            .refs
+            .into_iter()
+            .map(|(reference, node)| (reference, node.expect("compiled once resolved")))
+            .collect();
+        check_cycles(&refs)?;
+
+        Ok(Schema { root, refs })
+    }
+
+    /// Every violation of the schema by `instance`, none if it is valid
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        self.check(&self.root, instance, "", &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        let mut violations = Vec::new();
+        self.check(&self.root, instance, "", &mut violations);
+        violations.is_empty()
+    }
+
+    fn check(&self, node: &Node, instance: &Value, path: &str, out: &mut Vec<Violation>) {
+        for keyword in &node.keywords {
+            self.check_keyword(node, keyword, instance, path, out);
+        }
+    }
+
+    fn check_keyword(
+        &self,
+        node: &Node,
+        keyword: &Keyword,
+        instance: &Value,
+        path: &str,
+        out: &mut Vec<Violation>,
+    ) {
+        let mut fail = |message: String| {
+            out.push(Violation {
+                instance_path: path.to_owned(),
+                schema_path: format!("{}/{}", node.path, keyword.name()),
+                message,
+            });
+        };
+
+        match keyword {
+            Keyword::False => fail(String::from("no value is allowed here")),
+            Keyword::Type(types) => {
+                if !types.iter().any(|ty| ty.matches(instance)) {
+                    let names: Vec<&str> = types.iter().map(|ty| ty.name()).collect();
+                    fail(format!("expected {}", names.join(" or ")));
+                }
+            }
+            Keyword::Enum(values) => {
+                if !values.contains(instance) {
+                    fail(String::from("not one of the allowed values"));
+                }
+            }
+            Keyword::Const(value) => {
+                if instance != value {
+                    fail(String::from("not the required value"));
+                }
+            }
+            Keyword::Bound { bound, limit } => {
+                let Some(actual) = bound.measure(instance) else {
+                    return;
+                };
+                if !bound.allows(actual, *limit) {
+                    fail(format!(
+                        "{} {actual} {} {limit}",
+                        bound.subject(),
+                        bound.relation()
+                    ));
+                }
+            }
+            Keyword::MultipleOf(divisor) => {
+                if let Value::Number(n) = instance {
+                    let quotient = n / divisor;
+                    if quotient.is_finite() && quotient % 1.0 != 0.0 {
+                        fail(format!("{n} is not a multiple of {divisor}"));
+                    }
+                }
+            }
+            Keyword::Required(names) => {
+                if let Value::Object(map) = instance {
+                    for name in names.iter().filter(|name| !map.contains_key(*name)) {
+                        fail(format!("missing required property {name:?}"));
+                    }
+                }
+            }
+            Keyword::Properties(properties) => {
+                if let Value::Object(map) = instance {
+                    for (name, schema) in properties {
+                        if let Some(value) = map.get(name) {
+                            self.check(schema, value, &child_path(path, name), out);
+                        }
+                    }
+                }
+            }
+            Keyword::AdditionalProperties { known, schema } => {
+                if let Value::Object(map) = instance {
+                    let mut names: Vec<&String> =
+                        map.keys().filter(|name| !known.contains(*name)).collect();
+                    names.sort();
+                    for name in names {
+                        self.check(schema, &map[name], &child_path(path, name), out);
+                    }
+                }
+            }
+            Keyword::PrefixItems(schemas) => {
+                if let Value::Array(values) = instance {
+                    for (i, (schema, value)) in schemas.iter().zip(values).enumerate() {
+                        self.check(schema, value, &child_path(path, &i.to_string()), out);
+                    }
+                }
+            }
+            Keyword::Items { skip, schema } => {
+                if let Value::Array(values) = instance {
+                    for (i, value) in values.iter().enumerate().skip(*skip) {
+                        self.check(schema, value, &child_path(path, &i.to_string()), out);
+                    }
+                }
+            }
+            Keyword::UniqueItems => {
+                if let Value::Array(values) = instance {
+                    let repeated = values
+                        .iter()
+                        .enumerate()
+                        .any(|(i, value)| values[..i].contains(value));
+                    if repeated {
+                        fail(String::from("array items are not unique"));
+                    }
+                }
+            }
+            Keyword::AllOf(schemas) => {
+                for schema in schemas {
+                    self.check(schema, instance, path, out);
+                }
+            }
+            Keyword::AnyOf(schemas) => {
+                if !schemas
+                    .iter()
+                    .any(|schema| self.passes(schema, instance, path))
+                {
+                    fail(String::from("matches none of the schemas"));
+                }
+            }
+            Keyword::OneOf(schemas) => {
+                let matches = schemas
+                    .iter()
+                    .filter(|schema| self.passes(schema, instance, path))
+                    .count();
+                if matches != 1 {
+                    fail(format!(
+                        "matches {matches} of the schemas instead of exactly one"
+                    ));
+                }
+            }
+            Keyword::Not(schema) => {
+                if self.passes(schema, instance, path) {
+                    fail(String::from("matches a schema it must not"));
+                }
+            }
+            Keyword::Ref(reference) => self.check(&self.refs[reference], instance, path, out),
+        }
+    }
+
+    fn passes(&self, node: &Node, instance: &Value, path: &str) -> bool {
+        let mut violations = Vec::new();
+        self.check(node, instance, path, &mut violations);
+        violations.is_empty()
+    }
+}
+
+/// Fail on a `$ref` that leads back to itself through nothing but other
+/// references and `allOf`, `anyOf`, `oneOf` or `not`, all of which check the
+/// same instance, so that validating would recurse forever
+fn check_cycles(refs: &Map<String, Node>) -> Result<(), SchemaError> {
+    for reference in refs.keys() {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut pending = vec![reference.as_str()];
+        while let Some(next) = pending.pop() {
+            let mut targets = Vec::new();
+            same_instance_refs(&refs[next], &mut targets);
+            for target in targets {
+                if target == reference {
+                    return Err(SchemaError::ReferenceCycle(reference.clone()));
+                }
+                if !seen.contains(&target) {
+                    seen.push(target);
+                    pending.push(target);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The references `node` follows without going into a part of the instance
+fn same_instance_refs<'n>(node: &'n Node, out: &mut Vec<&'n str>) {
+    for keyword in &node.keywords {
+        match keyword {
+            Keyword::Ref(reference) => out.push(reference),
+            Keyword::AllOf(nodes) | Keyword::AnyOf(nodes) | Keyword::OneOf(nodes) => {
+                for node in nodes {
+                    same_instance_refs(node, out);
+                }
+            }
+            Keyword::Not(node) => same_instance_refs(node, out),
+            _ => {}
+        }
+    }
+}
+
+/// Path of the member `name` of the value at `path`, escaped as a JSON Pointer
+fn child_path(path: &str, name: &str) -> String {
+    format!("{path}/{}", escape_token(name))
+}
+
+#[derive(Debug)]
+struct Node {
+    /// schema path of this (sub)schema
+    path: String,
+    keywords: Vec<Keyword>,
+}
+
+#[derive(Debug)]
+enum Keyword {
+    /// the `false` schema
+    False,
+    Type(Vec<Type>),
+    Enum(Vec<Value>),
+    Const(Value),
+    Bound {
+        bound: Bound,
+        limit: f64,
+    },
+    MultipleOf(f64),
+    Required(Vec<String>),
+    Properties(Vec<(String, Node)>),
+    AdditionalProperties {
+        known: Vec<String>,
+        schema: Box<Node>,
+    },
+    PrefixItems(Vec<Node>),
+    /// items from index `skip` on, following any prefix items
+    Items {
+        skip: usize,
+        schema: Box<Node>,
+    },
+    UniqueItems,
+    AllOf(Vec<Node>),
+    AnyOf(Vec<Node>),
+    OneOf(Vec<Node>),
+    Not(Box<Node>),
+    Ref(String),
+}
+
+impl Keyword {
+    fn name(&self) -> &'static str {
+        match self {
+            Keyword::False => "false",
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::Bound { bound, .. } => bound.keyword(),
+            Keyword::MultipleOf(_) => "multipleOf",
+            Keyword::Required(_) => "required",
+            Keyword::Properties(_) => "properties",
+            Keyword::AdditionalProperties { .. } => "additionalProperties",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items { .. } => "items",
+            Keyword::UniqueItems => "uniqueItems",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+            Keyword::Ref(_) => "$ref",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    String,
+    Integer,
+}
+
+impl Type {
+    const ALL: [Type; 7] = [
+        Type::Null,
+        Type::Boolean,
+        Type::Object,
+        Type::Array,
+        Type::Number,
+        Type::String,
+        Type::Integer,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Type::Null => "null",
+            Type::Boolean => "boolean",
+            Type::Object => "object",
+            Type::Array => "array",
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Integer => "integer",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Null, Value::Null)
+            | (Type::Boolean, Value::Boolean(_))
+            | (Type::Object, Value::Object(_))
+            | (Type::Array, Value::Array(_))
+            | (Type::Number, Value::Number(_))
+            | (Type::String, Value::String(_)) => true,
+            (Type::Integer, Value::Number(n)) => n.is_finite() && n % 1.0 == 0.0,
+            _ => false,
+        }
+    }
+}
+
+/// Keywords putting a lower or upper limit on a number or a size
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Minimum,
+    Maximum,
+    ExclusiveMinimum,
+    ExclusiveMaximum,
+    MinLength,
+    MaxLength,
+    MinItems,
+    MaxItems,
+    MinProperties,
+    MaxProperties,
+}
+
+impl Bound {
+    const ALL: [Bound; 10] = [
+        Bound::Minimum,
+        Bound::Maximum,
+        Bound::ExclusiveMinimum,
+        Bound::ExclusiveMaximum,
+        Bound::MinLength,
+        Bound::MaxLength,
+        Bound::MinItems,
+        Bound::MaxItems,
+        Bound::MinProperties,
+        Bound::MaxProperties,
+    ];
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Bound::Minimum => "minimum",
+            Bound::Maximum => "maximum",
+            Bound::ExclusiveMinimum => "exclusiveMinimum",
+            Bound::ExclusiveMaximum => "exclusiveMaximum",
+            Bound::MinLength => "minLength",
+            Bound::MaxLength => "maxLength",
+            Bound::MinItems => "minItems",
+            Bound::MaxItems => "maxItems",
+            Bound::MinProperties => "minProperties",
+            Bound::MaxProperties => "maxProperties",
+        }
+    }
+
+    /// Whether the limit must be a count rather than any number
+    fn is_count(self) -> bool {
+        !matches!(
+            self,
+            Bound::Minimum | Bound::Maximum | Bound::ExclusiveMinimum | Bound::ExclusiveMaximum
+        )
+    }
+
+    /// The quantity the bound limits, `None` for values it doesn't apply to.
+    /// String lengths count characters rather than bytes.
+    fn measure(self, value: &Value) -> Option<f64> {
+        let measure = match (self, value) {
+            (
+                Bound::Minimum | Bound::Maximum | Bound::ExclusiveMinimum | Bound::ExclusiveMaximum,
+                Value::Number(n),
+            ) => *n,
+            (Bound::MinLength | Bound::MaxLength, Value::String(s)) => s.chars().count() as f64,
+            (Bound::MinItems | Bound::MaxItems, Value::Array(values)) => values.len() as f64,
+            (Bound::MinProperties | Bound::MaxProperties, Value::Object(map)) => map.len() as f64,
+            _ => return None,
+        };
+        Some(measure)
+    }
+
+    fn allows(self, actual: f64, limit: f64) -> bool {
+        match self {
+            Bound::Minimum | Bound::MinLength | Bound::MinItems | Bound::MinProperties => {
+                actual >= limit
+            }
+            Bound::Maximum | Bound::MaxLength | Bound::MaxItems | Bound::MaxProperties => {
+                actual <= limit
+            }
+            Bound::ExclusiveMinimum => actual > limit,
+            Bound::ExclusiveMaximum => actual < limit,
+        }
+    }
+
+    fn subject(self) -> &'static str {
+        match self {
+            Bound::Minimum | Bound::Maximum | Bound::ExclusiveMinimum | Bound::ExclusiveMaximum => {
+                "value"
+            }
+            Bound::MinLength | Bound::MaxLength => "length",
+            Bound::MinItems | Bound::MaxItems => "item count",
+            Bound::MinProperties | Bound::MaxProperties => "property count",
+        }
+    }
+
+    /// How `actual` should relate to the limit
+    fn relation(self) -> &'static str {
+        match self {
+            Bound::Minimum | Bound::MinLength | Bound::MinItems | Bound::MinProperties => {
+                "is less than the minimum of"
+            }
+            Bound::Maximum | Bound::MaxLength | Bound::MaxItems | Bound::MaxProperties => {
+                "is more than the maximum of"
+            }
+            Bound::ExclusiveMinimum => "is not more than",
+            Bound::ExclusiveMaximum => "is not less than",
+        }
+    }
+}
+
+struct Compiler<'s> {
+    document: &'s Value,
+    /// every reference met so far, `None` while its target is being compiled
+    refs: Map<String, Option<Node>>,
+}
+
+impl Compiler<'_> {
+    fn node(&mut self, schema: &Value, path: String) -> Result<Node, SchemaError> {
+        let map = match schema {
+            Value::Boolean(true) => {
+                return Ok(Node {
+                    path,
+                    keywords: Vec::new(),
+                });
+            }
+            Value::Boolean(false) => {
+                return Ok(Node {
+                    path,
+                    keywords: vec![Keyword::False],
+                });
+            }
+            Value::Object(map) => map,
+            _ => return Err(SchemaError::InvalidKeyword(path)),
+        };
+        let keyword_path = |name: &str| format!("{path}/{name}");
+        let invalid = |name: &str| SchemaError::InvalidKeyword(keyword_path(name));
+        let mut keywords = Vec::new();
+
+        if let Some(types) = map.get("type") {
+            let types = match types {
+                Value::Array(types) => types.iter().map(parse_type).collect(),
+                ty => parse_type(ty).map(|ty| vec![ty]),
+            };
+            keywords.push(Keyword::Type(types.ok_or_else(|| invalid("type"))?));
+        }
+        if let Some(values) = map.get("enum") {
+            let Value::Array(values) = values else {
+                return Err(invalid("enum"));
+            };
+            keywords.push(Keyword::Enum(values.clone()));
+        }
+        if let Some(value) = map.get("const") {
+            keywords.push(Keyword::Const(value.clone()));
+        }
+        for bound in Bound::ALL {
+            let Some(limit) = map.get(bound.keyword()) else {
+                continue;
+            };
+            match limit {
+                Value::Number(limit)
+                    if !bound.is_count() || (*limit >= 0.0 && limit % 1.0 == 0.0) =>
+                {
+                    keywords.push(Keyword::Bound {
+                        bound,
+                        limit: *limit,
+                    });
+                }
+                _ => return Err(invalid(bound.keyword())),
+            }
+        }
+        if let Some(divisor) = map.get("multipleOf") {
+            match divisor {
+                Value::Number(divisor) if *divisor > 0.0 => {
+                    keywords.push(Keyword::MultipleOf(*divisor))
+                }
+                _ => return Err(invalid("multipleOf")),
+            }
+        }
+        if let Some(names) = map.get("required") {
+            let names = match names {
+                Value::Array(names) => names
+                    .iter()
+                    .map(|name| match name {
+                        Value::String(name) => Some(name.as_str().to_owned()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => None,
+            };
+            keywords.push(Keyword::Required(names.ok_or_else(|| invalid("required"))?));
+        }
+
+        let mut known = Vec::new();
+        if let Some(properties) = map.get("properties") {
+            let Value::Object(properties) = properties else {
+                return Err(invalid("properties"));
+            };
+            let mut names: Vec<&String> = properties.keys().collect();
+            names.sort();
+            let mut compiled = Vec::new();
+            for name in names {
+                let path = child_path(&keyword_path("properties"), name);
+                compiled.push((name.clone(), self.node(&properties[name], path)?));
+                known.push(name.clone());
+            }
+            keywords.push(Keyword::Properties(compiled));
+        }
+        if let Some(schema) = map.get("additionalProperties") {
+            let schema = Box::new(self.node(schema, keyword_path("additionalProperties"))?);
+            keywords.push(Keyword::AdditionalProperties { known, schema });
+        }
+
+        // the tuple form is `prefixItems`, or `items` holding an array in draft-07
+        let (tuple, rest) = match (map.get("prefixItems"), map.get("items")) {
+            (Some(tuple), rest) => (
+                Some(("prefixItems", tuple)),
+                rest.map(|rest| ("items", rest)),
+            ),
+            (None, Some(items @ Value::Array(_))) => (
+                Some(("items", items)),
+                map.get("additionalItems")
+                    .map(|rest| ("additionalItems", rest)),
+            ),
+            (None, rest) => (None, rest.map(|rest| ("items", rest))),
+        };
+        let mut skip = 0;
+        if let Some((name, tuple)) = tuple {
+            let Value::Array(schemas) = tuple else {
+                return Err(invalid(name));
+            };
+            let schemas = schemas
+                .iter()
+                .enumerate()
+                .map(|(i, schema)| self.node(schema, format!("{path}/{name}/{i}")))
+                .collect::<Result<Vec<Node>, SchemaError>>()?;
+            skip = schemas.len();
+            keywords.push(Keyword::PrefixItems(schemas));
+        }
+        if let Some((name, schema)) = rest {
+            let schema = Box::new(self.node(schema, keyword_path(name))?);
+            keywords.push(Keyword::Items { skip, schema });
+        }
+        match map.get("uniqueItems") {
+            None | Some(Value::Boolean(false)) => {}
+            Some(Value::Boolean(true)) => keywords.push(Keyword::UniqueItems),
+            Some(_) => return Err(invalid("uniqueItems")),
+        }
+
+        for (name, make) in [
+            ("allOf", Keyword::AllOf as fn(Vec<Node>) -> Keyword),
+            ("anyOf", Keyword::AnyOf),
+            ("oneOf", Keyword::OneOf),
+        ] {
+            let Some(schemas) = map.get(name) else {
+                continue;
+            };
+            let Value::Array(schemas) = schemas else {
+                return Err(invalid(name));
+            };
+            let schemas = schemas
+                .iter()
+                .enumerate()
+                .map(|(i, schema)| self.node(schema, format!("{path}/{name}/{i}")))
+                .collect::<Result<Vec<Node>, SchemaError>>()?;
+            keywords.push(make(schemas));
+        }
+        if let Some(schema) = map.get("not") {
+            keywords.push(Keyword::Not(Box::new(
+                self.node(schema, keyword_path("not"))?,
+            )));
+        }
+        if let Some(reference) = map.get("$ref") {
+            let Value::String(reference) = reference else {
+                return Err(invalid("$ref"));
+            };
+            self.resolve(reference)?;
+            keywords.push(Keyword::Ref(reference.as_str().to_owned()));
+        }
+
+        Ok(Node { path, keywords })
+    }
+
+    /// Compile the target of `reference` unless it already is
+    fn resolve(&mut self, reference: &str) -> Result<(), SchemaError> {
+        if self.refs.contains_key(reference) {
+            return Ok(());
+        }
+        let Some(fragment) = reference.strip_prefix('#') else {
+            return Err(SchemaError::UnsupportedReference(reference.to_owned()));
+        };
+        let unresolved = || SchemaError::UnresolvedReference(reference.to_owned());
+        let pointer = percent_decode(fragment).ok_or_else(unresolved)?;
+        let target = self.document.pointer(&pointer).ok_or_else(unresolved)?;
+
+        // registered before compiling so that recursive references end here
+        self.refs.insert(reference.to_owned(), None);
+        let node = self.node(target, pointer)?;
+        self.refs.insert(reference.to_owned(), Some(node));
+        Ok(())
+    }
+}
+
+/// The fragment of a URI with its `%XX` escapes decoded, `None` if they
+/// are malformed or don't decode to UTF-8
+fn percent_decode(fragment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(fragment.len());
+    let mut rest = fragment.bytes();
+    while let Some(byte) = rest.next() {
+        if byte == b'%' {
+            let hex = [rest.next()?, rest.next()?];
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            let hex = core::str::from_utf8(&hex).expect("hex digits are ASCII");
+            bytes.push(u8::from_str_radix(hex, 16).expect("checked above"));
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse_type(value: &Value) -> Option<Type> {
+    let Value::String(name) = value else {
+        return None;
+    };
+    Type::ALL.into_iter().find(|ty| ty.name() == name)
+}
+
+/// Schema describing every one of `samples`, such as payloads captured from
+/// an undocumented API
+///
+/// Values seen with different types get a union of types, object members
+/// found in every sample are `required`, and array items are described by a
+/// single schema covering all of them.
+pub fn infer<'v>(samples: impl IntoIterator<Item = &'v Value>) -> Value {
+    let mut shape = Shape::default();
+    for sample in samples {
+        shape.add(sample);
+    }
+
+    let mut schema = match shape.to_schema() {
+        Value::Object(map) => map,
+        _ => unreachable!("shapes describe themselves as objects"),
+    };
+    schema.insert(
+        String::from("$schema"),
+        Value::String("https://json-schema.org/draft/2020-12/schema".into()),
+    );
+    Value::Object(schema)
+}
+
+/// Everything seen so far at one place in the samples
+#[derive(Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    /// numbers with a fractional part
+    number: bool,
+    string: bool,
+    /// union of the items of every array, `Some` once an array was seen
+    items: Option<Box<Shape>>,
+    object: Option<ObjectShape>,
+}
+
+#[derive(Default)]
+struct ObjectShape {
+    /// number of objects seen
+    count: usize,
+    /// shape of every member, with the number of objects holding it
+    members: Map<String, (Shape, usize)>,
+}
+
+impl Shape {
+    fn add(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.null = true,
+            Value::Boolean(_) => self.boolean = true,
+            Value::Number(_) if Type::Integer.matches(value) => self.integer = true,
+            Value::Number(_) => self.number = true,
+            Value::String(_) => self.string = true,
+            Value::Array(values) => {
+                let items = self.items.get_or_insert_default();
+                for value in values {
+                    items.add(value);
+                }
+            }
+            Value::Object(map) => {
+                let object = self.object.get_or_insert_default();
+                object.count += 1;
+                for (key, value) in map {
+                    let (shape, count) = object.members.entry(key.clone()).or_default();
+                    shape.add(value);
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.null
+            || self.boolean
+            || self.integer
+            || self.number
+            || self.string
+            || self.items.is_some()
+            || self.object.is_some())
+    }
+
+    fn to_schema(&self) -> Value {
+        let mut schema = Map::default();
+        let string = |s: &str| Value::String(s.into());
+
+        let types: Vec<Value> = [
+            (self.null, "null"),
+            (self.boolean, "boolean"),
+            // integers are numbers too, only name them when alone
+            (self.integer && !self.number, "integer"),
+            (self.number, "number"),
+            (self.string, "string"),
+            (self.items.is_some(), "array"),
+            (self.object.is_some(), "object"),
+        ]
+        .into_iter()
+        .filter(|(seen, _)| *seen)
+        .map(|(_, name)| string(name))
+        .collect();
+        match <[Value; 1]>::try_from(types) {
+            Ok([ty]) => {
+                schema.insert(String::from("type"), ty);
+            }
+            Err(types) if !types.is_empty() => {
+                schema.insert(String::from("type"), Value::Array(types));
+            }
+            Err(_) => {}
+        }
+
+        if let Some(items) = self.items.as_ref().filter(|items| !items.is_empty()) {
+            schema.insert(String::from("items"), items.to_schema());
+        }
+        if let Some(object) = &self.object {
+            let properties = object
+                .members
+                .iter()
+                .map(|(key, (shape, _))| (key.clone(), shape.to_schema()))
+                .collect();
+            let mut required: Vec<&String> = object
+                .members
+                .iter()
+                .filter(|(_, (_, count))| *count == object.count)
+                .map(|(key, _)| key)
+                .collect();
+            required.sort();
+
+            schema.insert(String::from("properties"), Value::Object(properties));
+            if !required.is_empty() {
+                let required = required.into_iter().map(|key| string(key)).collect();
+                schema.insert(String::from("required"), Value::Array(required));
+            }
+        }
+        Value::Object(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Schema, SchemaError, infer};
+    use crate::parse;
+    use crate::prelude::*;
+
+    fn schema(json: &str) -> Schema {
+        Schema::compile(&parse(json).unwrap()).unwrap()
+    }
+
+    /// `(instance path, schema path)` of every violation
+    fn violations(schema: &Schema, instance: &str) -> Vec<(String, String)> {
+        match schema.validate(&parse(instance).unwrap()) {
+            Ok(()) => Vec::new(),
+            Err(violations) => violations
+                .into_iter()
+                .map(|v| (v.instance_path, v.schema_path))
+                .collect(),
+        }
+    }
+
+    fn paths(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn validate_objects() {
+        let schema = schema(
+            r#"{
+                "type": "object",
+                "required": ["name", "age"],
+                "properties": {
+                    "name": {"type": "string", "minLength": 1},
+                    "age": {"type": "integer", "minimum": 0}
+                },
+                "additionalProperties": false
+            }"#,
+        );
+
+        assert!(schema.is_valid(&parse(r#"{"name": "ana", "age": 30}"#).unwrap()));
+        assert_eq!(
+            violations(&schema, r#"{"name": "", "age": 1.5, "x/y": 1}"#),
+            paths(&[
+                ("/age", "/properties/age/type"),
+                ("/name", "/properties/name/minLength"),
+                ("/x~1y", "/additionalProperties/false"),
+            ])
+        );
+        assert_eq!(
+            violations(&schema, r#"{"age": -1}"#),
+            paths(&[("", "/required"), ("/age", "/properties/age/minimum")])
+        );
+        assert_eq!(violations(&schema, "[]"), paths(&[("", "/type")]));
+    }
+
+    #[test]
+    fn validate_arrays() {
+        let schema = schema(
+            r#"{
+                "prefixItems": [{"type": "string"}],
+                "items": {"type": "number", "exclusiveMaximum": 10},
+                "maxItems": 3,
+                "uniqueItems": true
+            }"#,
+        );
+
+        assert!(schema.is_valid(&parse(r#"["a", 1, 2]"#).unwrap()));
+        assert_eq!(
+            violations(&schema, r#"[1, 10, 10, 3]"#),
+            paths(&[
+                ("", "/maxItems"),
+                ("/0", "/prefixItems/0/type"),
+                ("/1", "/items/exclusiveMaximum"),
+                ("/2", "/items/exclusiveMaximum"),
+                ("", "/uniqueItems"),
+            ])
+        );
+    }
+
+    #[test]
+    fn validate_draft_07_tuples() {
+        let schema = schema(r#"{"items": [{"const": 1}], "additionalItems": false}"#);
+
+        assert!(schema.is_valid(&parse("[1]").unwrap()));
+        assert_eq!(
+            violations(&schema, "[2, 3]"),
+            paths(&[("/0", "/items/0/const"), ("/1", "/additionalItems/false")])
+        );
+    }
+
+    #[test]
+    fn validate_combinators() {
+        let schema = schema(
+            r#"{
+                "anyOf": [{"type": "string"}, {"multipleOf": 5}],
+                "oneOf": [{"enum": [5, "a"]}, {"type": "number", "minimum": 5}],
+                "not": {"const": "b"}
+            }"#,
+        );
+
+        assert!(schema.is_valid(&parse("20").unwrap()));
+        assert!(schema.is_valid(&parse(r#""a""#).unwrap()));
+        assert_eq!(violations(&schema, "5"), paths(&[("", "/oneOf")]));
+        assert_eq!(violations(&schema, "7"), paths(&[("", "/anyOf")]));
+        assert_eq!(
+            violations(&schema, r#""b""#),
+            paths(&[("", "/oneOf"), ("", "/not")])
+        );
+    }
+
+    #[test]
+    fn validate_recursive_references() {
+        let schema = schema(
+            r##"{
+                "$defs": {
+                    "node": {
+                        "type": "object",
+                        "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/node"}}}
+                    }
+                },
+                "$ref": "#/$defs/node"
+            }"##,
+        );
+
+        assert!(schema.is_valid(&parse(r#"{"children": [{"children": []}]}"#).unwrap()));
+        assert_eq!(
+            violations(&schema, r#"{"children": [{"children": [1]}]}"#),
+            paths(&[("/children/0/children/0", "/$defs/node/type")])
+        );
+    }
+
+    #[test]
+    fn compile_rejects_reference_cycles() {
+        let compile = |json: &str| Schema::compile(&parse(json).unwrap()).err();
+
+        assert_eq!(
+            compile(r##"{"$ref": "#"}"##),
+            Some(SchemaError::ReferenceCycle(String::from("#")))
+        );
+        assert!(matches!(
+            compile(
+                r##"{
+                    "$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"anyOf": [{"$ref": "#/$defs/a"}]}},
+                    "$ref": "#/$defs/a"
+                }"##
+            ),
+            Some(SchemaError::ReferenceCycle(_))
+        ));
+        assert_eq!(
+            compile(r##"{"properties": {"next": {"$ref": "#"}}}"##),
+            None
+        );
+    }
+
+    #[test]
+    fn references_are_percent_decoded() {
+        let schema = schema(r##"{"$defs": {"a b": {"type": "string"}}, "$ref": "#/$defs/a%20b"}"##);
+
+        assert!(schema.is_valid(&parse(r#""x""#).unwrap()));
+        assert!(!schema.is_valid(&parse("1").unwrap()));
+        assert_eq!(
+            Schema::compile(&parse(r##"{"$ref": "#/a%2"}"##).unwrap()).err(),
+            Some(SchemaError::UnresolvedReference(String::from("#/a%2")))
+        );
+    }
+
+    #[test]
+    fn compile_rejects_malformed_schemas() {
+        let compile = |json: &str| Schema::compile(&parse(json).unwrap()).err();
+
+        assert_eq!(
+            compile(r#"{"properties": {"a": {"type": "text"}}}"#),
+            Some(SchemaError::InvalidKeyword(String::from(
+                "/properties/a/type"
+            )))
+        );
+        assert_eq!(
+            compile(r#"{"minLength": -1}"#),
+            Some(SchemaError::InvalidKeyword(String::from("/minLength")))
+        );
+        assert_eq!(
+            compile(r#"{"$ref": "other.json"}"#),
+            Some(SchemaError::UnsupportedReference(String::from(
+                "other.json"
+            )))
+        );
+        assert_eq!(
+            compile(r##"{"$ref": "#/$defs/missing"}"##),
+            Some(SchemaError::UnresolvedReference(String::from(
+                "#/$defs/missing"
+            )))
+        );
+    }
+
+    #[test]
+    fn infer_from_samples() {
+        let samples = [
+            parse(r#"{"id": 1, "name": "a", "tags": ["x"], "score": 1.5}"#).unwrap(),
+            parse(r#"{"id": 2, "name": null, "tags": [], "score": 2}"#).unwrap(),
+            parse(r#"{"id": 3, "tags": [1, "y"], "extra": {"ok": true}}"#).unwrap(),
+        ];
+        let inferred = infer(&samples);
+
+        let expected = parse(
+            r#"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "name": {"type": ["null", "string"]},
+                    "tags": {"type": "array", "items": {"type": ["integer", "string"]}},
+                    "score": {"type": "number"},
+                    "extra": {
+                        "type": "object",
+                        "properties": {"ok": {"type": "boolean"}},
+                        "required": ["ok"]
+                    }
+                },
+                "required": ["id", "tags"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(inferred, expected);
+
+        let schema = Schema::compile(&inferred).unwrap();
+        assert!(samples.iter().all(|sample| schema.is_valid(sample)));
+    }
+
+    #[test]
+    fn infer_without_samples_accepts_anything() {
+        let inferred = infer(&[]);
+
+        assert!(
+            Schema::compile(&inferred)
+                .unwrap()
+                .is_valid(&parse("[1]").unwrap())
+        );
+    }
+}
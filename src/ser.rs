@@ -0,0 +1,753 @@
+// Writing values back out as compact JSON text
+
+use core::fmt::{self, Write};
+use core::hash::Hasher;
+#[cfg(feature = "std")]
+use std::io;
+
+use super::prelude::*;
+use super::pretty::{Pretty, PrettyOptions};
+use super::{Map, Value};
+
+/// How numbers JSON can't represent, NaN and the infinities, are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// as `null`, like JavaScript's `JSON.stringify`
+    #[default]
+    Null,
+
+    /// not at all, writing the value fails with [`WriteError::NonFinite`]
+    Error,
+
+    /// as `NaN`, `Infinity` and `-Infinity`, like Python's `json.dumps`,
+    /// for readers that accept them such as
+    /// [`Syntax::non_finite`](crate::Syntax::non_finite)
+    Literal,
+}
+
+/// How [`Value::to_string_with`] writes JSON text
+///
+/// The default is what every other writer in the crate produces:
+///
+/// ```
+/// use parser_json_rs::{NonFinite, Value, WriteOptions, json};
+///
+/// let value = json!([1.5, f64::NAN, f64::NEG_INFINITY]);
+/// assert_eq!(value.to_string_with(WriteOptions::default()).unwrap(), "[1.5,null,null]");
+/// let options = WriteOptions { non_finite: NonFinite::Literal, ..WriteOptions::default() };
+/// assert_eq!(value.to_string_with(options).unwrap(), "[1.5,NaN,-Infinity]");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub non_finite: NonFinite,
+    /// escape every character outside ASCII in strings and keys as `\uXXXX`,
+    /// with a surrogate pair for those outside the Basic Multilingual Plane,
+    /// so the output survives systems that only handle ASCII or Latin-1
+    pub ascii_only: bool,
+    /// escape `<`, `>` and `&`, so the output can't close a `<script>` tag
+    /// it is embedded in, and U+2028 and U+2029, which end string literals
+    /// in older JavaScript
+    pub html_safe: bool,
+    /// write object members ordered by key, so the output of equal values
+    /// is the same whatever map they are kept in and diffs stay small
+    pub sort_keys: bool,
+}
+
+/// Why a value couldn't be written with the [`WriteOptions`] given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WriteError {
+    /// a NaN or infinite number, under [`NonFinite::Error`]
+    NonFinite(f64),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::NonFinite(n) => write!(f, "{n} can't be written as a JSON number"),
+        }
+    }
+}
+
+impl core::error::Error for WriteError {}
+
+/// A value the writers can write out, giving the members of its objects in
+/// the order they are written when keys aren't sorted
+pub(crate) trait Tree: Sized {
+    type Members<'v>: ExactSizeIterator<Item = (&'v str, &'v Self)>
+    where
+        Self: 'v;
+
+    fn node(&self) -> Node<'_, Self>;
+}
+
+/// What a [`Tree`] holds at its root
+pub(crate) enum Node<'v, T: Tree> {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(&'v str),
+    Array(&'v [T]),
+    Object(T::Members<'v>),
+}
+
+type MapMembers<'v> = core::iter::Map<
+    <&'v Map<String, Value> as IntoIterator>::IntoIter,
+    fn((&'v String, &'v Value)) -> (&'v str, &'v Value),
+>;
+
+impl Tree for Value {
+    type Members<'v> = MapMembers<'v>;
+
+    fn node(&self) -> Node<'_, Self> {
+        match self {
+            Value::Null => Node::Null,
+            Value::Boolean(b) => Node::Boolean(*b),
+            Value::Number(n) => Node::Number(*n),
+            Value::String(s) => Node::String(s),
+            Value::Array(values) => Node::Array(values),
+            Value::Object(members) => Node::Object(members.iter().map(member as fn(_) -> _)),
+        }
+    }
+}
+
+fn member<'v>((key, value): (&'v String, &'v Value)) -> (&'v str, &'v Value) {
+    (key, value)
+}
+
+/// Write `value` as compact JSON, with no whitespace between tokens
+pub(crate) fn write_value<W: Write>(out: &mut W, value: &Value) -> fmt::Result {
+    write_with(out, value, WriteOptions::default())
+}
+
+/// [`write_value`] with `options`, which must already have been checked
+/// against the value
+fn write_with<W: Write, T: Tree>(out: &mut W, value: &T, options: WriteOptions) -> fmt::Result {
+    match value.node() {
+        Node::Null => out.write_str("null"),
+        Node::Boolean(b) => out.write_str(if b { "true" } else { "false" }),
+        Node::Number(n) => write_number(out, n, options.non_finite),
+        Node::String(s) => write_string_with(out, s, options),
+        Node::Array(values) => {
+            out.write_str("[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(",")?;
+                }
+                write_with(out, value, options)?;
+            }
+            out.write_str("]")
+        }
+        Node::Object(members) if options.sort_keys => {
+            let mut members: Vec<_> = members.collect();
+            // stable, so that repeated keys keep their order
+            members.sort_by_key(|(key, _)| *key);
+            write_members(out, members, options)
+        }
+        Node::Object(members) => write_members(out, members, options),
+    }
+}
+
+/// Write `value` in the [JSON Canonicalization Scheme](https://www.rfc-editor.org/rfc/rfc8785):
+/// compact, members sorted by the UTF-16 code units of their keys, numbers
+/// formatted as ECMAScript does and strings with only the required escapes.
+/// The scheme has no form for non-finite numbers, they are written as
+/// `non_finite` says.
+pub(crate) fn write_canonical<W: Write>(
+    out: &mut W,
+    value: &Value,
+    non_finite: NonFinite,
+) -> fmt::Result {
+    match value {
+        Value::Number(n) if n.is_finite() => out.write_str(&es6_number(*n)),
+        Value::Number(n) => write_number(out, *n, non_finite),
+        Value::Array(values) => {
+            out.write_str("[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(",")?;
+                }
+                write_canonical(out, value, non_finite)?;
+            }
+            out.write_str("]")
+        }
+        Value::Object(members) => {
+            let mut members: Vec<(&String, &Value)> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.write_str("{")?;
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.write_str(",")?;
+                }
+                write_string(out, key)?;
+                out.write_str(":")?;
+                write_canonical(out, value, non_finite)?;
+            }
+            out.write_str("}")
+        }
+        value => write_value(out, value),
+    }
+}
+
+/// `n` formatted like ECMAScript's `Number.prototype.toString`, from the
+/// shortest digits that round-trip
+fn es6_number(n: f64) -> String {
+    if n == 0.0 {
+        // covers -0 as well
+        return String::from("0");
+    }
+    let sign = if n < 0.0 { "-" } else { "" };
+
+    // `{:e}` gives the shortest round-tripping digits as `d.ddde±x`
+    let scientific = format!("{:e}", n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("`{:e}` has an exponent");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().expect("`{:e}` exponent is an integer");
+    let k = digits.len() as i32;
+    // the value is 0.digits × 10^n
+    let n = exponent + 1;
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat(-n as usize))
+    } else {
+        let exponent_sign = if n > 0 { "+" } else { "-" };
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        format!("{first}{fraction}e{exponent_sign}{}", (n - 1).abs())
+    };
+    format!("{sign}{formatted}")
+}
+
+/// Write the members of an object, in the order given
+fn write_members<'v, W: Write, T: Tree + 'v>(
+    out: &mut W,
+    members: impl IntoIterator<Item = (&'v str, &'v T)>,
+    options: WriteOptions,
+) -> fmt::Result {
+    out.write_str("{")?;
+    for (i, (key, value)) in members.into_iter().enumerate() {
+        if i > 0 {
+            out.write_str(",")?;
+        }
+        write_string_with(out, key, options)?;
+        out.write_str(":")?;
+        write_with(out, value, options)?;
+    }
+    out.write_str("}")
+}
+
+/// The first number in `value` JSON can't represent
+pub(crate) fn find_non_finite<T: Tree>(value: &T) -> Option<f64> {
+    match value.node() {
+        Node::Number(n) if !n.is_finite() => Some(n),
+        Node::Array(values) => values.iter().find_map(find_non_finite),
+        Node::Object(mut members) => members.find_map(|(_, value)| find_non_finite(value)),
+        _ => None,
+    }
+}
+
+/// Fail on the first number in `value` JSON can't represent
+fn check_finite<T: Tree>(value: &T) -> Result<(), WriteError> {
+    match find_non_finite(value) {
+        Some(n) => Err(WriteError::NonFinite(n)),
+        None => Ok(()),
+    }
+}
+
+/// `value` as compact JSON text, written as `options` say
+pub(crate) fn to_string_with<T: Tree>(
+    value: &T,
+    options: WriteOptions,
+) -> Result<String, WriteError> {
+    if options.non_finite == NonFinite::Error {
+        check_finite(value)?;
+    }
+    let mut out = String::new();
+    write_with(&mut out, value, options).expect("writing to a String never fails");
+    Ok(out)
+}
+
+impl Value {
+    /// The value as compact JSON text, written as `options` say
+    pub fn to_string_with(&self, options: WriteOptions) -> Result<String, WriteError> {
+        to_string_with(self, options)
+    }
+
+    /// Write the value as compact JSON to `writer`, a file or socket say,
+    /// as it is produced rather than after building it all in a `String`
+    ///
+    /// The writer gets many small writes, wrap it in a
+    /// [`BufWriter`](std::io::BufWriter) unless it buffers already.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        write_io(writer, |out| write_value(out, self))
+    }
+
+    /// Write the value as compact JSON to `out`, appending it to a `String`
+    /// or a `fmt::Formatter` without an intermediate allocation
+    pub fn write_fmt_to<W: Write>(&self, mut out: W) -> fmt::Result {
+        write_value(&mut out, self)
+    }
+
+    /// The value in the JSON Canonicalization Scheme (RFC 8785), giving
+    /// byte-identical output for equal values on any machine, as needed
+    /// for signing and hashing
+    ///
+    /// The scheme has no form for NaN and the infinities, a value holding
+    /// one fails with [`WriteError::NonFinite`] as under [`NonFinite::Error`].
+    pub fn to_canonical_string(&self) -> Result<String, WriteError> {
+        check_finite(self)?;
+        let mut out = String::new();
+        write_canonical(&mut out, self, NonFinite::Error).expect("writing to a String never fails");
+        Ok(out)
+    }
+
+    /// Hash of the canonical form of the value computed by `H`, so equal
+    /// values hash the same whatever their key order or the text they were
+    /// parsed from, failing like [`to_canonical_string`](Value::to_canonical_string)
+    ///
+    /// For cryptographic digests, hash the bytes of
+    /// [`to_canonical_string`](Value::to_canonical_string) instead.
+    #[cfg_attr(
+        feature = "std",
+        doc = "[`write_canonical`](Value::write_canonical) feeds them to a hasher implementing \
+               `io::Write` without building the string."
+    )]
+    pub fn digest<H: Hasher + Default>(&self) -> Result<u64, WriteError> {
+        check_finite(self)?;
+        let mut hasher = H::default();
+        write_canonical(&mut HashWriter(&mut hasher), self, NonFinite::Error)
+            .expect("hashers never fail to write");
+        Ok(hasher.finish())
+    }
+
+    /// Write the canonical form of the value to `writer`, which can be a
+    /// cryptographic hasher implementing `io::Write` such as `sha2::Sha256`
+    ///
+    /// A non-finite number fails with an [`InvalidData`](io::ErrorKind::InvalidData)
+    /// error holding [`WriteError::NonFinite`], before anything is written.
+    #[cfg(feature = "std")]
+    pub fn write_canonical<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        check_finite(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        write_io(writer, |out| write_canonical(out, self, NonFinite::Error))
+    }
+}
+
+/// Compact JSON, or with `{:#}` JSON indented as by
+/// [`Value::to_string_pretty`], for `println!` and logging
+///
+/// ```
+/// use parser_json_rs::json;
+///
+/// let value = json!({"ids": [1, 2]});
+/// assert_eq!(format!("{value}"), r#"{"ids":[1,2]}"#);
+/// assert_eq!(format!("{value:#}"), "{\n  \"ids\": [\n    1,\n    2\n  ]\n}");
+/// ```
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            Pretty::new(f, PrettyOptions::default()).write(self)
+        } else {
+            write_value(f, self)
+        }
+    }
+}
+
+/// Feeds everything written to it into a hasher
+struct HashWriter<'h, H>(&'h mut H);
+
+impl<H: Hasher> Write for HashWriter<'_, H> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Run `write` against `writer`, the serializer producing text for a
+/// `fmt::Write` while readers and files want an `io::Write`
+#[cfg(feature = "std")]
+pub(crate) fn write_io<W: io::Write>(
+    writer: W,
+    write: impl FnOnce(&mut IoWriter<W>) -> fmt::Result,
+) -> io::Result<()> {
+    let mut out = IoWriter {
+        inner: writer,
+        error: None,
+    };
+    match write(&mut out) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => Err(out
+            .error
+            .unwrap_or_else(|| io::Error::other("formatting failed"))),
+    }
+}
+
+/// `fmt::Write` adapter keeping the `io::Error` that `fmt::Error` can't carry
+#[cfg(feature = "std")]
+pub(crate) struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Largest magnitude below which every whole `f64` converts to `i64` exactly
+const WHOLE_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+/// Write `n` without going through `format!`: whole numbers as integer
+/// digits, anything else as the shortest digits that parse back to the
+/// same `f64`. JSON has no NaN or infinities, they are written as
+/// `non_finite` says.
+pub(crate) fn write_number<W: Write>(out: &mut W, n: f64, non_finite: NonFinite) -> fmt::Result {
+    if !n.is_finite() {
+        out.write_str(match non_finite {
+            NonFinite::Literal if n.is_nan() => "NaN",
+            NonFinite::Literal if n > 0.0 => "Infinity",
+            NonFinite::Literal => "-Infinity",
+            NonFinite::Null | NonFinite::Error => "null",
+        })
+    } else if n == 0.0 && n.is_sign_negative() {
+        out.write_str("-0")
+    } else if n.abs() < WHOLE_LIMIT && n as i64 as f64 == n {
+        out.write_str(itoa::Buffer::new().format(n as i64))
+    } else {
+        out.write_str(ryu::Buffer::new().format_finite(n))
+    }
+}
+
+/// Write `s` quoted, escaping quotes, backslashes and control characters so
+/// the output never spans more than one line
+pub(crate) fn write_string<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_str("\"")?;
+    write_escaped(out, s)?;
+    out.write_str("\"")
+}
+
+/// [`write_string`], also escaping the characters `options` asks for
+fn write_string_with<W: Write>(out: &mut W, s: &str, options: WriteOptions) -> fmt::Result {
+    if !(options.ascii_only || options.html_safe) {
+        return write_string(out, s);
+    }
+    let escaped = |c: char| {
+        options.ascii_only && !c.is_ascii()
+            || options.html_safe && matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}')
+    };
+
+    out.write_str("\"")?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if !escaped(c) {
+            continue;
+        }
+        write_escaped(out, &s[start..i])?;
+        for unit in c.encode_utf16(&mut [0; 2]) {
+            write!(out, "\\u{unit:04x}")?;
+        }
+        start = i + c.len_utf8();
+    }
+    write_escaped(out, &s[start..])?;
+    out.write_str("\"")
+}
+
+/// Write the contents of a string, with only the escapes JSON requires
+fn write_escaped<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    let mut start = 0;
+    for (i, byte) in s.bytes().enumerate() {
+        let escape = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0x00..=0x1f => {
+                out.write_str(&s[start..i])?;
+                write!(out, "\\u{byte:04x}")?;
+                start = i + 1;
+                continue;
+            }
+            _ => continue,
+        };
+        out.write_str(&s[start..i])?;
+        out.write_str(escape)?;
+        start = i + 1;
+    }
+    out.write_str(&s[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    #[cfg(feature = "std")]
+    use std::hash::DefaultHasher;
+
+    use super::{NonFinite, WriteError, WriteOptions, es6_number, write_value};
+    use crate::{Syntax, Value, parse, parse_with_syntax};
+
+    fn to_string(value: &Value) -> String {
+        let mut out = String::new();
+        write_value(&mut out, value).unwrap();
+        out
+    }
+
+    #[test]
+    fn write_compact() {
+        let value = parse(r#"[null, true, 1.5, -3, "a", {"k": []}]"#).unwrap();
+
+        assert_eq!(to_string(&value), r#"[null,true,1.5,-3,"a",{"k":[]}]"#);
+    }
+
+    #[test]
+    fn display_is_compact_or_pretty() {
+        let value = parse(r#"[{"a": "b\n"}, 1.5, null]"#).unwrap();
+
+        assert_eq!(value.to_string(), to_string(&value));
+        assert_eq!(format!("{value:#}"), value.to_string_pretty());
+        assert_eq!(format!("{:#}", Value::Null), "null");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_to_sinks() {
+        let value = parse(r#"{"a": [1, "b"]}"#).unwrap();
+        let mut bytes = Vec::new();
+        value.write_to(&mut bytes).unwrap();
+        let mut text = String::from("value: ");
+        value.write_fmt_to(&mut text).unwrap();
+
+        assert_eq!(bytes, to_string(&value).into_bytes());
+        assert_eq!(text, format!("value: {value}"));
+
+        struct Full;
+        impl std::io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = value.write_to(Full).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn write_escapes() {
+        let value = Value::String("a\"b\\c\nd\u{1}é".into());
+
+        assert_eq!(to_string(&value), r#""a\"b\\c\nd\u0001é""#);
+    }
+
+    #[test]
+    fn write_round_trips() {
+        let value = parse(r#"{"a": {"b": [1, 2.25, "x\ty"]}, "c": null}"#).unwrap();
+
+        assert_eq!(parse(&to_string(&value)), Ok(value));
+    }
+
+    #[test]
+    fn write_numbers_round_trip() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "-0"),
+            (-3.0, "-3"),
+            (0.1, "0.1"),
+            (1e18, "1000000000000000000"),
+            (1e21, "1e21"),
+            (1.5e-7, "1.5e-7"),
+            (0.30000000000000004, "0.30000000000000004"),
+            (f64::MAX, "1.7976931348623157e308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+        ];
+        for (n, expected) in cases {
+            let written = to_string(&Value::Number(n));
+            assert_eq!(written, expected);
+            let Ok(Value::Number(read)) = parse(&written) else {
+                panic!("{written} doesn't parse as a number");
+            };
+            assert_eq!(read.to_bits(), n.to_bits());
+        }
+    }
+
+    #[test]
+    fn write_non_finite_numbers_as_null() {
+        assert_eq!(to_string(&Value::Number(f64::NAN)), "null");
+    }
+
+    #[test]
+    fn non_finite_policy() {
+        let value = json!({"a": [f64::INFINITY, 1]});
+        let with = |non_finite| {
+            value.to_string_with(WriteOptions {
+                non_finite,
+                ..WriteOptions::default()
+            })
+        };
+
+        assert_eq!(with(NonFinite::Null), Ok(r#"{"a":[null,1]}"#.into()));
+        assert_eq!(
+            with(NonFinite::Error),
+            Err(WriteError::NonFinite(f64::INFINITY))
+        );
+        let literal = with(NonFinite::Literal).unwrap();
+        assert_eq!(literal, r#"{"a":[Infinity,1]}"#);
+        let syntax = Syntax {
+            non_finite: true,
+            ..Syntax::default()
+        };
+        assert_eq!(parse_with_syntax(&literal, syntax), Ok(value));
+        assert_eq!(
+            json!([1]).to_string_with(WriteOptions::default()),
+            Ok("[1]".into())
+        );
+    }
+
+    #[test]
+    fn ascii_only_escapes() {
+        let value = json!({"café": ["😀\n", "a~"]});
+        let options = WriteOptions {
+            ascii_only: true,
+            ..WriteOptions::default()
+        };
+
+        let written = value.to_string_with(options).unwrap();
+        assert_eq!(written, r#"{"caf\u00e9":["\ud83d\ude00\n","a~"]}"#);
+        assert!(written.is_ascii());
+        assert_eq!(parse(&written), Ok(value));
+    }
+
+    #[test]
+    fn html_safe_escapes() {
+        let value = json!({"</script>": "a && b\u{2028}"});
+        let options = WriteOptions {
+            html_safe: true,
+            ..WriteOptions::default()
+        };
+
+        let written = value.to_string_with(options).unwrap();
+        assert_eq!(
+            written,
+            r#"{"\u003c/script\u003e":"a \u0026\u0026 b\u2028"}"#
+        );
+        assert_eq!(parse(&written), Ok(value));
+    }
+
+    #[test]
+    fn sort_keys() {
+        let value = json!({"b": 1, "a": {"z": [{"y": 2, "x": 3}], "é": 4, "B": 5}});
+        let options = WriteOptions {
+            sort_keys: true,
+            ..WriteOptions::default()
+        };
+
+        assert_eq!(
+            value.to_string_with(options).unwrap(),
+            r#"{"a":{"B":5,"z":[{"x":3,"y":2}],"é":4},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn canonical_sorts_keys_by_utf16() {
+        let value =
+            parse(r#"{"b": [1, {"z": null, "a": true}], "a": "\u000f", "😀": 1, "\ufb33": 2}"#)
+                .unwrap();
+
+        assert_eq!(
+            value.to_canonical_string().unwrap(),
+            "{\"a\":\"\\u000f\",\"b\":[1,{\"a\":true,\"z\":null}],\"😀\":1,\"\u{fb33}\":2}"
+        );
+    }
+
+    #[test]
+    fn canonical_numbers() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (123456789.0, "123456789"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (1.5e-7, "1.5e-7"),
+            (0.000001, "0.000001"),
+            (333333333.3333333, "333333333.3333333"),
+            (9007199254740992.0, "9007199254740992"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(es6_number(n), expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn digest_of_equal_values() {
+        let digest = |json: &str| parse(json).unwrap().digest::<DefaultHasher>().unwrap();
+
+        assert_eq!(
+            digest(r#"{"a": 1, "b": [true, "\u0041"]}"#),
+            digest(r#"{"b":[true,"A"],"a":1.0}"#)
+        );
+        assert_ne!(digest(r#"{"a": 1}"#), digest(r#"{"a": 2}"#));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_canonical_feeds_any_writer() {
+        let mut out = Vec::new();
+        parse(r#"{"b": 1, "a": 2}"#)
+            .unwrap()
+            .write_canonical(&mut out)
+            .unwrap();
+
+        assert_eq!(out, br#"{"a":2,"b":1}"#);
+    }
+
+    #[test]
+    fn canonical_form_refuses_non_finite_numbers() {
+        let value = json!({"a": [1, f64::NAN]});
+
+        assert!(matches!(
+            value.to_canonical_string(),
+            Err(WriteError::NonFinite(n)) if n.is_nan()
+        ));
+        assert!(matches!(
+            json!(f64::NEG_INFINITY).to_canonical_string(),
+            Err(WriteError::NonFinite(f64::NEG_INFINITY))
+        ));
+        #[cfg(feature = "std")]
+        {
+            assert!(value.digest::<DefaultHasher>().is_err());
+            let mut out = Vec::new();
+            let err = value.write_canonical(&mut out).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            assert!(out.is_empty());
+        }
+    }
+
+    #[cfg(feature = "sorted-keys")]
+    #[test]
+    fn sorted_keys_write_members_in_key_order() {
+        let value = parse(r#"{"b": 1, "a": {"d": null, "c": true}}"#).unwrap();
+
+        assert_eq!(to_string(&value), r#"{"a":{"c":true,"d":null},"b":1}"#);
+    }
+}
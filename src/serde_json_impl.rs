@@ -0,0 +1,85 @@
+// Conversions to and from `serde_json::Value`, for passing documents between
+// the two crates
+
+use super::Value;
+use super::string::{from_string, into_string};
+
+/// Numbers become `f64`, so integers beyond 2^53 are rounded
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(b),
+            serde_json::Value::Number(n) => {
+                Value::Number(n.as_f64().expect("serde_json numbers fit an f64"))
+            }
+            serde_json::Value::String(s) => Value::String(from_string(s)),
+            serde_json::Value::Array(values) => {
+                Value::Array(values.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(members) => Value::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key, Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Whole numbers become integers, and non-finite numbers, which
+/// `serde_json` can't represent, become null as when serializing
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Boolean(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => {
+                let number = if let Some(i) = Value::Number(n).as_i64() {
+                    Some(i.into())
+                } else if let Some(u) = Value::Number(n).as_u64() {
+                    Some(u.into())
+                } else {
+                    serde_json::Number::from_f64(n)
+                };
+                number.map_or(serde_json::Value::Null, serde_json::Value::Number)
+            }
+            Value::String(s) => serde_json::Value::String(into_string(s)),
+            Value::Array(values) => {
+                serde_json::Value::Array(values.into_iter().map(Into::into).collect())
+            }
+            Value::Object(members) => serde_json::Value::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Value;
+
+    #[test]
+    fn round_trips_through_serde_json() {
+        let value = json!({"a": [1, -2, 0.5, 1e300, null, true], "s": "x", "o": {}});
+        let other = serde_json::Value::from(value.clone());
+
+        assert_eq!(
+            other,
+            serde_json::json!({"a": [1, -2, 0.5, 1e300, null, true], "s": "x", "o": {}})
+        );
+        assert!(other["a"][0].is_i64());
+        assert_eq!(Value::from(other), value);
+    }
+
+    #[test]
+    fn non_finite_numbers_become_null() {
+        assert_eq!(
+            serde_json::Value::from(Value::Number(f64::NAN)),
+            serde_json::Value::Null
+        );
+    }
+}
@@ -0,0 +1,488 @@
+// A DOM whose every node remembers where it was in the source text
+
+use core::mem;
+use core::ops::Range;
+
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+use super::ser::{self, Node, Tree};
+use super::tokenize::{SliceSource, Token, TokenStream};
+use super::{
+    DEFAULT_MAX_DEPTH, JsonError, Map, PrettyOptions, Syntax, Value, WriteError, WriteOptions,
+    pretty,
+};
+
+type Tokens<'a> = TokenStream<'a, SliceSource<'a>>;
+
+/// A value and the byte range of its source text, see [`parse_spanned`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedValue {
+    pub span: Range<usize>,
+    pub node: SpannedNode,
+    /// spans of the comments on the lines before the value, or before the
+    /// key of an object member, see [`parse_spanned_with_comments`]
+    pub leading_comments: Vec<Range<usize>>,
+    /// spans of the comments after the value on the line it ends, and of
+    /// those before the closing bracket of its array or object following no
+    /// other value
+    pub trailing_comments: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedNode {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<SpannedValue>),
+    /// the members in document order, repeated keys included
+    Object(Vec<SpannedMember>),
+}
+
+/// A member of an object, with the byte range of its quoted key
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedMember {
+    pub key: String,
+    pub key_span: Range<usize>,
+    pub value: SpannedValue,
+}
+
+impl SpannedValue {
+    fn new(span: Range<usize>, node: SpannedNode) -> Self {
+        SpannedValue {
+            span,
+            node,
+            leading_comments: Vec::new(),
+            trailing_comments: Vec::new(),
+        }
+    }
+
+    /// The member `key` of an object, the last one if the key is repeated as
+    /// when parsing into a [`Value`]
+    pub fn get(&self, key: &str) -> Option<&SpannedValue> {
+        match &self.node {
+            SpannedNode::Object(members) => members
+                .iter()
+                .rev()
+                .find(|member| member.key == key)
+                .map(|member| &member.value),
+            _ => None,
+        }
+    }
+
+    /// The element at `index` of an array
+    pub fn get_index(&self, index: usize) -> Option<&SpannedValue> {
+        match &self.node {
+            SpannedNode::Array(values) => values.get(index),
+            _ => None,
+        }
+    }
+
+    /// The value as compact JSON text written as `options` say, with the
+    /// members of objects in document order, repeated keys included
+    pub fn to_string_with(&self, options: WriteOptions) -> Result<String, WriteError> {
+        ser::to_string_with(self, options)
+    }
+
+    /// The value as JSON text indented for people to read, laid out as
+    /// `options` say, with the members of objects in document order unless
+    /// they are sorted
+    ///
+    /// ```
+    /// use parser_json_rs::{PrettyOptions, parse_spanned};
+    ///
+    /// let doc = parse_spanned(r#"{"b": 1, "a": [true]}"#).unwrap();
+    /// let options = PrettyOptions { width: Some(80), ..PrettyOptions::default() };
+    /// assert_eq!(doc.to_string_pretty_with(options), r#"{"b": 1, "a": [true]}"#);
+    /// ```
+    pub fn to_string_pretty_with(&self, options: PrettyOptions) -> String {
+        pretty::to_string_pretty_with(self, options)
+    }
+
+    /// The value without its spans
+    pub fn to_value(&self) -> Value {
+        match &self.node {
+            SpannedNode::Null => Value::Null,
+            SpannedNode::Boolean(b) => Value::Boolean(*b),
+            SpannedNode::Number(n) => Value::Number(*n),
+            SpannedNode::String(s) => Value::String(s.as_str().into()),
+            SpannedNode::Array(values) => {
+                Value::Array(values.iter().map(SpannedValue::to_value).collect())
+            }
+            SpannedNode::Object(members) => {
+                let mut map = Map::default();
+                for member in members {
+                    map.insert(member.key.clone(), member.value.to_value());
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+impl Tree for SpannedValue {
+    type Members<'v> = core::iter::Map<
+        core::slice::Iter<'v, SpannedMember>,
+        fn(&'v SpannedMember) -> (&'v str, &'v SpannedValue),
+    >;
+
+    fn node(&self) -> Node<'_, Self> {
+        match &self.node {
+            SpannedNode::Null => Node::Null,
+            SpannedNode::Boolean(b) => Node::Boolean(*b),
+            SpannedNode::Number(n) => Node::Number(*n),
+            SpannedNode::String(s) => Node::String(s),
+            SpannedNode::Array(values) => Node::Array(values),
+            SpannedNode::Object(members) => Node::Object(members.iter().map(member as fn(_) -> _)),
+        }
+    }
+}
+
+fn member(member: &SpannedMember) -> (&str, &SpannedValue) {
+    (&member.key, &member.value)
+}
+
+/// A container whose members are still being parsed, with the offset it
+/// starts at
+enum Open {
+    Array(usize, Vec<SpannedValue>),
+    /// the members so far and the key of the member being parsed
+    Object(usize, Vec<SpannedMember>, Option<(String, Range<usize>)>),
+}
+
+/// Parse a JSON document from text, keeping the span of every value and
+/// key, as needed to point at the source of a value found to be wrong:
+///
+/// ```
+/// use parser_json_rs::{LineCol, LineIndex, parse_spanned};
+///
+/// let input = "{\n  \"port\": \"80\"\n}";
+/// let port = parse_spanned(input).unwrap().get("port").unwrap().span.clone();
+///
+/// assert_eq!(&input[port.clone()], "\"80\"");
+/// assert_eq!(
+///     LineIndex::new(input).line_col(port.start),
+///     Some(LineCol { line: 2, column: 11 })
+/// );
+/// ```
+pub fn parse_spanned(input: &str) -> Result<SpannedValue, JsonError> {
+    parse_tree(&mut Tokens::new(SliceSource::new(input.as_bytes())))
+}
+
+/// Parse a document in JSON extended with what `syntax` enables, keeping
+/// the span of every value and key and attaching each comment to the value
+/// nearest to it
+///
+/// A comment goes with the value it follows on the same line, and otherwise
+/// with the value, or object member, after it. Tools editing configuration
+/// files use this to keep the documentation next to the setting it is about:
+///
+/// ```
+/// use parser_json_rs::{Syntax, parse_spanned_with_comments};
+///
+/// let input = "{
+///     // seconds
+///     \"timeout\": 30, // per request
+///     \"retries\": 2
+/// }";
+/// let doc = parse_spanned_with_comments(input, Syntax::jsonc()).unwrap();
+/// let timeout = doc.get("timeout").unwrap();
+/// assert_eq!(&input[timeout.leading_comments[0].clone()], "// seconds");
+/// assert_eq!(&input[timeout.trailing_comments[0].clone()], "// per request");
+/// assert!(doc.get("retries").unwrap().leading_comments.is_empty());
+/// ```
+pub fn parse_spanned_with_comments(input: &str, syntax: Syntax) -> Result<SpannedValue, JsonError> {
+    let mut tokens = Tokens::with_syntax(SliceSource::new(input.as_bytes()), syntax);
+    tokens.capture_comments();
+    let mut root = parse_tree(&mut tokens)?;
+
+    let comments = tokens.take_comments();
+    let (before, rest) = comments.split_at(comments.partition_point(|c| c.start < root.span.start));
+    let (inside, after) = rest.split_at(rest.partition_point(|c| c.start < root.span.end));
+    root.leading_comments.extend_from_slice(before);
+    attach(&mut root, inside, input);
+    root.trailing_comments.extend_from_slice(after);
+    Ok(root)
+}
+
+/// Attach `comments`, sorted and all within the array or object `value`, to
+/// its elements or members, or to `value` itself when it has none
+fn attach(value: &mut SpannedValue, comments: &[Range<usize>], input: &str) {
+    if comments.is_empty() {
+        return;
+    }
+    // each element or member with the offset it starts at
+    let mut children: Vec<(usize, &mut SpannedValue)> = match &mut value.node {
+        SpannedNode::Array(values) => values.iter_mut().map(|v| (v.span.start, v)).collect(),
+        SpannedNode::Object(members) => members
+            .iter_mut()
+            .map(|member| (member.key_span.start, &mut member.value))
+            .collect(),
+        _ => return,
+    };
+
+    // comments inside children come first, keeping every list in input order
+    for (_, child) in &mut children {
+        let start = comments.partition_point(|c| c.start < child.span.start);
+        let end = comments.partition_point(|c| c.start < child.span.end);
+        attach(child, &comments[start..end], input);
+    }
+
+    // the first child not ending before the comment
+    let mut i = 0;
+    for comment in comments {
+        while i < children.len() && children[i].1.span.end <= comment.start {
+            i += 1;
+        }
+        match children.get_mut(i) {
+            // inside the value, already attached
+            Some((_, child)) if child.span.start <= comment.start => continue,
+            // between a key and its value
+            Some((start, child)) if *start <= comment.start => {
+                child.leading_comments.push(comment.clone());
+                continue;
+            }
+            _ => {}
+        }
+
+        let same_line = |prev: &SpannedValue| !input[prev.span.end..comment.start].contains('\n');
+        let target = match (i.checked_sub(1), children.len() > i) {
+            (Some(prev), _) if same_line(children[prev].1) => {
+                &mut children[prev].1.trailing_comments
+            }
+            (_, true) => &mut children[i].1.leading_comments,
+            (Some(prev), false) => &mut children[prev].1.trailing_comments,
+            (None, false) => &mut value.trailing_comments,
+        };
+        target.push(comment.clone());
+    }
+}
+
+/// Parse the tokens of a whole document into a tree
+fn parse_tree(tokens: &mut Tokens<'_>) -> Result<SpannedValue, JsonError> {
+    let mut stack: Vec<Open> = Vec::new();
+    // a token read to see whether a container is empty, and not used yet
+    let mut pending = match tokens.next() {
+        Some(token) => Some((token?, tokens.span())),
+        None => return Err(TokenParseError::EmptyInput.into()),
+    };
+
+    loop {
+        let (token, span) = match pending.take() {
+            Some(pending) => pending,
+            None => (next_token(tokens)?, tokens.span()),
+        };
+        let node = match token {
+            Token::Null => SpannedNode::Null,
+            Token::False => SpannedNode::Boolean(false),
+            Token::True => SpannedNode::Boolean(true),
+            Token::Number(n) => SpannedNode::Number(n),
+            Token::String(raw) => SpannedNode::String(unescape(raw)?.into_owned()),
+            Token::LeftSquareBracket | Token::LeftCurlyBracket
+                if stack.len() == DEFAULT_MAX_DEPTH =>
+            {
+                return Err(TokenParseError::DepthLimitExceeded.into());
+            }
+            Token::LeftSquareBracket => match next_token(tokens)? {
+                Token::RightSquareBracket => SpannedNode::Array(Vec::new()),
+                token => {
+                    pending = Some((token, tokens.span()));
+                    stack.push(Open::Array(span.start, Vec::new()));
+                    continue;
+                }
+            },
+            Token::LeftCurlyBracket => match next_token(tokens)? {
+                Token::RightCurlyBracket => SpannedNode::Object(Vec::new()),
+                token => {
+                    let key = parse_key(tokens, token)?;
+                    stack.push(Open::Object(span.start, Vec::new(), Some(key)));
+                    continue;
+                }
+            },
+            _ => return Err(TokenParseError::ExpectedValue.into()),
+        };
+        let mut value = SpannedValue::new(span.start..tokens.span().end, node);
+
+        // the value is complete, add it to its container and close every
+        // container that ends right after it
+        loop {
+            let (start, node) = match stack.last_mut() {
+                None => {
+                    return match tokens.next() {
+                        None => Ok(value),
+                        Some(_) => Err(TokenParseError::TrailingTokens.into()),
+                    };
+                }
+                Some(Open::Array(start, values)) => {
+                    values.push(value);
+                    match next_token(tokens)? {
+                        Token::Comma => break,
+                        Token::RightSquareBracket => {
+                            (*start, SpannedNode::Array(mem::take(values)))
+                        }
+                        _ => return Err(TokenParseError::ExpectedComma.into()),
+                    }
+                }
+                Some(Open::Object(start, members, next_key)) => {
+                    let (key, key_span) =
+                        next_key.take().expect("a key is read before every value");
+                    members.push(SpannedMember {
+                        key,
+                        key_span,
+                        value,
+                    });
+                    match next_token(tokens)? {
+                        Token::Comma => {
+                            let token = next_token(tokens)?;
+                            *next_key = Some(parse_key(tokens, token)?);
+                            break;
+                        }
+                        Token::RightCurlyBracket => {
+                            (*start, SpannedNode::Object(mem::take(members)))
+                        }
+                        _ => return Err(TokenParseError::ExpectedComma.into()),
+                    }
+                }
+            };
+            stack.pop();
+            value = SpannedValue::new(start..tokens.span().end, node);
+        }
+    }
+}
+
+fn next_token<'a>(tokens: &mut Tokens<'a>) -> Result<Token<'a>, JsonError> {
+    Ok(tokens.next().ok_or(TokenParseError::UnexpectedEnd)??)
+}
+
+/// Parse the `"key":` of an object member, `token` being the key
+fn parse_key(
+    tokens: &mut Tokens<'_>,
+    token: Token<'_>,
+) -> Result<(String, Range<usize>), JsonError> {
+    let Token::String(raw) = token else {
+        return Err(TokenParseError::ExpectedProperty.into());
+    };
+    let span = tokens.span();
+    if next_token(tokens)? != Token::Colon {
+        return Err(TokenParseError::ExpectedColon.into());
+    }
+    Ok((unescape(raw)?.into_owned(), span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpannedNode, parse_spanned, parse_spanned_with_comments};
+    use crate::prelude::*;
+    use crate::{PrettyOptions, Syntax, WriteOptions, parse};
+
+    #[test]
+    fn every_node_has_its_span() {
+        let input = r#" {"a": [1, {"b": "x\"y"}], "c": {}, "a": null} "#;
+        let doc = parse_spanned(input).unwrap();
+        let text = |span: &core::ops::Range<usize>| &input[span.clone()];
+
+        assert_eq!(text(&doc.span), input.trim());
+        let SpannedNode::Object(members) = &doc.node else {
+            panic!("expected an object");
+        };
+        let keys: Vec<_> = members
+            .iter()
+            .map(|member| text(&member.key_span))
+            .collect();
+        assert_eq!(keys, [r#""a""#, r#""c""#, r#""a""#]);
+        let first = &members[0].value;
+        assert_eq!(text(&first.span), r#"[1, {"b": "x\"y"}]"#);
+        assert_eq!(text(&first.get_index(0).unwrap().span), "1");
+        let b = first.get_index(1).unwrap().get("b").unwrap();
+        assert_eq!(text(&b.span), r#""x\"y""#);
+        assert_eq!(b.node, SpannedNode::String(String::from("x\"y")));
+        assert_eq!(text(&doc.get("c").unwrap().span), "{}");
+        assert_eq!(doc.get("a").unwrap().node, SpannedNode::Null);
+    }
+
+    #[test]
+    fn parse_spanned_agrees_with_parse() {
+        let inputs = [
+            r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e", "f": [], "g": {}}"#,
+            "[[]]",
+            "",
+            "[1, 2",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            "{1: 2}",
+            "[1}",
+            "[1] 2",
+            "]",
+        ];
+
+        for input in inputs {
+            assert_eq!(
+                parse_spanned(input).map(|doc| doc.to_value()),
+                parse(input),
+                "{input}"
+            );
+        }
+        assert!(parse_spanned(&"[".repeat(1000)).is_err());
+    }
+
+    #[test]
+    fn comments_attach_to_the_nearest_value() {
+        let input = "// top
+{
+  /* a */ \"a\": /* a value */ [1, // one
+    2 /* two */
+    // dangling
+  ],
+  \"b\": {
+    // inside
+  }, // b
+  \"c\": 3
+} // end";
+        let doc = parse_spanned_with_comments(input, Syntax::jsonc()).unwrap();
+        let texts = |spans: &[core::ops::Range<usize>]| -> Vec<&str> {
+            spans.iter().map(|span| &input[span.clone()]).collect()
+        };
+
+        assert_eq!(texts(&doc.leading_comments), ["// top"]);
+        assert_eq!(texts(&doc.trailing_comments), ["// end"]);
+        let a = doc.get("a").unwrap();
+        assert_eq!(texts(&a.leading_comments), ["/* a */", "/* a value */"]);
+        assert_eq!(
+            texts(&a.get_index(0).unwrap().trailing_comments),
+            ["// one"]
+        );
+        assert_eq!(
+            texts(&a.get_index(1).unwrap().trailing_comments),
+            ["/* two */", "// dangling"]
+        );
+        let b = doc.get("b").unwrap();
+        assert_eq!(texts(&b.trailing_comments), ["// inside", "// b"]);
+        assert!(doc.get("c").unwrap().leading_comments.is_empty());
+        assert_eq!(doc.to_value(), crate::parse_jsonc(input).unwrap());
+        assert!(parse_spanned(input).is_err());
+    }
+
+    #[test]
+    fn written_out_in_document_order() {
+        let doc = parse_spanned(r#"{"b": 1, "a": {"d": [], "c": 2}, "b": 3}"#).unwrap();
+
+        assert_eq!(
+            doc.to_string_with(WriteOptions::default()).unwrap(),
+            r#"{"b":1,"a":{"d":[],"c":2},"b":3}"#
+        );
+        let sorted = WriteOptions {
+            sort_keys: true,
+            ..WriteOptions::default()
+        };
+        assert_eq!(
+            doc.to_string_with(sorted).unwrap(),
+            r#"{"a":{"c":2,"d":[]},"b":1,"b":3}"#
+        );
+        assert_eq!(
+            doc.get("a")
+                .unwrap()
+                .to_string_pretty_with(PrettyOptions::default()),
+            "{\n  \"d\": [],\n  \"c\": 2\n}"
+        );
+    }
+}
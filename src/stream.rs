@@ -0,0 +1,704 @@
+//! Pull parsing: walking a document as a flat sequence of [`JsonEvent`]s
+//! instead of building a [`Value`](crate::Value) tree, so huge documents can
+//! be processed with memory bounded by their nesting depth, its
+//! counterpart for output, the [`JsonWriter`], and [`minify`], which copies
+//! a document between the two without ever holding it whole
+
+#[cfg(feature = "std")]
+use core::{fmt, mem};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use super::JsonError;
+use super::parser::{TokenParseError, unescape};
+use super::prelude::*;
+#[cfg(feature = "std")]
+use super::reader::{BUFFER_SIZE, ReaderLexer};
+use super::tokenize::{Lexer, Token};
+#[cfg(feature = "std")]
+use super::{
+    Syntax, ToJson,
+    ser::{write_io, write_string, write_value},
+};
+
+/// One step through a JSON document
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    /// `{`
+    StartObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    StartArray,
+    /// `]`
+    EndArray,
+    /// unescaped key of the next object member
+    Key(String),
+    Null,
+    Boolean(bool),
+    Number(f64),
+    /// unescaped string value
+    String(String),
+}
+
+/// What the next token is allowed to be
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+/// Iterator over the events of one JSON value, checking the structure of the
+/// tokens as it goes and stopping after the first error
+#[derive(Clone)]
+pub struct Events<I> {
+    tokens: I,
+    started: bool,
+    stack: Vec<Container>,
+    expect: Expect,
+}
+
+impl<'a, I, E> Events<I>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    /// Events over any token source, such as a [`Lexer`]
+    pub fn new(tokens: I) -> Self {
+        Events {
+            tokens,
+            started: false,
+            stack: Vec::new(),
+            expect: Expect::Value,
+        }
+    }
+
+    /// The token source, for reading its position between events
+    pub(crate) fn tokens(&self) -> &I {
+        &self.tokens
+    }
+
+    /// Check that only whitespace is left after the value, once all its
+    /// events have been taken
+    #[cfg(feature = "std")]
+    pub(crate) fn finish(mut self) -> Result<(), JsonError> {
+        match self
+            .tokens
+            .next()
+            .map(|token| token.map_err(JsonError::from))
+        {
+            None => Ok(()),
+            // failing to read past the value is not about what the input holds
+            Some(Err(err @ JsonError::Io(_))) => Err(err),
+            Some(_) => Err(TokenParseError::TrailingTokens.into()),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        loop {
+            if self.is_done() {
+                return Ok(None);
+            }
+            let Some(token) = self.tokens.next() else {
+                return Err(self.unfinished().into());
+            };
+            if let Some(event) = self.push(token?)? {
+                return Ok(Some(event));
+            }
+        }
+    }
+
+    /// Take the next token, returning the event it makes if any, for tokens
+    /// handed over as they arrive rather than pulled from `tokens`
+    pub(crate) fn push(&mut self, token: Token<'a>) -> Result<Option<JsonEvent>, TokenParseError> {
+        self.started = true;
+        match self.expect {
+            Expect::Value | Expect::ValueOrEnd => {
+                if self.expect == Expect::ValueOrEnd && token == Token::RightSquareBracket {
+                    return Ok(Some(self.close()));
+                }
+                return Ok(Some(self.value(token)?));
+            }
+            Expect::Key | Expect::KeyOrEnd => match token {
+                Token::String(key) => {
+                    self.expect = Expect::Colon;
+                    return Ok(Some(JsonEvent::Key(unescape(key)?.into_owned())));
+                }
+                Token::RightCurlyBracket if self.expect == Expect::KeyOrEnd => {
+                    return Ok(Some(self.close()));
+                }
+                _ => return Err(TokenParseError::ExpectedProperty),
+            },
+            Expect::Colon => match token {
+                Token::Colon => self.expect = Expect::Value,
+                _ => return Err(TokenParseError::ExpectedColon),
+            },
+            Expect::CommaOrEnd => match (token, self.stack.last()) {
+                (Token::Comma, Some(Container::Array)) => self.expect = Expect::Value,
+                (Token::Comma, Some(Container::Object)) => self.expect = Expect::Key,
+                (Token::RightSquareBracket, Some(Container::Array))
+                | (Token::RightCurlyBracket, Some(Container::Object)) => {
+                    return Ok(Some(self.close()));
+                }
+                _ => return Err(TokenParseError::ExpectedComma),
+            },
+            Expect::Done => return Err(TokenParseError::TrailingTokens),
+        }
+        Ok(None)
+    }
+
+    /// Whether the value is complete
+    pub(crate) fn is_done(&self) -> bool {
+        self.expect == Expect::Done
+    }
+
+    /// The error for the tokens running out before the value is complete
+    pub(crate) fn unfinished(&self) -> TokenParseError {
+        if self.started {
+            TokenParseError::UnexpectedEnd
+        } else {
+            TokenParseError::EmptyInput
+        }
+    }
+
+    fn value(&mut self, token: Token<'a>) -> Result<JsonEvent, TokenParseError> {
+        let event = match token {
+            Token::Null => JsonEvent::Null,
+            Token::False => JsonEvent::Boolean(false),
+            Token::True => JsonEvent::Boolean(true),
+            Token::Number(number) => JsonEvent::Number(number),
+            Token::String(string) => JsonEvent::String(unescape(string)?.into_owned()),
+            Token::LeftSquareBracket => {
+                self.stack.push(Container::Array);
+                self.expect = Expect::ValueOrEnd;
+                return Ok(JsonEvent::StartArray);
+            }
+            Token::LeftCurlyBracket => {
+                self.stack.push(Container::Object);
+                self.expect = Expect::KeyOrEnd;
+                return Ok(JsonEvent::StartObject);
+            }
+            _ => return Err(TokenParseError::ExpectedValue),
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    fn close(&mut self) -> JsonEvent {
+        let event = match self.stack.pop() {
+            Some(Container::Array) => JsonEvent::EndArray,
+            Some(Container::Object) => JsonEvent::EndObject,
+            None => unreachable!("only reached while inside a container"),
+        };
+        self.after_value();
+        event
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::Done
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+}
+
+impl<'a, I, E> Iterator for Events<I>
+where
+    I: Iterator<Item = Result<Token<'a>, E>>,
+    JsonError: From<E>,
+{
+    type Item = Result<JsonEvent, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(err) => {
+                // stop after the first error
+                self.expect = Expect::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Events of the JSON document in `input`
+pub fn events(input: &str) -> Events<Lexer<'_>> {
+    Events::new(Lexer::new(input))
+}
+
+/// Events of the JSON document read from `reader`, which is consumed in small
+/// buffered chunks as the events are pulled
+#[cfg(feature = "std")]
+pub fn events_from_reader<R: Read>(reader: R) -> Events<ReaderLexer<R>> {
+    Events::new(ReaderLexer::new(reader))
+}
+
+/// Copy the JSON document read from `reader` to `writer` without the
+/// whitespace between its tokens, checking its structure on the way
+///
+/// The document is read and written in small chunks, so memory use depends
+/// only on how deeply it nests, whatever its size. Strings and numbers are
+/// copied byte for byte, escapes and all.
+///
+/// ```
+/// use parser_json_rs::stream::minify;
+///
+/// let mut out = Vec::new();
+/// minify("{\n  \"a b\": [1, 2.50]\n}\n".as_bytes(), &mut out)?;
+/// assert_eq!(out, br#"{"a b":[1,2.50]}"#);
+/// # Ok::<(), parser_json_rs::JsonError>(())
+/// ```
+///
+/// When the document turns out to be invalid, whatever was copied before the
+/// error was found is left in `writer`.
+#[cfg(feature = "std")]
+pub fn minify<R: Read, W: Write>(reader: R, writer: W) -> Result<(), JsonError> {
+    minify_with_syntax(reader, writer, Syntax::default())
+}
+
+/// [`minify`] a document written with the extensions to JSON in `syntax`,
+/// dropping its comments along with the whitespace
+///
+/// The other extensions are kept as written, so the output is only strict
+/// JSON when the input doesn't use them.
+#[cfg(feature = "std")]
+pub fn minify_with_syntax<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    syntax: Syntax,
+) -> Result<(), JsonError> {
+    let mut events = Events::new(ReaderLexer::with_syntax(
+        Minifier::new(reader, writer, syntax),
+        syntax,
+    ));
+    for event in &mut events {
+        event?;
+    }
+    // read to the end, so everything after the document is copied or refused
+    events.finish()
+}
+
+/// Where a [`Minifier`] is in the document
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MinifyState {
+    Between,
+    /// inside a string opened by the quote
+    String(u8),
+    /// after a backslash inside a string opened by the quote
+    Escape(u8),
+    /// after a `/` that starts a comment
+    Slash,
+    LineComment,
+    BlockComment,
+    /// after a `*` inside a block comment
+    BlockStar,
+}
+
+/// Reader handing the document on to the lexer untouched while copying it
+/// to a writer without its whitespace and comments
+#[cfg(feature = "std")]
+struct Minifier<R, W> {
+    reader: R,
+    writer: W,
+    syntax: Syntax,
+    state: MinifyState,
+    /// how many bytes of a leading byte order mark were read and held back,
+    /// `None` once the input is past where one could be
+    bom: Option<usize>,
+    /// the bytes of the last read to keep, reused from read to read
+    kept: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, W: Write> Minifier<R, W> {
+    fn new(reader: R, writer: W, syntax: Syntax) -> Self {
+        Minifier {
+            reader,
+            writer,
+            syntax,
+            state: MinifyState::Between,
+            bom: (!syntax.reject_bom).then_some(0),
+            kept: Vec::with_capacity(BUFFER_SIZE),
+        }
+    }
+
+    /// Add `byte` to `kept` if it belongs in the output, moving past it
+    fn minify(&mut self, byte: u8, kept: &mut Vec<u8>) {
+        const BOM: &[u8] = "\u{feff}".as_bytes();
+
+        // a leading byte order mark is skipped by the lexer too, but bytes
+        // that only start like one are the start of the document
+        if let Some(matched) = self.bom {
+            if byte == BOM[matched] {
+                self.bom = (matched + 1 < BOM.len()).then_some(matched + 1);
+                return;
+            }
+            kept.extend_from_slice(&BOM[..matched]);
+            self.bom = None;
+        }
+        if self.keep(byte) {
+            kept.push(byte);
+        }
+    }
+
+    /// Whether `byte` belongs in the output, moving past it
+    fn keep(&mut self, byte: u8) -> bool {
+        use MinifyState::*;
+
+        let (next, keep) = match (self.state, byte) {
+            (String(quote), b'\\') => (Escape(quote), true),
+            (String(quote), byte) if byte == quote => (Between, true),
+            (String(_), _) => (self.state, true),
+            (Escape(quote), _) => (String(quote), true),
+            (Slash, b'/') => (LineComment, false),
+            (Slash, b'*') => (BlockComment, false),
+            // not a comment after all, which the lexer refuses
+            (Slash, _) => (Between, true),
+            (LineComment, b'\n') => (Between, false),
+            (LineComment, _) => (LineComment, false),
+            (BlockComment | BlockStar, b'*') => (BlockStar, false),
+            (BlockStar, b'/') => (Between, false),
+            (BlockComment | BlockStar, _) => (BlockComment, false),
+            (Between, b'"') => (String(byte), true),
+            (Between, b'\'') if self.syntax.single_quotes => (String(byte), true),
+            (Between, b'/') if self.syntax.comments => (Slash, false),
+            (Between, byte) => (Between, !byte.is_ascii_whitespace()),
+        };
+        self.state = next;
+        keep
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, W: Write> Read for Minifier<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        let mut kept = mem::take(&mut self.kept);
+        kept.clear();
+        for &byte in &buf[..len] {
+            self.minify(byte, &mut kept);
+        }
+        self.writer.write_all(&kept)?;
+        self.kept = kept;
+        Ok(len)
+    }
+}
+
+/// Writes a JSON document piece by piece, checking that the pieces come in
+/// an order that makes valid JSON, so output of any size can be produced
+/// without building a [`Value`](crate::Value) first
+///
+/// ```
+/// use parser_json_rs::stream::JsonWriter;
+///
+/// let mut writer = JsonWriter::new(Vec::new());
+/// writer.begin_object()?;
+/// writer.key("rows")?;
+/// writer.begin_array()?;
+/// for i in 0..3 {
+///     writer.value(i)?;
+/// }
+/// writer.end()?;
+/// writer.key("done")?;
+/// writer.value(true)?;
+/// writer.end()?;
+/// assert_eq!(writer.finish()?, br#"{"rows":[0,1,2],"done":true}"#);
+/// # Ok::<(), parser_json_rs::stream::WriterError>(())
+/// ```
+///
+/// The inner writer gets many small writes, wrap it in a
+/// [`BufWriter`](std::io::BufWriter) unless it buffers already.
+#[cfg(feature = "std")]
+pub struct JsonWriter<W: Write> {
+    inner: W,
+    /// the open containers, innermost last
+    stack: Vec<Container>,
+    /// whether the innermost container, or the document when none is open,
+    /// has anything in it yet
+    started: bool,
+    /// whether an object member has its key written but not its value
+    after_key: bool,
+}
+
+/// Why a [`JsonWriter`] call failed
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum WriterError {
+    /// the inner writer failed
+    Io(io::Error),
+    /// a key outside an object, or right after another key
+    UnexpectedKey,
+    /// a value or the end of an object where a key belongs
+    ExpectedKey,
+    /// the end of an object right after a key
+    ExpectedValue,
+    /// an end with no array or object open
+    NothingToEnd,
+    /// a second value after the document's one
+    DocumentComplete,
+    /// finishing with arrays or objects open, or before any value
+    Unfinished,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for WriterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriterError::Io(err) => write!(f, "writing failed: {err}"),
+            WriterError::UnexpectedKey => f.write_str("a key can only start an object member"),
+            WriterError::ExpectedKey => f.write_str("an object member must start with a key"),
+            WriterError::ExpectedValue => f.write_str("an object key must have a value"),
+            WriterError::NothingToEnd => f.write_str("no array or object is open"),
+            WriterError::DocumentComplete => f.write_str("the document already has its value"),
+            WriterError::Unfinished => f.write_str("the document is incomplete"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WriterError {}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for WriterError {
+    fn from(err: io::Error) -> Self {
+        WriterError::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> JsonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        JsonWriter {
+            inner,
+            stack: Vec::new(),
+            started: false,
+            after_key: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.begin(Container::Object, b"{")
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.begin(Container::Array, b"[")
+    }
+
+    /// Write the key of the next member of the innermost object
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        if self.stack.last() != Some(&Container::Object) || self.after_key {
+            return Err(WriterError::UnexpectedKey);
+        }
+        if self.started {
+            self.inner.write_all(b",")?;
+        }
+        write_io(&mut self.inner, |out| write_string(out, key))?;
+        self.inner.write_all(b":")?;
+        self.after_key = true;
+        Ok(())
+    }
+
+    /// Write a whole value, the next element of the innermost array, the
+    /// value of the member whose key was just written, or the document
+    pub fn value(&mut self, value: impl ToJson) -> Result<(), WriterError> {
+        self.before_value()?;
+        write_io(&mut self.inner, |out| write_value(out, &value.to_json()))?;
+        self.started = true;
+        Ok(())
+    }
+
+    /// Close the innermost array or object
+    pub fn end(&mut self) -> Result<(), WriterError> {
+        if self.after_key {
+            return Err(WriterError::ExpectedValue);
+        }
+        let close = match self.stack.pop() {
+            None => return Err(WriterError::NothingToEnd),
+            Some(Container::Array) => b"]",
+            Some(Container::Object) => b"}",
+        };
+        self.inner.write_all(close)?;
+        self.started = true;
+        Ok(())
+    }
+
+    /// Check the document is complete and hand back the inner writer,
+    /// flushed
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        if !self.stack.is_empty() || !self.started {
+            return Err(WriterError::Unfinished);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn begin(&mut self, container: Container, open: &[u8]) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.inner.write_all(open)?;
+        self.stack.push(container);
+        self.started = false;
+        Ok(())
+    }
+
+    /// Check a value can come next and write the comma before it
+    fn before_value(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            None if self.started => return Err(WriterError::DocumentComplete),
+            Some(Container::Object) if !self.after_key => return Err(WriterError::ExpectedKey),
+            Some(Container::Array) if self.started => self.inner.write_all(b",")?,
+            _ => {}
+        }
+        self.after_key = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonEvent;
+    #[cfg(feature = "std")]
+    use super::events_from_reader;
+    use crate::JsonError;
+    use crate::parser::TokenParseError;
+    use crate::prelude::*;
+
+    fn events(input: &str) -> Vec<Result<JsonEvent, JsonError>> {
+        super::events(input).collect()
+    }
+
+    #[test]
+    fn events_of_nested_document() {
+        let expected = vec![
+            Ok(JsonEvent::StartObject),
+            Ok(JsonEvent::Key("a".into())),
+            Ok(JsonEvent::StartArray),
+            Ok(JsonEvent::Null),
+            Ok(JsonEvent::Number(6.0)),
+            Ok(JsonEvent::EndArray),
+            Ok(JsonEvent::Key("b".into())),
+            Ok(JsonEvent::StartObject),
+            Ok(JsonEvent::EndObject),
+            Ok(JsonEvent::EndObject),
+        ];
+
+        assert_eq!(events(r#"{"a": [null, 6], "b": {}}"#), expected);
+    }
+
+    #[test]
+    fn events_stop_at_first_error() {
+        let expected = vec![
+            Ok(JsonEvent::StartArray),
+            Ok(JsonEvent::Boolean(true)),
+            Err(TokenParseError::ExpectedComma.into()),
+        ];
+
+        assert_eq!(events("[true : false]"), expected);
+    }
+
+    #[test]
+    fn events_of_unfinished_document() {
+        let expected = vec![
+            Ok(JsonEvent::StartArray),
+            Err(TokenParseError::UnexpectedEnd.into()),
+        ];
+
+        assert_eq!(events("["), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn events_from_reader_match_events() {
+        let input = r#"[{"k": "v"}, 1.5, false]"#;
+        let from_reader: Vec<Result<JsonEvent, JsonError>> =
+            events_from_reader(input.as_bytes()).collect();
+
+        assert_eq!(from_reader, events(input));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn writer_rejects_out_of_order_calls() {
+        use super::{JsonWriter, WriterError};
+
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.key("a"), Err(WriterError::UnexpectedKey)));
+        writer.begin_object().unwrap();
+        assert!(matches!(writer.value(1), Err(WriterError::ExpectedKey)));
+        writer.key("a").unwrap();
+        assert!(matches!(writer.key("b"), Err(WriterError::UnexpectedKey)));
+        assert!(matches!(writer.end(), Err(WriterError::ExpectedValue)));
+        writer.begin_array().unwrap();
+        writer.value("x").unwrap();
+        writer.value(2).unwrap();
+        writer.end().unwrap();
+        writer.end().unwrap();
+        assert!(matches!(writer.end(), Err(WriterError::NothingToEnd)));
+        assert!(matches!(
+            writer.value(3),
+            Err(WriterError::DocumentComplete)
+        ));
+        assert_eq!(writer.finish().unwrap(), br#"{"a":["x",2]}"#);
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        assert!(matches!(writer.finish(), Err(WriterError::Unfinished)));
+        assert!(matches!(
+            JsonWriter::new(Vec::new()).finish(),
+            Err(WriterError::Unfinished)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn minify_strips_whitespace_and_comments() {
+        use super::{minify, minify_with_syntax};
+        use crate::{Syntax, TokenizeError};
+
+        let minified = |input: &str, syntax| {
+            let mut out = Vec::new();
+            minify_with_syntax(input.as_bytes(), &mut out, syntax).map(|()| out)
+        };
+
+        assert_eq!(
+            minified("\u{feff} [ \"a \\\" // b\" , 1e3 ]\n", Syntax::default()),
+            Ok(br#"["a \" // b",1e3]"#.to_vec())
+        );
+        assert_eq!(
+            minified("{ // one\n 'a b': /* two * */ [1, '/*'] }", Syntax::json5()),
+            Ok(br#"{'a b':[1,'/*']}"#.to_vec())
+        );
+        assert_eq!(
+            minified("[1, /* 2 */]", Syntax::default()),
+            Err(TokenizeError::CharNotRecognized('/').into())
+        );
+        assert_eq!(
+            minified("\"¿\" ", Syntax::default()),
+            Ok("\"¿\"".as_bytes().to_vec())
+        );
+        assert_eq!(
+            minified("\u{feff}\u{feff}", Syntax::default()),
+            Err(TokenizeError::CharNotRecognized('\u{feff}').into())
+        );
+        assert_eq!(
+            minified("[1] [2]", Syntax::default()),
+            Err(TokenParseError::TrailingTokens.into())
+        );
+        assert_eq!(
+            minify("{\"a\" 1}".as_bytes(), Vec::new()),
+            Err(TokenParseError::ExpectedColon.into())
+        );
+    }
+}
@@ -0,0 +1,58 @@
+// The string held by `Value::String`: the standard `String` unless
+// `compact-strings` is enabled
+
+/// The string held by a [`Value::String`](crate::Value::String)
+///
+/// `String`, or with the `compact-strings` feature a `CompactString` from
+/// the `compact_str` crate, which is just as large but stores strings of up
+/// to 24 bytes inline instead of allocating them. Both dereference to `str`
+/// and convert from and into `String` and `&str` with `From`.
+#[cfg(not(feature = "compact-strings"))]
+pub type JsonString = alloc::string::String;
+
+#[cfg(feature = "compact-strings")]
+pub type JsonString = compact_str::CompactString;
+
+/// `s` as a [`JsonString`], reusing its buffer
+#[cfg(not(feature = "compact-strings"))]
+pub(crate) fn from_string(s: alloc::string::String) -> JsonString {
+    s
+}
+
+#[cfg(feature = "compact-strings")]
+pub(crate) fn from_string(s: alloc::string::String) -> JsonString {
+    JsonString::from_string_buffer(s)
+}
+
+/// `s` as a `String`, reusing its buffer when it has one
+#[cfg(not(feature = "compact-strings"))]
+pub(crate) fn into_string(s: JsonString) -> alloc::string::String {
+    s
+}
+
+#[cfg(feature = "compact-strings")]
+pub(crate) fn into_string(s: JsonString) -> alloc::string::String {
+    s.into_string()
+}
+
+#[cfg(all(test, feature = "compact-strings"))]
+mod tests {
+    use crate::{Value, parse};
+
+    #[test]
+    fn short_strings_are_inline() {
+        let value = parse(r#"["ok", "a string well over twenty-four bytes"]"#).unwrap();
+        let Value::Array(values) = value else {
+            panic!("expected an array");
+        };
+        let inline: Vec<bool> = values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => !s.is_heap_allocated(),
+                _ => panic!("expected a string"),
+            })
+            .collect();
+
+        assert_eq!(inline, [true, false]);
+    }
+}
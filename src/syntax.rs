@@ -0,0 +1,66 @@
+// Extensions to the JSON grammar the lexer can be asked to accept
+
+/// Which extensions to strict JSON the lexer accepts
+///
+/// The default is strict JSON, but for a leading byte order mark being
+/// skipped as [RFC 8259](https://www.rfc-editor.org/rfc/rfc8259#section-8.1)
+/// allows. The extensions are handled entirely while lexing, so the
+/// documents they allow come out as ordinary tokens and every parser in the
+/// crate reads them the same way. Parse with
+/// [`parse_with_syntax`](crate::parse_with_syntax), or
+/// [`parse_json5`](crate::parse_json5) for the whole of JSON5:
+///
+/// ```
+/// use parser_json_rs::{Syntax, json, parse_with_syntax};
+///
+/// let value = parse_with_syntax("[1, /* two */ 2] // done", Syntax::jsonc()).unwrap();
+/// assert_eq!(value, json!([1, 2]));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Syntax {
+    /// `//` line comments and `/* */` block comments, skipped like
+    /// whitespace unless [captured](crate::Lexer::capture_comments)
+    pub comments: bool,
+    /// strings in `'single quotes'`, in which `"` needs no escaping and `'`
+    /// does, as JavaScript writes them
+    pub single_quotes: bool,
+    /// object keys written as bare identifiers, `{key: 1}`, as JavaScript
+    /// and the MongoDB shell write them
+    pub unquoted_keys: bool,
+    /// the numbers `NaN`, `Infinity` and `-Infinity`, as Python's
+    /// `json.dumps` writes them, with a `+` sign too under `json5`
+    pub non_finite: bool,
+    /// hexadecimal integers, `0xFF` and `-0xff`, as embedded devices log
+    /// them and JavaScript writes them
+    pub hex_numbers: bool,
+    /// fail on a U+FEFF byte order mark at the start of the input, as
+    /// Windows tools often write one, instead of skipping it
+    pub reject_bom: bool,
+    /// the rest of [JSON5](https://spec.json5.org): the extra escapes of
+    /// its strings, trailing commas, and numbers with a leading `+` or a
+    /// leading or trailing `.`
+    pub json5: bool,
+}
+
+impl Syntax {
+    /// JSON with comments, as in VS Code's settings files
+    pub fn jsonc() -> Self {
+        Syntax {
+            comments: true,
+            ..Syntax::default()
+        }
+    }
+
+    /// Everything JSON5 allows
+    pub fn json5() -> Self {
+        Syntax {
+            comments: true,
+            single_quotes: true,
+            unquoted_keys: true,
+            non_finite: true,
+            hex_numbers: true,
+            json5: true,
+            ..Syntax::default()
+        }
+    }
+}
@@ -0,0 +1,402 @@
+// A document laid out as one flat array of entries instead of a tree
+
+use core::ops::Range;
+
+use super::parser::TokenParseError;
+use super::prelude::*;
+use super::stream::{Events, JsonEvent};
+use super::tokenize::Lexer;
+use super::{DEFAULT_MAX_DEPTH, JsonError, Value};
+
+/// One value of a tape, or the key of an object member
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    /// where the unescaped string is in the tape's string buffer
+    String(Range<usize>),
+    /// `len` elements follow, the entry after the last one is `end`
+    Array {
+        len: usize,
+        end: usize,
+    },
+    /// `len` members follow, each a key entry then a value, the entry after
+    /// the last one is `end`
+    Object {
+        len: usize,
+        end: usize,
+    },
+}
+
+/// A parsed document stored as a flat array of entries in document order,
+/// with the contents of every string in one shared buffer
+///
+/// Building a tape takes two allocations that grow as needed instead of one
+/// per array, object and string, and reading it walks memory front to back.
+/// Arrays and objects record where they end, so unread values are skipped
+/// over rather than visited. Navigate it from [`Tape::root`]:
+///
+/// ```
+/// use parser_json_rs::parse_tape;
+///
+/// let tape = parse_tape(r#"{"rows": [{"id": 1}, {"id": 2}], "total": 2}"#).unwrap();
+/// let ids: Vec<f64> = tape.root().get("rows").unwrap().members()
+///     .filter_map(|row| row.get("id")?.as_f64())
+///     .collect();
+/// assert_eq!(ids, [1.0, 2.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tape {
+    entries: Vec<Entry>,
+    strings: String,
+}
+
+impl Tape {
+    /// The top-level value of the document
+    pub fn root(&self) -> TapeValue<'_> {
+        TapeValue {
+            tape: self,
+            index: 0,
+        }
+    }
+
+    /// The index of the entry following the value at `index`
+    fn skip(&self, index: usize) -> usize {
+        match self.entries[index] {
+            Entry::Array { end, .. } | Entry::Object { end, .. } => end,
+            _ => index + 1,
+        }
+    }
+
+    /// Add `s` to the string buffer, returning the entry pointing to it
+    fn string(&mut self, s: &str) -> Entry {
+        let start = self.strings.len();
+        self.strings.push_str(s);
+        Entry::String(start..self.strings.len())
+    }
+
+    fn str(&self, index: usize) -> Option<&str> {
+        match &self.entries[index] {
+            Entry::String(range) => Some(&self.strings[range.clone()]),
+            _ => None,
+        }
+    }
+}
+
+/// A value within a [`Tape`]
+#[derive(Debug, Clone, Copy)]
+pub struct TapeValue<'t> {
+    tape: &'t Tape,
+    index: usize,
+}
+
+impl<'t> TapeValue<'t> {
+    fn entry(&self) -> &'t Entry {
+        &self.tape.entries[self.index]
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.entry(), Entry::Null)
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self.entry(), Entry::Array { .. })
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self.entry(), Entry::Object { .. })
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.entry() {
+            Entry::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.entry() {
+            Entry::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number if it is whole and within `i64` range, as [`Value::as_i64`]
+    pub fn as_i64(&self) -> Option<i64> {
+        Value::Number(self.as_f64()?).as_i64()
+    }
+
+    /// The number if it is whole and within `u64` range, as [`Value::as_u64`]
+    pub fn as_u64(&self) -> Option<u64> {
+        Value::Number(self.as_f64()?).as_u64()
+    }
+
+    pub fn as_str(&self) -> Option<&'t str> {
+        self.tape.str(self.index)
+    }
+
+    /// The number of elements of an array or members of an object, `None`
+    /// for any other value
+    pub fn len(&self) -> Option<usize> {
+        match self.entry() {
+            Entry::Array { len, .. } | Entry::Object { len, .. } => Some(*len),
+            _ => None,
+        }
+    }
+
+    /// Whether this is an array or object without elements or members,
+    /// `None` for any other value
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// The elements of an array, nothing for any other value
+    pub fn members(&self) -> TapeMembers<'t> {
+        let (index, remaining) = match self.entry() {
+            Entry::Array { len, .. } => (self.index + 1, *len),
+            _ => (self.index, 0),
+        };
+        TapeMembers {
+            tape: self.tape,
+            index,
+            remaining,
+        }
+    }
+
+    /// The keys and values of an object in document order, nothing for any
+    /// other value
+    pub fn entries(&self) -> TapeEntries<'t> {
+        let (index, remaining) = match self.entry() {
+            Entry::Object { len, .. } => (self.index + 1, *len),
+            _ => (self.index, 0),
+        };
+        TapeEntries {
+            tape: self.tape,
+            index,
+            remaining,
+        }
+    }
+
+    /// The element at `index` of an array, skipping over the ones before it
+    pub fn get_index(&self, index: usize) -> Option<TapeValue<'t>> {
+        self.members().nth(index)
+    }
+
+    /// The member `key` of an object, the last one if the key is repeated as
+    /// when parsing into a [`Value`]
+    pub fn get(&self, key: &str) -> Option<TapeValue<'t>> {
+        self.entries()
+            .filter(|(name, _)| *name == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// Build the [`Value`] tree of this value
+    pub fn to_value(&self) -> Value {
+        match self.entry() {
+            Entry::Null => Value::Null,
+            Entry::Boolean(b) => Value::Boolean(*b),
+            Entry::Number(n) => Value::Number(*n),
+            Entry::String(range) => Value::String(self.tape.strings[range.clone()].into()),
+            Entry::Array { .. } => Value::Array(self.members().map(|v| v.to_value()).collect()),
+            Entry::Object { .. } => Value::Object(
+                self.entries()
+                    .map(|(key, value)| (key.to_owned(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The elements of an array, see [`TapeValue::members`]
+#[derive(Debug, Clone)]
+pub struct TapeMembers<'t> {
+    tape: &'t Tape,
+    index: usize,
+    remaining: usize,
+}
+
+/// The keys and values of an object, see [`TapeValue::entries`]
+#[derive(Debug, Clone)]
+pub struct TapeEntries<'t> {
+    tape: &'t Tape,
+    index: usize,
+    remaining: usize,
+}
+
+impl<'t> Iterator for TapeMembers<'t> {
+    type Item = TapeValue<'t>;
+
+    fn next(&mut self) -> Option<TapeValue<'t>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = TapeValue {
+            tape: self.tape,
+            index: self.index,
+        };
+        self.index = self.tape.skip(self.index);
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'t> Iterator for TapeEntries<'t> {
+    type Item = (&'t str, TapeValue<'t>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let key = self.tape.str(self.index).expect("keys are strings");
+        let value = TapeValue {
+            tape: self.tape,
+            index: self.index + 1,
+        };
+        self.index = self.tape.skip(self.index + 1);
+        self.remaining -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for TapeMembers<'_> {}
+impl ExactSizeIterator for TapeEntries<'_> {}
+
+/// Parse a JSON document from text into a [`Tape`]
+pub fn parse_tape(input: &str) -> Result<Tape, JsonError> {
+    let mut lexer = Lexer::new(input);
+    let mut tape = Tape {
+        entries: Vec::new(),
+        strings: String::new(),
+    };
+    // the open arrays and objects, innermost last
+    let mut stack: Vec<usize> = Vec::new();
+
+    for event in Events::new(&mut lexer) {
+        let entry = match event? {
+            JsonEvent::Null => Entry::Null,
+            JsonEvent::Boolean(b) => Entry::Boolean(b),
+            JsonEvent::Number(n) => Entry::Number(n),
+            JsonEvent::String(s) => tape.string(&s),
+            JsonEvent::Key(key) => {
+                // objects count their members by their keys
+                let parent = *stack.last().expect("keys are inside objects");
+                if let Entry::Object { len, .. } = &mut tape.entries[parent] {
+                    *len += 1;
+                }
+                let key = tape.string(&key);
+                tape.entries.push(key);
+                continue;
+            }
+            JsonEvent::StartArray | JsonEvent::StartObject if stack.len() == DEFAULT_MAX_DEPTH => {
+                return Err(TokenParseError::DepthLimitExceeded.into());
+            }
+            JsonEvent::StartArray => Entry::Array { len: 0, end: 0 },
+            JsonEvent::StartObject => Entry::Object { len: 0, end: 0 },
+            JsonEvent::EndArray | JsonEvent::EndObject => {
+                let start = stack.pop().expect("events are balanced");
+                let next = tape.entries.len();
+                if let Entry::Array { end, .. } | Entry::Object { end, .. } =
+                    &mut tape.entries[start]
+                {
+                    *end = next;
+                }
+                continue;
+            }
+        };
+
+        if let Some(&parent) = stack.last()
+            && let Entry::Array { len, .. } = &mut tape.entries[parent]
+        {
+            *len += 1;
+        }
+        if matches!(entry, Entry::Array { .. } | Entry::Object { .. }) {
+            stack.push(tape.entries.len());
+        }
+        tape.entries.push(entry);
+    }
+
+    if lexer.next().is_some() {
+        return Err(TokenParseError::TrailingTokens.into());
+    }
+    Ok(tape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_tape;
+    use crate::prelude::*;
+    use crate::{JsonError, TokenParseError, parse};
+
+    #[test]
+    fn tape_matches_parse() {
+        let input = r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e", "f": [], "g": {}}"#;
+
+        assert_eq!(
+            parse_tape(input).unwrap().root().to_value(),
+            parse(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn navigate_a_tape() {
+        let tape = parse_tape(r#"[{"id": 1, "tags": ["x", "y"]}, {"id": -2}, true]"#).unwrap();
+        let root = tape.root();
+
+        assert_eq!(root.len(), Some(3));
+        assert_eq!(
+            root.get_index(0).unwrap().get("tags").unwrap().is_empty(),
+            Some(false)
+        );
+        assert_eq!(
+            root.get_index(1).unwrap().get("id").unwrap().as_i64(),
+            Some(-2)
+        );
+        assert_eq!(root.get_index(2).unwrap().as_bool(), Some(true));
+        assert!(root.get_index(3).is_none());
+        let first = root.get_index(0).unwrap();
+        assert_eq!(
+            first.entries().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["id", "tags"]
+        );
+        let tags = first.get("tags").unwrap();
+        assert_eq!(
+            tags.members().map(|tag| tag.as_str()).collect::<Vec<_>>(),
+            [Some("x"), Some("y")]
+        );
+        assert!(first.get("missing").is_none());
+        assert!(tags.get("x").is_none());
+    }
+
+    #[test]
+    fn tape_keeps_the_last_duplicate_key() {
+        let tape = parse_tape(r#"{"a": [1], "a": 2}"#).unwrap();
+
+        assert_eq!(tape.root().get("a").unwrap().as_f64(), Some(2.0));
+    }
+
+    #[test]
+    fn parse_tape_rejects_malformed_input() {
+        assert_eq!(
+            parse_tape("[1, 2"),
+            Err(JsonError::Parse(TokenParseError::UnexpectedEnd))
+        );
+        assert_eq!(
+            parse_tape("[1] 2"),
+            Err(JsonError::Parse(TokenParseError::TrailingTokens))
+        );
+        assert_eq!(
+            parse_tape(&"[".repeat(1000)),
+            Err(JsonError::Parse(TokenParseError::DepthLimitExceeded))
+        );
+    }
+}
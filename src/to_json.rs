@@ -0,0 +1,300 @@
+// Turning Rust types into values without serde
+
+use alloc::collections::BTreeMap;
+
+use super::Value;
+use super::map::HashMap;
+use super::prelude::*;
+use super::ser::write_value;
+use super::string::from_string;
+
+/// A type that can be written as a [`Value`], the counterpart of
+/// [`FromJson`](crate::FromJson)
+///
+/// Implemented for the standard library's scalars, strings, `Option`s
+/// (`None` being null), sequences and string-keyed maps, and for references
+/// to any of them.
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+
+    /// The value as compact JSON text
+    fn to_json_string(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, &self.to_json()).expect("writing to a String never fails");
+        out
+    }
+}
+
+impl ToJson for Value {
+    fn to_json(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Value {
+        Value::Boolean(*self)
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.as_str().into())
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Value {
+        Value::String(from_string(self.to_string()))
+    }
+}
+
+macro_rules! to_json_number {
+    ($($ty:ty),*) => {
+        $(
+            impl ToJson for $ty {
+                fn to_json(&self) -> Value {
+                    Value::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+to_json_number!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        match self {
+            Some(value) => value.to_json(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+}
+
+impl<K: AsRef<str>, T: ToJson, S> ToJson for HashMap<K, T, S> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (key.as_ref().to_owned(), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: AsRef<str>, T: ToJson> ToJson for BTreeMap<K, T> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (key.as_ref().to_owned(), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use alloc::collections::BTreeMap;
+
+    use super::ToJson;
+    use crate::{FromJson, JsonError, Value};
+
+    struct Server {
+        host: String,
+        ports: Vec<u16>,
+        note: Option<&'static str>,
+    }
+
+    impl ToJson for Server {
+        fn to_json(&self) -> Value {
+            let mut value = Value::Null;
+            value["host"] = self.host.to_json();
+            value["ports"] = self.ports.to_json();
+            value["note"] = self.note.to_json();
+            value
+        }
+    }
+
+    impl FromJson for Server {
+        fn from_json(value: &Value) -> Result<Self, JsonError> {
+            Ok(Server {
+                host: value.field("host")?,
+                ports: value.field("ports")?,
+                note: None,
+            })
+        }
+    }
+
+    #[test]
+    fn to_json_user_types() {
+        let server = Server {
+            host: String::from("a"),
+            ports: vec![80, 443],
+            note: None,
+        };
+        let value = server.to_json();
+
+        assert_eq!(
+            value,
+            json!({"host": "a", "ports": [80, 443], "note": null})
+        );
+        assert_eq!(Server::from_json(&value).unwrap().ports, server.ports);
+    }
+
+    #[test]
+    fn to_json_string_writes_compact_text() {
+        let map = BTreeMap::from([("k", [Some('c'), None])]);
+
+        assert_eq!(map.to_json_string(), r#"{"k":["c",null]}"#);
+        assert_eq!("\"".to_json_string(), r#""\"""#);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_round_trips() {
+        use crate::{ConversionError, FromJson, ToJson};
+
+        #[derive(Debug, PartialEq, FromJson, ToJson)]
+        #[json(rename_all = "camelCase")]
+        struct Config<T> {
+            server_name: String,
+            #[json(rename = "ports")]
+            listen_on: Vec<u16>,
+            #[json(default)]
+            retries: u8,
+            #[json(skip)]
+            cache: Option<T>,
+            mode: Mode,
+            point: Point,
+        }
+
+        #[derive(Debug, PartialEq, FromJson, ToJson)]
+        struct Point(i32, i32);
+
+        #[derive(Debug, PartialEq, FromJson, ToJson)]
+        #[json(rename_all = "snake_case")]
+        enum Mode {
+            ReadOnly,
+            Limited(u32),
+            Custom { max_size: f64 },
+        }
+
+        let value = json!({
+            "serverName": "a",
+            "ports": [80],
+            "mode": {"limited": 5},
+            "point": [1, -1]
+        });
+        let config = Config::<String>::from_json(&value).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                server_name: String::from("a"),
+                listen_on: vec![80],
+                retries: 0,
+                cache: None,
+                mode: Mode::Limited(5),
+                point: Point(1, -1),
+            }
+        );
+
+        let mut written = value.clone();
+        written["retries"] = json!(0);
+        assert_eq!(config.to_json(), written);
+        assert_eq!(Mode::ReadOnly.to_json(), json!("read_only"));
+        assert_eq!(
+            Mode::from_json(&json!({"custom": {"max_size": 1.5}})),
+            Ok(Mode::Custom { max_size: 1.5 })
+        );
+
+        let err = Config::<String>::from_json(&json!({
+            "serverName": "a",
+            "ports": [],
+            "mode": {"custom": {"max_size": "big"}},
+            "point": [1, 2]
+        }));
+        assert_eq!(
+            err,
+            Err(JsonError::Conversion(ConversionError {
+                path: String::from("/mode/custom/max_size"),
+                expected: "a number",
+                found: String::from("a string"),
+            }))
+        );
+        assert!(Mode::from_json(&json!("other")).is_err());
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_fields_named_like_generated_locals() {
+        use crate::{FromJson, ToJson};
+
+        #[derive(Debug, PartialEq, FromJson, ToJson)]
+        struct Page {
+            members: Vec<u32>,
+            value: String,
+            inner: bool,
+            r#type: u8,
+        }
+
+        #[derive(Debug, PartialEq, FromJson, ToJson)]
+        enum Event {
+            Page { members: Vec<u32>, inner: bool },
+        }
+
+        let page = Page {
+            members: vec![1, 2],
+            value: String::from("a"),
+            inner: true,
+            r#type: 3,
+        };
+        let value = json!({"members": [1, 2], "value": "a", "inner": true, "type": 3});
+        assert_eq!(page.to_json(), value);
+        assert_eq!(Page::from_json(&value), Ok(page));
+
+        let event = Event::Page {
+            members: vec![],
+            inner: false,
+        };
+        let value = json!({"Page": {"members": [], "inner": false}});
+        assert_eq!(event.to_json(), value);
+        assert_eq!(Event::from_json(&value), Ok(event));
+    }
+}
@@ -1,11 +1,16 @@
 // REference for possible tokens https://www.json.org/json-en.html
 
-use std::num::ParseFloatError;
+use alloc::borrow::Cow;
+use core::marker::PhantomData;
+use core::mem;
+use core::num::ParseFloatError;
+use core::ops::Range;
 
-use regex::Regex;
+use super::Syntax;
+use super::prelude::*;
 
-#[derive(Debug, PartialEq)]
-pub enum Token {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
     // punctuation tokens
     /// `{`
     LeftCurlyBracket,
@@ -29,143 +34,1242 @@ pub enum Token {
     True,
     /// Any number literal
     Number(f64),
-    /// Key of a key/value pair or String
-    String(String),
+    /// Key of a key/value pair or String, with escapes left as written and
+    /// borrowed from the input whenever the input is held in memory
+    String(Cow<'a, str>),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum TokenizeError {
     UnrecognizedToken,
     UnfinishedLiteralValue,
+    /// a word that is a typo of the literal it holds, `ture` for `true`
+    MisspelledLiteral(&'static str),
     ParseNumberError(ParseFloatError),
     UnclosedQuotes,
     UnexpectedEof,
     CharNotRecognized(char),
+    /// byte offset of an invalid UTF-8 sequence in byte input
+    InvalidUtf8(usize),
+    /// byte offset of an invalid sequence in UTF-16 or UTF-32 byte input,
+    /// an unpaired surrogate or a code unit cut short by the end of input
+    InvalidEncoding(usize),
+}
+
+impl TokenizeError {
+    /// See [`JsonError::code`](crate::JsonError::code)
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenizeError::UnclosedQuotes => "E001_UNCLOSED_STRING",
+            TokenizeError::UnrecognizedToken => "E002_UNRECOGNIZED_TOKEN",
+            TokenizeError::UnfinishedLiteralValue => "E003_INVALID_LITERAL",
+            TokenizeError::ParseNumberError(_) => "E004_INVALID_NUMBER",
+            TokenizeError::UnexpectedEof => "E005_UNEXPECTED_EOF",
+            TokenizeError::CharNotRecognized(_) => "E006_UNEXPECTED_CHARACTER",
+            TokenizeError::InvalidUtf8(_) => "E007_INVALID_UTF8",
+            TokenizeError::MisspelledLiteral(_) => "E008_MISSPELLED_LITERAL",
+            TokenizeError::InvalidEncoding(_) => "E009_INVALID_ENCODING",
+        }
+    }
 }
 
-pub fn tokenize(input: String) -> Result<Vec<Token>, TokenizeError> {
-    let chars: Vec<char> = input.chars().collect();
-    let mut index = 0;
-    let mut tokens: Vec<Token> = Vec::new();
+/// Where the lexer reads its bytes from, strings can borrow from it for `'a`
+pub(crate) trait Source<'a> {
+    type Error: From<TokenizeError>;
+
+    /// The next byte without consuming it, `None` at the end of the input
+    fn peek(&mut self) -> Result<Option<u8>, Self::Error>;
+
+    /// Consume the byte returned by the last `peek`
+    fn bump(&mut self);
+
+    /// Number of bytes consumed so far
+    fn offset(&self) -> usize;
+
+    fn next_byte(&mut self) -> Result<Option<u8>, Self::Error> {
+        let byte = self.peek()?;
+        if byte.is_some() {
+            self.bump();
+        }
+        Ok(byte)
+    }
+
+    /// Consume whitespace up to the next token
+    fn skip_whitespace(&mut self) -> Result<(), Self::Error> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                break;
+            }
+            self.bump();
+        }
+        Ok(())
+    }
+
+    /// Scan the raw contents of a string up to and including its closing
+    /// `quote`, the opening quote is already consumed
+    fn string(&mut self, quote: u8) -> Result<Cow<'a, str>, Self::Error> {
+        let start = self.offset();
+        let mut bytes = Vec::new();
+        let mut is_escaping = false;
+
+        loop {
+            let Some(byte) = self.next_byte()? else {
+                return Err(TokenizeError::UnclosedQuotes.into());
+            };
+            if end_of_string(byte, quote, &mut is_escaping) {
+                break;
+            }
+            bytes.push(byte);
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(string) => Ok(Cow::Owned(string)),
+            Err(err) => Err(invalid_utf8(start, err.utf8_error()).into()),
+        }
+    }
+
+    /// Scan the rest of an identifier, its first byte is already consumed
+    fn word(&mut self, first: u8) -> Result<Cow<'a, str>, Self::Error> {
+        let start = self.offset() - 1;
+        let mut bytes = vec![first];
+        while let Some(byte) = self.peek()?
+            && is_identifier_byte(byte)
+        {
+            self.bump();
+            bytes.push(byte);
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(word) => Ok(Cow::Owned(word)),
+            Err(err) => Err(invalid_utf8(start, err.utf8_error()).into()),
+        }
+    }
+}
 
-    while index < chars.len() {
-        let token = make_token(&chars, &mut index, &input)?;
-        tokens.push(token);
-        index += 1
+/// Track escapes while scanning a string byte by byte, returning whether
+/// `byte` is its closing `quote`. Bytes of multi-byte UTF-8 characters are
+/// never quotes or `\`, so scanning bytes can't end the string early.
+pub(crate) fn end_of_string(byte: u8, quote: u8, is_escaping: &mut bool) -> bool {
+    match byte {
+        _ if byte == quote && !*is_escaping => return true,
+        b'\\' => *is_escaping = !*is_escaping,
+        _ => *is_escaping = false,
     }
+    false
+}
 
-    Ok(tokens)
+/// Offset of the first `quote` or `\` in `bytes`, found with SIMD
+/// instructions where the target has them when the `simd-strings` feature is
+/// enabled
+#[cfg(feature = "simd-strings")]
+pub(crate) fn find_quote_or_backslash(bytes: &[u8], quote: u8) -> Option<usize> {
+    memchr::memchr2(quote, b'\\', bytes)
 }
 
-fn make_token(chars: &[char], index: &mut usize, input: &str) -> Result<Token, TokenizeError> {
-    let mut ch = chars[*index];
-    while ch.is_ascii_whitespace() {
-        *index += 1;
-        if *index >= chars.len() {
-            return Err(TokenizeError::UnexpectedEof);
+#[cfg(not(feature = "simd-strings"))]
+pub(crate) fn find_quote_or_backslash(bytes: &[u8], quote: u8) -> Option<usize> {
+    bytes
+        .iter()
+        .position(|&byte| byte == quote || byte == b'\\')
+}
+
+/// Whether `byte` can start a JSON5 identifier. Bytes of non-ASCII
+/// characters are let through rather than checked against Unicode's
+/// letters.
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' || !byte.is_ascii()
+}
+
+fn is_identifier_byte(byte: u8) -> bool {
+    is_identifier_start(byte) || byte.is_ascii_digit()
+}
+
+fn invalid_utf8(start: usize, err: core::str::Utf8Error) -> TokenizeError {
+    TokenizeError::InvalidUtf8(start + err.valid_up_to())
+}
+
+/// Input held in memory as a whole
+pub(crate) struct SliceSource<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceSource<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        SliceSource { bytes, pos: 0 }
+    }
+}
+
+impl<'a> Source<'a> for SliceSource<'a> {
+    type Error = TokenizeError;
+
+    fn peek(&mut self) -> Result<Option<u8>, TokenizeError> {
+        Ok(self.bytes.get(self.pos).copied())
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+    }
+
+    fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), TokenizeError> {
+        let rest = &self.bytes[self.pos..];
+        self.pos += rest
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        Ok(())
+    }
+
+    /// Borrow the string contents straight from the input, jumping from one
+    /// quote or backslash to the next rather than looking at every byte
+    fn string(&mut self, quote: u8) -> Result<Cow<'a, str>, TokenizeError> {
+        let start = self.pos;
+        let mut end = start;
+        loop {
+            let rest = self.bytes.get(end..).ok_or(TokenizeError::UnclosedQuotes)?;
+            end += find_quote_or_backslash(rest, quote).ok_or(TokenizeError::UnclosedQuotes)?;
+            if self.bytes[end] == quote {
+                break;
+            }
+            // step over the backslash and the byte it escapes
+            end += 2;
+        }
+        self.pos = end + 1;
+
+        match core::str::from_utf8(&self.bytes[start..end]) {
+            Ok(string) => Ok(Cow::Borrowed(string)),
+            Err(err) => Err(invalid_utf8(start, err)),
         }
-        ch = chars[*index];
     }
-    let token = match ch {
-        '{' => Token::LeftCurlyBracket,
-        '}' => Token::RightCurlyBracket,
-        '[' => Token::LeftSquareBracket,
-        ']' => Token::RightSquareBracket,
-        ':' => Token::Colon,
-        ',' => Token::Comma,
-        'n' => tokenize_literal(index, Token::Null, input)?,
-        't' => tokenize_literal(index, Token::True, input)?,
-        'f' => tokenize_literal(index, Token::False, input)?,
-        ch if ch.is_ascii_digit() | (ch == '-' && chars[*index + 1].is_ascii_digit()) => {
-            tokenize_float(chars, index)?
+
+    fn word(&mut self, _first: u8) -> Result<Cow<'a, str>, TokenizeError> {
+        let start = self.pos - 1;
+        let rest = &self.bytes[self.pos..];
+        self.pos += rest
+            .iter()
+            .position(|&byte| !is_identifier_byte(byte))
+            .unwrap_or(rest.len());
+
+        match core::str::from_utf8(&self.bytes[start..self.pos]) {
+            Ok(word) => Ok(Cow::Borrowed(word)),
+            Err(err) => Err(invalid_utf8(start, err)),
         }
-        '"' => tokenize_string(chars, index)?,
+    }
+}
 
-        ch => return Err(TokenizeError::CharNotRecognized(ch)),
-    };
+/// Tokens lexed one at a time from any [`Source`], stopping after the first error
+pub(crate) struct TokenStream<'a, S> {
+    source: S,
+    syntax: Syntax,
+    failed: bool,
+    /// offset where the last token started
+    start: usize,
+    /// offset where the last token ended, when the lexer looked past it
+    end: Option<usize>,
+    /// whether the last token ends a value, so a comma after it may trail
+    after_value: bool,
+    /// spans of the comments skipped, when they are being kept
+    comments: Option<Vec<Range<usize>>>,
+    input: PhantomData<&'a [u8]>,
+}
 
-    Ok(token)
+impl<'a, S: Source<'a>> TokenStream<'a, S> {
+    pub(crate) fn new(source: S) -> Self {
+        TokenStream::with_syntax(source, Syntax::default())
+    }
+
+    pub(crate) fn with_syntax(source: S, syntax: Syntax) -> Self {
+        TokenStream {
+            source,
+            syntax,
+            failed: false,
+            start: 0,
+            end: None,
+            after_value: false,
+            comments: None,
+            input: PhantomData,
+        }
+    }
+
+    /// Byte range of the last token produced
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.start..self.end.unwrap_or(self.source.offset())
+    }
+
+    /// Whether the last token ends a value, see [`TokenStream::resume`]
+    pub(crate) fn after_value(&self) -> bool {
+        self.after_value
+    }
+
+    /// Carry on from where an earlier stream over the same input stopped,
+    /// after a token that ended a value if `after_value`
+    pub(crate) fn resume(mut self, after_value: bool) -> Self {
+        self.after_value = after_value;
+        self
+    }
+
+    /// Keep the spans of the comments skipped from now on
+    pub(crate) fn capture_comments(&mut self) {
+        self.comments.get_or_insert_with(Vec::new);
+    }
+
+    /// Spans of the comments skipped since the last call, if kept
+    pub(crate) fn take_comments(&mut self) -> Vec<Range<usize>> {
+        self.comments.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    fn lex(&mut self) -> Result<Option<Token<'a>>, S::Error> {
+        self.end = None;
+        self.start = self.source.offset();
+        if self.start == 0 && !self.syntax.reject_bom && self.source.peek()? == Some(0xef) {
+            self.skip_bom()?;
+        }
+        self.skip_ignored()?;
+        self.start = self.source.offset();
+        let Some(byte) = self.source.next_byte()? else {
+            return Ok(None);
+        };
+
+        let token = match byte {
+            b'\'' if self.syntax.single_quotes => {
+                let raw = self.source.string(byte)?;
+                Token::String(if self.syntax.json5 {
+                    json5_escapes(raw)
+                } else {
+                    raw
+                })
+            }
+            byte if (self.syntax.unquoted_keys || self.syntax.non_finite)
+                && is_identifier_start(byte) =>
+            {
+                let word = self.source.word(byte)?;
+                self.end = Some(self.source.offset());
+                // an identifier followed by a colon is a key, even `null`
+                self.skip_ignored()?;
+                if self.syntax.unquoted_keys && self.source.peek()? == Some(b':') {
+                    Token::String(word)
+                } else {
+                    keyword(&word, self.syntax)?
+                }
+            }
+            b'-' | b'+'
+                if self.syntax.non_finite
+                    && (byte == b'-' || self.syntax.json5)
+                    && matches!(self.source.peek()?, Some(b'I' | b'N')) =>
+            {
+                let first = self.source.next_byte()?.expect("peeked above");
+                let Token::Number(n) = keyword(&self.source.word(first)?, self.syntax)? else {
+                    return Err(TokenizeError::UnrecognizedToken.into());
+                };
+                Token::Number(if byte == b'-' { -n } else { n })
+            }
+            b'0' | b'-' | b'+'
+                if self.syntax.hex_numbers && (byte != b'+' || self.syntax.json5) =>
+            {
+                self.number(byte)?
+            }
+            _ if !self.syntax.json5 => make_token(&mut self.source, byte)?,
+            b',' if self.after_value => {
+                let end = self.source.offset();
+                self.skip_ignored()?;
+                match self.source.peek()? {
+                    // a trailing comma is left out
+                    Some(b']') => self.close(Token::RightSquareBracket),
+                    Some(b'}') => self.close(Token::RightCurlyBracket),
+                    _ => {
+                        self.end = Some(end);
+                        Token::Comma
+                    }
+                }
+            }
+            byte => json5_token(&mut self.source, byte)?,
+        };
+        self.after_value = !matches!(
+            token,
+            Token::LeftCurlyBracket | Token::LeftSquareBracket | Token::Comma | Token::Colon
+        );
+        Ok(Some(token))
+    }
+
+    /// Lex a number that may be hexadecimal, `0x1F` or `-0x1F`, its sign or
+    /// leading zero already consumed
+    fn number(&mut self, first: u8) -> Result<Token<'a>, S::Error> {
+        let json5 = self.syntax.json5;
+        let source = &mut self.source;
+        if first != b'0' {
+            if source.peek()? != Some(b'0') {
+                // a sign needs a digit after it unless JSON5 allows more
+                return if json5 {
+                    json5_token(source, first)
+                } else {
+                    make_token(source, first)
+                };
+            }
+            source.bump();
+        }
+
+        let n = if matches!(source.peek()?, Some(b'x' | b'X')) {
+            source.bump();
+            hex_number(source)?
+        } else {
+            let Token::Number(n) = tokenize_float(source, b'0', json5)? else {
+                unreachable!("tokenize_float only lexes numbers");
+            };
+            n
+        };
+        Ok(Token::Number(if first == b'-' { -n } else { n }))
+    }
+
+    /// Skip the byte order mark starting the input, whose first byte is
+    /// peeked at but can be the start of another character too
+    fn skip_bom(&mut self) -> Result<(), S::Error> {
+        self.source.bump();
+        match unrecognized(&mut self.source, 0xef)? {
+            TokenizeError::CharNotRecognized('\u{feff}') => Ok(()),
+            err => Err(err.into()),
+        }
+    }
+
+    /// Consume the closing bracket peeked at, as the token lexed
+    fn close(&mut self, token: Token<'a>) -> Token<'a> {
+        self.start = self.source.offset();
+        self.source.bump();
+        token
+    }
+
+    /// Consume whitespace, and comments where the syntax allows them, up to
+    /// the next token
+    fn skip_ignored(&mut self) -> Result<(), S::Error> {
+        loop {
+            self.source.skip_whitespace()?;
+            if !self.syntax.comments || self.source.peek()? != Some(b'/') {
+                return Ok(());
+            }
+            let start = self.source.offset();
+            self.source.bump();
+            match self.source.next_byte()? {
+                Some(b'/') => {
+                    while self.source.peek()?.is_some_and(|byte| byte != b'\n') {
+                        self.source.bump();
+                    }
+                }
+                Some(b'*') => {
+                    let mut star = false;
+                    loop {
+                        match self.source.next_byte()? {
+                            None => return Err(TokenizeError::UnexpectedEof.into()),
+                            Some(b'/') if star => break,
+                            Some(byte) => star = byte == b'*',
+                        }
+                    }
+                }
+                _ => return Err(TokenizeError::CharNotRecognized('/').into()),
+            }
+            if let Some(comments) = &mut self.comments {
+                comments.push(start..self.source.offset());
+            }
+        }
+    }
+}
+
+impl<'a, S: Source<'a>> Iterator for TokenStream<'a, S> {
+    type Item = Result<Token<'a>, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.lex() {
+            Ok(token) => token.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Streaming tokenizer producing one [`Token`] at a time from the input,
+/// stopping after the first error
+pub struct Lexer<'a> {
+    input: &'a str,
+    tokens: TokenStream<'a, SliceSource<'a>>,
 }
 
-fn tokenize_literal(index: &mut usize, token: Token, input: &str) -> Result<Token, TokenizeError> {
-    let re = Regex::new(r"(?<name>null|false|true)").unwrap();
-    let Some(captures) = re.captures(input) else {
-        return Err(TokenizeError::UnfinishedLiteralValue);
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer::with_syntax(input, Syntax::default())
+    }
+
+    /// A lexer accepting the extensions to JSON enabled in `syntax`
+    pub fn with_syntax(input: &'a str, syntax: Syntax) -> Self {
+        Lexer {
+            input,
+            tokens: TokenStream::with_syntax(SliceSource::new(input.as_bytes()), syntax),
+        }
+    }
+
+    /// Keep the comments the lexer skips, for [`Lexer::take_comments`]
+    pub fn capture_comments(mut self) -> Self {
+        self.tokens.capture_comments();
+        self
+    }
+
+    /// The comments skipped since the last call, in input order. Always
+    /// empty unless the lexer was made to [capture](Lexer::capture_comments)
+    /// them.
+    pub fn take_comments(&mut self) -> Vec<Comment<'a>> {
+        self.tokens
+            .take_comments()
+            .into_iter()
+            .map(|span| Comment {
+                text: &self.input[span.clone()],
+                span,
+            })
+            .collect()
+    }
+}
+
+impl<'a> Lexer<'a> {
+    /// Byte range in the input of the last token produced, or of the text
+    /// that failed to lex
+    pub fn span(&self) -> Range<usize> {
+        self.tokens.span()
+    }
+
+    /// Turn the lexer into one producing each token with its span
+    pub fn spanned(self) -> SpannedTokens<'a> {
+        SpannedTokens { lexer: self }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokens.next()
+    }
+}
+
+/// A token and the byte range it covers in the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
+    pub span: Range<usize>,
+}
+
+/// A comment kept by a lexer, see [`Lexer::capture_comments`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment<'a> {
+    /// the comment as written, `//` or `/*` and `*/` included, the line
+    /// break ending a line comment not
+    pub text: &'a str,
+    pub span: Range<usize>,
+}
+
+impl<'a> Comment<'a> {
+    /// Whether this is a `/* */` comment rather than a `//` one
+    pub fn is_block(&self) -> bool {
+        self.text.starts_with("/*")
+    }
+
+    /// The text of the comment without its delimiters
+    pub fn body(&self) -> &'a str {
+        if self.is_block() {
+            &self.text[2..self.text.len() - 2]
+        } else {
+            &self.text[2..]
+        }
+    }
+}
+
+/// Tokens with their spans, see [`Lexer::spanned`]
+pub struct SpannedTokens<'a> {
+    lexer: Lexer<'a>,
+}
+
+impl<'a> Iterator for SpannedTokens<'a> {
+    type Item = Result<SpannedToken<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.lexer.next()?;
+        Some(token.map(|token| SpannedToken {
+            token,
+            span: self.lexer.span(),
+        }))
+    }
+}
+
+/// Lex the strict JSON token starting with `byte`, already consumed
+fn make_token<'a, S: Source<'a>>(source: &mut S, byte: u8) -> Result<Token<'a>, S::Error> {
+    let token = match byte {
+        b'{' => Token::LeftCurlyBracket,
+        b'}' => Token::RightCurlyBracket,
+        b'[' => Token::LeftSquareBracket,
+        b']' => Token::RightSquareBracket,
+        b':' => Token::Colon,
+        b',' => Token::Comma,
+        b'n' => tokenize_literal(source, "null", Token::Null)?,
+        b't' => tokenize_literal(source, "true", Token::True)?,
+        b'f' => tokenize_literal(source, "false", Token::False)?,
+        b'0'..=b'9' => tokenize_float(source, byte, false)?,
+        b'-' if matches!(source.peek()?, Some(b'0'..=b'9')) => tokenize_float(source, byte, false)?,
+        b'"' => Token::String(source.string(b'"')?),
+
+        byte => return Err(unrecognized(source, byte)?.into()),
     };
-    println!(">>> {:?}", &captures["name"]);
-    *index += &captures["name"].len() - 1;
+
     Ok(token)
 }
 
-fn tokenize_float(chars: &[char], index: &mut usize) -> Result<Token, TokenizeError> {
-    let mut unparsed_num = String::new();
-    let mut has_decimal = false;
-    let mut is_negative = false;
+/// Lex a token starting with `byte` as JSON5 allows, identifiers, trailing
+/// commas and single-quoted strings aside
+fn json5_token<'a, S: Source<'a>>(source: &mut S, byte: u8) -> Result<Token<'a>, S::Error> {
+    match byte {
+        b'"' => Ok(Token::String(json5_escapes(source.string(byte)?))),
+        b'+' | b'-' => match source.peek()? {
+            Some(next @ (b'0'..=b'9' | b'.')) => {
+                source.bump();
+                let Token::Number(n) = json5_number(source, next)? else {
+                    unreachable!("json5_number only lexes numbers");
+                };
+                Ok(Token::Number(if byte == b'-' { -n } else { n }))
+            }
+            _ => Err(unrecognized(source, byte)?.into()),
+        },
+        b'.' | b'0'..=b'9' => json5_number(source, byte),
+        byte => make_token(source, byte),
+    }
+}
+
+/// Lex an unsigned JSON5 number whose first byte is already consumed
+fn json5_number<'a, S: Source<'a>>(source: &mut S, first: u8) -> Result<Token<'a>, S::Error> {
+    match first {
+        b'.' if !matches!(source.peek()?, Some(b'0'..=b'9')) => {
+            Err(unrecognized(source, first)?.into())
+        }
+        _ => tokenize_float(source, first, true),
+    }
+}
 
-    while *index < chars.len() {
-        let ch = chars[*index];
-        match ch {
-            ch if ch.is_ascii_digit() => unparsed_num.push(ch),
-            ch if ch == '.' && !has_decimal => {
-                unparsed_num.push('.');
-                has_decimal = true;
+/// Lex the digits of a hexadecimal number, its `0x` already consumed
+fn hex_number<'a, S: Source<'a>>(source: &mut S) -> Result<f64, S::Error> {
+    let mut n = 0.0;
+    let mut digits = 0;
+    while let Some(byte) = source.peek()?
+        && let Some(digit) = char::from(byte).to_digit(16)
+    {
+        source.bump();
+        n = n * 16.0 + f64::from(digit);
+        digits += 1;
+    }
+    if digits == 0 {
+        return Err(invalid_number().into());
+    }
+    Ok(n)
+}
+
+/// Rewrite the escapes JSON5 has and JSON lacks as JSON ones, leaving the
+/// rest for [`unescape`](crate::parser::unescape). A `\x` escape becomes
+/// the start of a `\u` one, so bad hex digits are reported the same way.
+fn json5_escapes(raw: Cow<'_, str>) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return raw;
+    }
+
+    let mut output = String::with_capacity(raw.len() + 8);
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('v') => output.push_str("\\u000b"),
+            Some('0') => output.push_str("\\u0000"),
+            Some('x') => output.push_str("\\u00"),
+            // an escaped line break continues the string on the next line
+            Some('\r') => {
+                chars.next_if_eq(&'\n');
             }
-            ch if ch == '-' => is_negative = true,
-            _ => break,
+            Some('\n' | '\u{2028}' | '\u{2029}') => {}
+            Some(c) => {
+                output.push('\\');
+                output.push(c);
+            }
+            None => output.push('\\'),
         }
-        *index += 1;
+    }
+    Cow::Owned(output)
+}
+
+/// The literals an identifier outside a key can be, the non-finite ones last
+const KEYWORDS: [(&str, Token<'static>); 5] = [
+    ("true", Token::True),
+    ("false", Token::False),
+    ("null", Token::Null),
+    ("Infinity", Token::Number(f64::INFINITY)),
+    ("NaN", Token::Number(f64::NAN)),
+];
+
+/// The literal `word` is, or the error for a word that is none
+fn keyword<'a>(word: &str, syntax: Syntax) -> Result<Token<'a>, TokenizeError> {
+    let keywords = &KEYWORDS[..if syntax.non_finite { 5 } else { 3 }];
+    if let Some((_, token)) = keywords.iter().find(|(keyword, _)| *keyword == word) {
+        return Ok(token.clone());
+    }
+
+    let closest = keywords
+        .iter()
+        .filter(|_| word.len() <= MAX_WORD)
+        .map(|(keyword, _)| (edit_distance(word.as_bytes(), keyword.as_bytes()), *keyword))
+        .min_by_key(|(distance, _)| *distance);
+    match closest {
+        Some((distance, keyword)) if is_typo(distance, word.len()) => {
+            Err(TokenizeError::MisspelledLiteral(keyword))
+        }
+        _ if word.starts_with(['t', 'f', 'n']) => Err(TokenizeError::UnfinishedLiteralValue),
+        _ => Err(TokenizeError::CharNotRecognized(
+            word.chars().next().expect("identifiers aren't empty"),
+        )),
+    }
+}
+
+/// The error for a number with no digits where it needs some
+fn invalid_number() -> TokenizeError {
+    TokenizeError::ParseNumberError("".parse::<f64>().expect_err("no digits is no number"))
+}
+
+/// Words longer than this are never taken for a misspelled literal
+const MAX_WORD: usize = 8;
+
+/// `literal` is the whole literal, its first byte is already consumed.
+/// The rest of the word is read too, so a typo can be reported as one.
+fn tokenize_literal<'a, S: Source<'a>>(
+    source: &mut S,
+    literal: &'static str,
+    token: Token<'a>,
+) -> Result<Token<'a>, S::Error> {
+    let mut word = [0; MAX_WORD];
+    word[0] = literal.as_bytes()[0];
+    let mut len = 1;
+    while let Some(byte) = source.peek()?
+        && byte.is_ascii_alphanumeric()
+    {
+        source.bump();
+        if len < MAX_WORD {
+            word[len] = byte;
+        }
+        len += 1;
+    }
+
+    if len > MAX_WORD {
+        return Err(TokenizeError::UnfinishedLiteralValue.into());
+    }
+    let word = &word[..len];
+    if word == literal.as_bytes() {
+        Ok(token)
+    } else if is_typo(edit_distance(word, literal.as_bytes()), word.len()) {
+        Err(TokenizeError::MisspelledLiteral(literal).into())
+    } else {
+        Err(TokenizeError::UnfinishedLiteralValue.into())
+    }
+}
+
+/// Whether a word of `len` bytes `distance` edits away from a literal is
+/// likely a typo of it rather than a different word
+fn is_typo(distance: usize, len: usize) -> bool {
+    distance <= 2 && distance < len
+}
+
+/// The number of single byte insertions, deletions, substitutions and
+/// swaps of neighbours that turn `a` into `b`, for words of at most
+/// [`MAX_WORD`] bytes
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut d = [[0; MAX_WORD + 1]; MAX_WORD + 1];
+    for (i, row) in d.iter_mut().enumerate().take(a.len() + 1) {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate().take(b.len() + 1) {
+        *cell = j;
     }
 
-    match unparsed_num.parse() {
-        Ok(f) => {
-            if is_negative {
-                Ok(Token::Number(-1.0 * f))
-            } else {
-                Ok(Token::Number(f))
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
             }
         }
-        Err(err) => Err(TokenizeError::ParseNumberError(err)),
     }
+    d[a.len()][b.len()]
 }
 
-fn tokenize_string(chars: &[char], index: &mut usize) -> Result<Token, TokenizeError> {
-    let mut string = String::new();
-    let mut is_escaping = false;
+/// Powers of ten exactly representable as `f64`
+const POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Numbers at most this long are kept on the stack while they are lexed
+const SHORT_NUMBER: usize = 64;
+
+/// Lex a number without allocating. Numbers of at most 15 digits and no
+/// exponent are built from their digits directly: the digits and the power
+/// of ten both fit an `f64` exactly, so a single division gives the
+/// correctly rounded value. Anything else goes through the standard
+/// library's parser.
+///
+/// Unless `json5` is set the number must follow the grammar of RFC 8259, with
+/// no leading zeros and digits on both sides of a decimal point.
+fn tokenize_float<'a, S: Source<'a>>(
+    source: &mut S,
+    first: u8,
+    json5: bool,
+) -> Result<Token<'a>, S::Error> {
+    let mut short = [0; SHORT_NUMBER];
+    let mut long = Vec::new();
+    let mut len = 0;
+    let mut push = |byte: u8| {
+        if len < SHORT_NUMBER {
+            short[len] = byte;
+        } else {
+            if long.is_empty() {
+                long.extend_from_slice(&short);
+            }
+            long.push(byte);
+        }
+        len += 1;
+    };
 
+    let mut mantissa = 0u64;
+    let mut digits = 0;
+    let mut decimals = 0;
+    let mut has_decimal = false;
+    let mut byte = first;
     loop {
-        *index += 1;
-        if *index >= chars.len() {
-            return Err(TokenizeError::UnclosedQuotes);
+        match byte {
+            b'0'..=b'9' => {
+                // wrapping is harmless, long numbers don't use the mantissa
+                mantissa = mantissa
+                    .wrapping_mul(10)
+                    .wrapping_add(u64::from(byte - b'0'));
+                digits += 1;
+                decimals += usize::from(has_decimal);
+            }
+            b'.' => has_decimal = true,
+            _ => {}
         }
+        push(byte);
 
-        let ch = chars[*index];
-        match ch {
-            '"' if !is_escaping => break,
-            '\\' => is_escaping = !is_escaping,
-            _ => is_escaping = false,
+        match source.peek()? {
+            Some(next @ b'0'..=b'9') => byte = next,
+            Some(b'.') if !has_decimal => byte = b'.',
+            _ => break,
+        }
+        source.bump();
+    }
+
+    let mut has_exponent = false;
+    if let Some(e @ (b'e' | b'E')) = source.peek()? {
+        source.bump();
+        push(e);
+        has_exponent = true;
+        if let Some(sign @ (b'+' | b'-')) = source.peek()? {
+            source.bump();
+            push(sign);
+        }
+        while let Some(digit @ b'0'..=b'9') = source.peek()? {
+            source.bump();
+            push(digit);
         }
+    }
+
+    let text = if len <= SHORT_NUMBER {
+        &short[..len]
+    } else {
+        &long[..]
+    };
+    if !json5 && !is_strict_number(text) {
+        return Err(invalid_number().into());
+    }
 
-        string.push(ch);
+    if !has_exponent && digits <= 15 && decimals < POWERS_OF_TEN.len() {
+        let n = mantissa as f64 / POWERS_OF_TEN[decimals];
+        return Ok(Token::Number(if first == b'-' { -n } else { n }));
     }
 
-    Ok(Token::String(string))
+    // only ASCII digits, signs, `.` and `e` were pushed
+    let text = core::str::from_utf8(text).expect("numbers are ASCII");
+    match text.parse::<f64>() {
+        Ok(f) => Ok(Token::Number(f)),
+        Err(err) => Err(TokenizeError::ParseNumberError(err).into()),
+    }
+}
+
+/// Whether the integer and fraction of the number `text` are as RFC 8259 has
+/// them, the exponent being left to the standard library's parser
+fn is_strict_number(text: &[u8]) -> bool {
+    let text = text.strip_prefix(b"-").unwrap_or(text);
+    let int = text.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    if int == 0 || (int > 1 && text[0] == b'0') {
+        return false;
+    }
+    match text[int..].strip_prefix(b".") {
+        Some(fraction) => fraction.first().is_some_and(u8::is_ascii_digit),
+        None => true,
+    }
+}
+
+/// Error for a byte that can't start a token, naming the whole character it
+/// is the first byte of
+fn unrecognized<'a, S: Source<'a>>(source: &mut S, first: u8) -> Result<TokenizeError, S::Error> {
+    let offset = source.offset() - 1;
+    let len = match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Ok(TokenizeError::InvalidUtf8(offset)),
+    };
+
+    let mut buf = [first, 0, 0, 0];
+    for slot in &mut buf[1..len] {
+        match source.next_byte()? {
+            Some(byte) => *slot = byte,
+            None => return Ok(TokenizeError::InvalidUtf8(offset)),
+        }
+    }
+
+    Ok(core::str::from_utf8(&buf[..len])
+        .ok()
+        .and_then(|s| s.chars().next())
+        .map_or(
+            TokenizeError::InvalidUtf8(offset),
+            TokenizeError::CharNotRecognized,
+        ))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Token, tokenize};
+    use super::{Comment, Lexer, SpannedToken, Syntax, Token, TokenizeError};
+    use crate::prelude::*;
+
+    fn tokenize(input: &str) -> Result<Vec<Token<'_>>, TokenizeError> {
+        Lexer::new(input).collect()
+    }
+
+    #[test]
+    fn lexer_stops_after_error() {
+        let mut lexer = Lexer::new("[?, 1]");
+
+        assert_eq!(lexer.next(), Some(Ok(Token::LeftSquareBracket)));
+        assert_eq!(
+            lexer.next(),
+            Some(Err(TokenizeError::CharNotRecognized('?')))
+        );
+        assert_eq!(lexer.next(), None);
+    }
 
     #[test]
     fn test_broken_literal_tokens_return_error() {
         let bad_null = String::from("nolll");
-        assert!(tokenize(bad_null).is_err());
+        assert!(tokenize(&bad_null).is_err());
+    }
+
+    #[test]
+    fn misspelled_literals_suggest_the_literal() {
+        for (input, literal) in [("ture", "true"), ("flase", "false"), ("nul", "null")] {
+            assert_eq!(
+                tokenize(input),
+                Err(TokenizeError::MisspelledLiteral(literal)),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            tokenize("[nulll]"),
+            Err(TokenizeError::MisspelledLiteral("null"))
+        );
+        assert_eq!(tokenize("fizz"), Err(TokenizeError::UnfinishedLiteralValue));
+        assert_eq!(
+            tokenize("truetruetrue"),
+            Err(TokenizeError::UnfinishedLiteralValue)
+        );
+    }
+
+    #[test]
+    fn json5_tokens() {
+        let lex = |input| Lexer::with_syntax(input, Syntax::json5()).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            lex("{a_1: +0x1f, $b : .5, 'c\\'\"': -Infinity, null: [5.,],}").unwrap(),
+            vec![
+                Token::LeftCurlyBracket,
+                Token::String("a_1".into()),
+                Token::Colon,
+                Token::Number(31.0),
+                Token::Comma,
+                Token::String("$b".into()),
+                Token::Colon,
+                Token::Number(0.5),
+                Token::Comma,
+                Token::String("c\\'\"".into()),
+                Token::Colon,
+                Token::Number(f64::NEG_INFINITY),
+                Token::Comma,
+                Token::String("null".into()),
+                Token::Colon,
+                Token::LeftSquareBracket,
+                Token::Number(5.0),
+                Token::RightSquareBracket,
+                Token::RightCurlyBracket,
+            ]
+        );
+        assert_eq!(
+            lex("'\\x41\\v\\0\\\n'").unwrap(),
+            vec![Token::String("\\u0041\\u000b\\u0000".into())]
+        );
+        assert!(matches!(lex("-NaN").unwrap()[..], [Token::Number(n)] if n.is_nan()));
+        assert_eq!(lex("[,]").unwrap()[1], Token::Comma);
+        assert_eq!(lex("[Nan]"), Err(TokenizeError::MisspelledLiteral("NaN")));
+        assert_eq!(lex("[abc]"), Err(TokenizeError::CharNotRecognized('a')));
+        assert_eq!(lex("0x"), Err(super::invalid_number()));
+        assert_eq!(lex("[1] /* open"), Err(TokenizeError::UnexpectedEof));
+        assert!(Lexer::new("{a: 1}").collect::<Result<Vec<_>, _>>().is_err());
+    }
+
+    #[test]
+    fn comments_are_captured_on_request() {
+        let syntax = Syntax::jsonc();
+        let mut lexer = Lexer::with_syntax("// a\n[1, /* b */ 2]//", syntax).capture_comments();
+
+        assert_eq!(lexer.next(), Some(Ok(Token::LeftSquareBracket)));
+        assert_eq!(
+            lexer.take_comments(),
+            [Comment {
+                text: "// a",
+                span: 0..4
+            }]
+        );
+        assert_eq!(lexer.by_ref().count(), 4);
+        let comments = lexer.take_comments();
+        assert_eq!(
+            comments.iter().map(Comment::body).collect::<Vec<_>>(),
+            [" b ", ""]
+        );
+        assert!(comments[0].is_block() && !comments[1].is_block());
+        assert!(
+            Lexer::with_syntax("[] // c", syntax)
+                .take_comments()
+                .is_empty()
+        );
+        assert_eq!(
+            Lexer::with_syntax("{'a': 1}", syntax).nth(1),
+            Some(Err(TokenizeError::CharNotRecognized('\'')))
+        );
+    }
+
+    #[test]
+    fn single_quotes_on_their_own() {
+        let syntax = Syntax {
+            single_quotes: true,
+            ..Syntax::default()
+        };
+        let lex = |input| Lexer::with_syntax(input, syntax).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            lex(r#"{'a': 'it\'s "x"'}"#).unwrap()[3],
+            Token::String(r#"it\'s "x""#.into())
+        );
+        assert_eq!(lex("'\\v'").unwrap(), [Token::String("\\v".into())]);
+        assert_eq!(lex("{a: 1}"), Err(TokenizeError::CharNotRecognized('a')));
+        let json5 = Syntax {
+            single_quotes: false,
+            ..Syntax::json5()
+        };
+        assert_eq!(
+            Lexer::with_syntax("['a']", json5).nth(1),
+            Some(Err(TokenizeError::CharNotRecognized('\'')))
+        );
+    }
+
+    #[test]
+    fn unquoted_keys_on_their_own() {
+        let syntax = Syntax {
+            unquoted_keys: true,
+            ..Syntax::default()
+        };
+        let lex = |input| Lexer::with_syntax(input, syntax).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            lex("{$id: true, null : 1}").map(|tokens| tokens[1].clone()),
+            Ok(Token::String("$id".into()))
+        );
+        assert_eq!(
+            lex("{a: nul}"),
+            Err(TokenizeError::MisspelledLiteral("null"))
+        );
+        assert_eq!(lex("[NaN]"), Err(TokenizeError::CharNotRecognized('N')));
+        assert_eq!(lex("{a: 1,}").unwrap()[4], Token::Comma);
+    }
+
+    #[test]
+    fn non_finite_numbers_on_their_own() {
+        let syntax = Syntax {
+            non_finite: true,
+            ..Syntax::default()
+        };
+        let lex = |input| Lexer::with_syntax(input, syntax).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            lex("[Infinity, -Infinity]").unwrap()[1..4],
+            [
+                Token::Number(f64::INFINITY),
+                Token::Comma,
+                Token::Number(f64::NEG_INFINITY)
+            ]
+        );
+        assert!(matches!(lex("NaN").unwrap()[..], [Token::Number(n)] if n.is_nan()));
+        assert_eq!(lex("-Nope"), Err(TokenizeError::CharNotRecognized('N')));
+        assert_eq!(lex("+Infinity"), Err(TokenizeError::CharNotRecognized('+')));
+        assert_eq!(lex("{a: 1}"), Err(TokenizeError::CharNotRecognized('a')));
+    }
+
+    #[test]
+    fn hex_numbers_on_their_own() {
+        let syntax = Syntax {
+            hex_numbers: true,
+            ..Syntax::default()
+        };
+        let lex = |input| Lexer::with_syntax(input, syntax).collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            lex("[0xFF, -0x1f, 0, -0.5, -2]").unwrap(),
+            lex("[255, -31, 0, -0.5, -2]").unwrap()
+        );
+        assert_eq!(lex("0x"), Err(super::invalid_number()));
+        assert_eq!(lex("-0x"), Err(super::invalid_number()));
+        assert_eq!(lex("[-]"), Err(TokenizeError::CharNotRecognized('-')));
+        assert!(lex("-.5").is_err());
+        assert!(lex("+0x1").is_err());
+        let json5 = Syntax {
+            hex_numbers: false,
+            ..Syntax::json5()
+        };
+        assert!(Lexer::with_syntax("0x1", json5).nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn leading_byte_order_mark() {
+        let lex = |input, syntax| Lexer::with_syntax(input, syntax).spanned().next();
+
+        assert_eq!(
+            lex("\u{feff}[]", Syntax::default()),
+            Some(Ok(SpannedToken {
+                token: Token::LeftSquareBracket,
+                span: 3..4
+            }))
+        );
+        let strict = Syntax {
+            reject_bom: true,
+            ..Syntax::default()
+        };
+        assert_eq!(
+            lex("\u{feff}[]", strict),
+            Some(Err(TokenizeError::CharNotRecognized('\u{feff}')))
+        );
+        assert_eq!(
+            lex("\u{fec0}", Syntax::default()),
+            Some(Err(TokenizeError::CharNotRecognized('\u{fec0}')))
+        );
+        assert!(Lexer::new("[1, \u{feff}2]").nth(3).unwrap().is_err());
+    }
+
+    #[test]
+    fn spans_skip_comments_and_trailing_commas() {
+        let spans: Vec<_> = Lexer::with_syntax("[1, /* x */ ]  // y", Syntax::json5())
+            .spanned()
+            .map(|token| token.unwrap().span)
+            .collect();
+
+        assert_eq!(spans, [0..1, 1..2, 12..13]);
+    }
+
+    #[test]
+    fn strings_end_at_the_first_unescaped_quote() {
+        assert_eq!(
+            tokenize(r#""a\"b" "c\\" "\\\"""#).unwrap(),
+            vec![
+                Token::String(r#"a\"b"#.into()),
+                Token::String(r#"c\\"#.into()),
+                Token::String(r#"\\\""#.into()),
+            ]
+        );
+        assert_eq!(tokenize(r#""a\""#), Err(TokenizeError::UnclosedQuotes));
+        assert_eq!(tokenize(r#""a\"#), Err(TokenizeError::UnclosedQuotes));
+    }
+
+    #[test]
+    fn numbers_match_the_standard_parser() {
+        let long = format!("{}.5", "9".repeat(70));
+        let inputs = [
+            "0",
+            "-0",
+            "0.1",
+            "0.3",
+            "-123.456",
+            "123456789012345",
+            "9007199254740993",
+            "0.0000000000000000000000001",
+            "3.14159265358979323846",
+            "1e21",
+            "-1.5E-7",
+            "2e+3",
+            &long,
+        ];
+
+        for input in inputs {
+            let Ok(tokens) = tokenize(input) else {
+                panic!("{input} should lex");
+            };
+            let Token::Number(n) = tokens[0] else {
+                panic!("{input} should be a number");
+            };
+            let expected: f64 = input.parse().unwrap();
+            assert_eq!(n.to_bits(), expected.to_bits(), "{input}");
+        }
+    }
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        for input in [
+            "01", "000120", "-01.0", "1.", "1.e5", "-0.", "00", "1e", "1e+",
+        ] {
+            assert_eq!(
+                tokenize(input).map_err(|err| err.code()),
+                Err("E004_INVALID_NUMBER"),
+                "{input}"
+            );
+        }
+        let json5 =
+            |input| Lexer::with_syntax(input, Syntax::json5()).collect::<Result<Vec<_>, _>>();
+        assert_eq!(json5("1."), Ok(vec![Token::Number(1.0)]));
+        assert_eq!(json5("1.e1"), Ok(vec![Token::Number(10.0)]));
+        assert_eq!(json5(".5"), Ok(vec![Token::Number(0.5)]));
     }
 
     macro_rules! test_tokens {
         ($name:ident, $token_name:expr, $expected:expr) => {
             #[test]
             fn $name() {
-                assert_eq!(tokenize($token_name).unwrap(), $expected);
+                assert_eq!(tokenize(&$token_name).unwrap(), $expected);
             }
         };
     }
@@ -189,6 +1293,30 @@ mod tests {
         String::from("true,"),
         vec![Token::True, Token::Comma]
     );
+    test_tokens!(
+        test_literals_in_array,
+        String::from("[false, true, null]"),
+        vec![
+            Token::LeftSquareBracket,
+            Token::False,
+            Token::Comma,
+            Token::True,
+            Token::Comma,
+            Token::Null,
+            Token::RightSquareBracket,
+        ]
+    );
+    test_tokens!(
+        test_number_followed_by_punctuation,
+        String::from("[1,2] "),
+        vec![
+            Token::LeftSquareBracket,
+            Token::Number(1.0),
+            Token::Comma,
+            Token::Number(2.0),
+            Token::RightSquareBracket,
+        ]
+    );
     test_tokens!(
         test_integer,
         String::from("123"),
@@ -207,6 +1335,33 @@ mod tests {
     test_tokens!(
         test_string,
         String::from("\"gabe\""),
-        vec![Token::String(String::from("gabe"))]
+        vec![Token::String("gabe".into())]
     );
+
+    #[test]
+    fn spans_cover_each_token() {
+        let input = " {\"é\\n\": [-1.5, null]}";
+        let spans: Vec<_> = Lexer::new(input)
+            .spanned()
+            .map(|token| {
+                let SpannedToken { token, span } = token.unwrap();
+                (token, &input[span])
+            })
+            .collect();
+
+        assert_eq!(
+            spans,
+            [
+                (Token::LeftCurlyBracket, "{"),
+                (Token::String("é\\n".into()), "\"é\\n\""),
+                (Token::Colon, ":"),
+                (Token::LeftSquareBracket, "["),
+                (Token::Number(-1.5), "-1.5"),
+                (Token::Comma, ","),
+                (Token::Null, "null"),
+                (Token::RightSquareBracket, "]"),
+                (Token::RightCurlyBracket, "}"),
+            ]
+        );
+    }
 }
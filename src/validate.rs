@@ -0,0 +1,193 @@
+// Checking that text is valid JSON without building anything from it
+
+use core::iter::Peekable;
+
+use super::parser::{TokenParseError, unicode_escape};
+use super::tokenize::{Lexer, Token};
+use super::{DEFAULT_MAX_DEPTH, JsonError};
+
+/// Check that `input` is a single valid JSON document, failing with the same
+/// error [`parse`](crate::parse) would
+///
+/// Nothing is allocated: strings are checked in place, and the kind of each
+/// open container takes one bit, the default depth limit bounding how many
+/// there can be. Use it to reject malformed input before paying for a
+/// [`Value`](crate::Value):
+///
+/// ```
+/// use parser_json_rs::validate;
+///
+/// assert!(validate(r#"{"a": [1, "é"]}"#).is_ok());
+/// assert!(validate(r#"{"a": [1, }"#).is_err());
+/// ```
+pub fn validate(input: &str) -> Result<(), JsonError> {
+    let mut tokens = Lexer::new(input).peekable();
+    if tokens.peek().is_none() {
+        return Err(TokenParseError::EmptyInput.into());
+    }
+
+    // bit `i` is set when the container open at depth `i` is an object
+    let mut objects: u128 = 0;
+    let mut depth = 0;
+
+    loop {
+        match next_token(&mut tokens)? {
+            Token::Null | Token::False | Token::True | Token::Number(_) => {}
+            Token::String(raw) => check_escapes(&raw)?,
+            Token::LeftSquareBracket | Token::LeftCurlyBracket if depth == DEFAULT_MAX_DEPTH => {
+                return Err(TokenParseError::DepthLimitExceeded.into());
+            }
+            Token::LeftSquareBracket => {
+                if !next_if(&mut tokens, &Token::RightSquareBracket) {
+                    objects &= !(1 << depth);
+                    depth += 1;
+                    continue;
+                }
+            }
+            Token::LeftCurlyBracket => {
+                if !next_if(&mut tokens, &Token::RightCurlyBracket) {
+                    check_key(&mut tokens)?;
+                    objects |= 1 << depth;
+                    depth += 1;
+                    continue;
+                }
+            }
+            _ => return Err(TokenParseError::ExpectedValue.into()),
+        }
+
+        // the value is complete, close every container that ends after it
+        loop {
+            if depth == 0 {
+                return match tokens.next() {
+                    None => Ok(()),
+                    Some(_) => Err(TokenParseError::TrailingTokens.into()),
+                };
+            }
+            let in_object = objects & (1 << (depth - 1)) != 0;
+            match (next_token(&mut tokens)?, in_object) {
+                (Token::Comma, false) => break,
+                (Token::Comma, true) => {
+                    check_key(&mut tokens)?;
+                    break;
+                }
+                (Token::RightSquareBracket, false) | (Token::RightCurlyBracket, true) => {
+                    depth -= 1;
+                }
+                _ => return Err(TokenParseError::ExpectedComma.into()),
+            }
+        }
+    }
+}
+
+fn next_token<'a>(tokens: &mut Peekable<Lexer<'a>>) -> Result<Token<'a>, JsonError> {
+    Ok(tokens.next().ok_or(TokenParseError::UnexpectedEnd)??)
+}
+
+/// Consume the next token if it is `expected`
+fn next_if(tokens: &mut Peekable<Lexer<'_>>, expected: &Token) -> bool {
+    tokens
+        .next_if(|token| matches!(token, Ok(token) if token == expected))
+        .is_some()
+}
+
+/// Check the `"key":` of an object member
+fn check_key(tokens: &mut Peekable<Lexer<'_>>) -> Result<(), JsonError> {
+    let Token::String(raw) = next_token(tokens)? else {
+        return Err(TokenParseError::ExpectedProperty.into());
+    };
+    if next_token(tokens)? != Token::Colon {
+        return Err(TokenParseError::ExpectedColon.into());
+    }
+    Ok(check_escapes(&raw)?)
+}
+
+/// Check the escape sequences of the raw contents of a string token the way
+/// unescaping them would, without writing the result anywhere
+fn check_escapes(raw: &str) -> Result<(), TokenParseError> {
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' || chars.next() != Some('u') {
+            continue;
+        }
+        unicode_escape(&mut chars)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use crate::prelude::*;
+    use crate::{DEFAULT_MAX_DEPTH, parse};
+
+    #[test]
+    fn validate_agrees_with_parse() {
+        let deepest = format!(
+            "{}{}",
+            "[".repeat(DEFAULT_MAX_DEPTH),
+            "]".repeat(DEFAULT_MAX_DEPTH)
+        );
+        let too_deep = format!("[{deepest}]");
+        let inputs = [
+            r#"{"a": [1, 2.5, {"b": null}], "c": "d\"e", "f": [], "g": {}}"#,
+            r#"[true, false, "é\n"]"#,
+            " 1 ",
+            &deepest,
+            &too_deep,
+            "",
+            "[1, 2",
+            "[1 2]",
+            "[1,]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{1: 2}"#,
+            "[1}",
+            r#"{"a": 1]"#,
+            "[1] 2",
+            "[1] x",
+            ":",
+            r#""\u12""#,
+            r#""\u12g4""#,
+            r#"{"\ud800": 1}"#,
+            "[tru]",
+        ];
+
+        for input in inputs {
+            assert_eq!(validate(input), parse(input).map(|_| ()), "{input}");
+        }
+    }
+
+    #[test]
+    fn validate_agrees_with_parse_on_malformed_scalars() {
+        let malformed = [
+            "01",
+            "[000120]",
+            "-01.0",
+            "[1.]",
+            "1.e5",
+            "[-]",
+            "-.5",
+            "1e",
+            r#""\""#,
+            r#""\u00""#,
+        ];
+        for input in malformed {
+            assert!(parse(input).is_err(), "{input}");
+            assert_eq!(validate(input), parse(input).map(|_| ()), "{input}");
+        }
+
+        // unknown escapes and raw control characters in strings are let
+        // through by both alike
+        let lenient = [
+            r#""\q""#,
+            r#"["a\x41"]"#,
+            r#"{"\'": 1}"#,
+            "[\"a\tb\"]",
+            "\"a\nb\"",
+            "{\"\u{1}\": 1}",
+        ];
+        for input in lenient {
+            assert_eq!(validate(input), parse(input).map(|_| ()), "{input}");
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Walking a [`Value`] tree with callbacks for each kind of node, any of
+//! which can skip the subtree below it or end the walk, and rebuilding one
+//! with [`Value::transform`]
+
+use super::Map;
+use super::Value;
+use super::pointer::escape_token;
+use super::prelude::*;
+
+/// What [`walk`] does after a callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Go on, into the node's children if it has any
+    Continue,
+    /// Go on, but not into the children of this node
+    SkipChildren,
+    /// End the walk
+    Stop,
+}
+
+/// Callbacks for [`walk`], all of which continue by default
+///
+/// Objects call `visit_object`, then `visit_key` before each member's value,
+/// then `end_object`; arrays call `visit_array`, each element, then
+/// `end_array`. Skipping the children of an object or array also skips its
+/// `end_` callback, and skipping from `visit_key` skips that member's value.
+#[allow(unused_variables)]
+pub trait Visit {
+    fn visit_object(&mut self, members: &Map<String, Value>) -> Control {
+        Control::Continue
+    }
+
+    fn visit_key(&mut self, key: &str) -> Control {
+        Control::Continue
+    }
+
+    fn end_object(&mut self) -> Control {
+        Control::Continue
+    }
+
+    fn visit_array(&mut self, elements: &[Value]) -> Control {
+        Control::Continue
+    }
+
+    fn end_array(&mut self) -> Control {
+        Control::Continue
+    }
+
+    fn visit_string(&mut self, s: &str) -> Control {
+        Control::Continue
+    }
+
+    fn visit_number(&mut self, n: f64) -> Control {
+        Control::Continue
+    }
+
+    fn visit_boolean(&mut self, b: bool) -> Control {
+        Control::Continue
+    }
+
+    fn visit_null(&mut self) -> Control {
+        Control::Continue
+    }
+}
+
+/// Walk `value` depth first, visiting object members in no particular order,
+/// and return [`Control::Stop`] if a callback ended the walk
+pub fn walk<V: Visit + ?Sized>(value: &Value, visitor: &mut V) -> Control {
+    let control = match value {
+        Value::Null => visitor.visit_null(),
+        Value::Boolean(b) => visitor.visit_boolean(*b),
+        Value::Number(n) => visitor.visit_number(*n),
+        Value::String(s) => visitor.visit_string(s),
+        Value::Array(elements) => match visitor.visit_array(elements) {
+            Control::Continue => {
+                for element in elements {
+                    if walk(element, visitor) == Control::Stop {
+                        return Control::Stop;
+                    }
+                }
+                visitor.end_array()
+            }
+            control => control,
+        },
+        Value::Object(members) => match visitor.visit_object(members) {
+            Control::Continue => {
+                for (key, member) in members {
+                    let control = match visitor.visit_key(key) {
+                        Control::Continue => walk(member, visitor),
+                        control => control,
+                    };
+                    if control == Control::Stop {
+                        return Control::Stop;
+                    }
+                }
+                visitor.end_object()
+            }
+            control => control,
+        },
+    };
+    match control {
+        Control::Stop => Control::Stop,
+        _ => Control::Continue,
+    }
+}
+
+impl Value {
+    /// Rebuild the tree bottom up, replacing every node by what `f` returns
+    /// for it and its JSON Pointer
+    ///
+    /// Arrays and objects are passed to `f` after their children have been
+    /// transformed, so `f` can also rename an object's keys.
+    pub fn transform<F: FnMut(&str, Value) -> Value>(self, mut f: F) -> Value {
+        let mut pointer = String::new();
+        transform_at(self, &mut pointer, &mut f)
+    }
+}
+
+fn transform_at<F: FnMut(&str, Value) -> Value>(
+    value: Value,
+    pointer: &mut String,
+    f: &mut F,
+) -> Value {
+    let len = pointer.len();
+    let value = match value {
+        Value::Array(elements) => Value::Array(
+            elements
+                .into_iter()
+                .enumerate()
+                .map(|(i, element)| {
+                    pointer.push_str(&format!("/{i}"));
+                    let element = transform_at(element, pointer, f);
+                    pointer.truncate(len);
+                    element
+                })
+                .collect(),
+        ),
+        Value::Object(members) => Value::Object(
+            members
+                .into_iter()
+                .map(|(key, member)| {
+                    pointer.push('/');
+                    pointer.push_str(&escape_token(&key));
+                    let member = transform_at(member, pointer, f);
+                    pointer.truncate(len);
+                    (key, member)
+                })
+                .collect(),
+        ),
+        value => value,
+    };
+    f(pointer, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Map;
+    use crate::prelude::*;
+
+    use super::{Control, Visit, walk};
+    use crate::Value;
+
+    /// Counts strings, skipping members named `private`
+    #[derive(Default)]
+    struct Strings {
+        count: usize,
+        arrays: usize,
+    }
+
+    impl Visit for Strings {
+        fn visit_key(&mut self, key: &str) -> Control {
+            if key == "private" {
+                Control::SkipChildren
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn visit_array(&mut self, _: &[Value]) -> Control {
+            self.arrays += 1;
+            Control::Continue
+        }
+
+        fn visit_string(&mut self, _: &str) -> Control {
+            self.count += 1;
+            Control::Continue
+        }
+    }
+
+    #[test]
+    fn walk_skips_subtrees() {
+        let value = json!({"a": ["x", {"b": "y"}], "private": ["z", "z"], "n": 1});
+        let mut strings = Strings::default();
+
+        assert_eq!(walk(&value, &mut strings), Control::Continue);
+        assert_eq!(strings.count, 2);
+        assert_eq!(strings.arrays, 1);
+    }
+
+    /// Finds the first object with an `id`, without looking inside it
+    #[derive(Default)]
+    struct FirstWithId {
+        found: Option<Value>,
+        objects: usize,
+    }
+
+    impl Visit for FirstWithId {
+        fn visit_object(&mut self, members: &Map<String, Value>) -> Control {
+            self.objects += 1;
+            match members.get("id") {
+                Some(id) => {
+                    self.found = Some(id.clone());
+                    Control::Stop
+                }
+                None => Control::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn walk_stops_early() {
+        let value = json!([[{"id": 1, "child": {"id": 2}}], {"id": 3}]);
+        let mut finder = FirstWithId::default();
+
+        assert_eq!(walk(&value, &mut finder), Control::Stop);
+        assert_eq!(finder.found, Some(json!(1)));
+        assert_eq!(finder.objects, 1);
+    }
+
+    #[test]
+    fn transform_rounds_numbers_and_renames_keys() {
+        let value = json!({"Price": 1.2345, "Items": [{"Weight": 0.005}]});
+        let mut pointers = Vec::new();
+        let value = value.transform(|pointer, value| {
+            pointers.push(pointer.to_owned());
+            match value {
+                Value::Number(n) => Value::Number((n * 100.0).round() / 100.0),
+                Value::Object(members) => members
+                    .into_iter()
+                    .map(|(key, member)| (key.to_lowercase(), member))
+                    .collect(),
+                value => value,
+            }
+        });
+
+        assert_eq!(value, json!({"price": 1.23, "items": [{"weight": 0.01}]}));
+        pointers.sort();
+        assert_eq!(
+            pointers,
+            ["", "/Items", "/Items/0", "/Items/0/Weight", "/Price"]
+        );
+    }
+}